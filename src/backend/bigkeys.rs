@@ -0,0 +1,147 @@
+use super::{Backend, KeySnapshot, KeyType};
+use std::{thread, time::Duration};
+
+// keys summarized between two pauses of a scan
+const SCAN_BATCH: usize = 256;
+// pause between two batches, leaves the shards to the clients
+const SCAN_PAUSE: Duration = Duration::from_millis(1);
+
+/// Progress of a keyspace scan for the largest keys, see [`Backend::start_bigkeys_scan`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BigKeysReport {
+    pub running: bool,
+    pub scanned: usize,
+    // one entry per type seen so far, in KeyType::ALL order
+    pub types: Vec<TypeStats>,
+}
+
+/// Keys of one type found by a scan so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeStats {
+    pub kind: KeyType,
+    pub keys: usize,
+    // sum of the element counts, as in KeySnapshot::len
+    pub total_len: usize,
+    pub total_size: usize,
+    // the key with the largest estimated size
+    pub biggest: KeySnapshot,
+}
+
+impl BigKeysReport {
+    fn add(&mut self, snapshot: KeySnapshot) {
+        self.scanned += 1;
+        match self.types.iter_mut().find(|t| t.kind == snapshot.kind) {
+            Some(stats) => {
+                stats.keys += 1;
+                stats.total_len += snapshot.len;
+                stats.total_size += snapshot.size;
+                if snapshot.size > stats.biggest.size {
+                    stats.biggest = snapshot;
+                }
+            }
+            None => {
+                self.types.push(TypeStats {
+                    kind: snapshot.kind,
+                    keys: 1,
+                    total_len: snapshot.len,
+                    total_size: snapshot.size,
+                    biggest: snapshot,
+                });
+                let order = |kind| KeyType::ALL.iter().position(|k| *k == kind);
+                self.types.sort_by_key(|t| order(t.kind));
+            }
+        }
+    }
+}
+
+impl Backend {
+    /// Scan the keyspace for the largest key of each type in a background thread.
+    ///
+    /// The scan pauses every few hundred keys so that it does not compete with clients for
+    /// the shards. Returns false if a scan is already running.
+    pub fn start_bigkeys_scan(&self) -> bool {
+        {
+            let mut report = self.bigkeys.lock().unwrap();
+            if report.running {
+                return false;
+            }
+            *report = BigKeysReport {
+                running: true,
+                ..Default::default()
+            };
+        }
+
+        let backend = self.clone();
+        thread::spawn(move || {
+            let mut report = BigKeysReport {
+                running: true,
+                ..Default::default()
+            };
+            for (i, snapshot) in backend.snapshot_iter().enumerate() {
+                report.add(snapshot);
+                if (i + 1) % SCAN_BATCH == 0 {
+                    *backend.bigkeys.lock().unwrap() = report.clone();
+                    thread::sleep(SCAN_PAUSE);
+                }
+            }
+            report.running = false;
+            *backend.bigkeys.lock().unwrap() = report;
+        });
+        true
+    }
+
+    /// Results of the running or last finished scan.
+    pub fn bigkeys_report(&self) -> BigKeysReport {
+        self.bigkeys.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn wait_for_scan(backend: &Backend) -> BigKeysReport {
+        let start = Instant::now();
+        loop {
+            let report = backend.bigkeys_report();
+            if !report.running {
+                return report;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "scan timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_bigkeys_scan() {
+        let backend = Backend::new();
+        for i in 0..SCAN_BATCH * 2 {
+            backend.set(format!("s{}", i), "v");
+        }
+        backend.set("big".to_string(), vec![b'x'; 1000]);
+        backend.sadd("set".to_string(), "a".to_string()).unwrap();
+        backend.sadd("set".to_string(), "b".to_string()).unwrap();
+
+        assert!(backend.start_bigkeys_scan());
+        let report = wait_for_scan(&backend);
+        assert_eq!(report.scanned, SCAN_BATCH * 2 + 2);
+        assert_eq!(report.types.len(), 2);
+
+        let strings = &report.types[0];
+        assert_eq!(strings.kind, KeyType::String);
+        assert_eq!(strings.keys, SCAN_BATCH * 2 + 1);
+        assert_eq!(strings.total_len, SCAN_BATCH * 2 + 1000);
+        assert_eq!(strings.biggest.key, "big");
+        assert_eq!(strings.biggest.len, 1000);
+
+        let sets = &report.types[1];
+        assert_eq!(sets.kind, KeyType::Set);
+        assert_eq!(sets.biggest.key, "set");
+        assert_eq!(sets.biggest.len, 2);
+
+        // a new scan starts over
+        assert!(backend.start_bigkeys_scan());
+        assert_eq!(wait_for_scan(&backend), report);
+    }
+}
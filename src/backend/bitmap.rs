@@ -0,0 +1,373 @@
+use super::{Backend, ChangeEvent, KeyType, Value};
+use bytes::Bytes;
+use dashmap::mapref::entry::Entry;
+
+/// Largest bit offset of a bitmap, bitmaps are capped at 512MB like redis strings.
+pub const MAX_BIT_OFFSET: u64 = (512 * 1024 * 1024 * 8) - 1;
+
+/// Unit of the range of BITCOUNT and BITPOS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitUnit {
+    Byte,
+    Bit,
+}
+
+/// Bitwise operation of BITOP.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitwiseOp {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+impl Backend {
+    /// Set or clear the bit at an offset of a string, growing it with zero bytes as needed.
+    ///
+    /// Bits are numbered from the most significant bit of the first byte. Returns the previous
+    /// value of the bit, or None if the key holds a value that is not a string.
+    pub fn setbit(&self, key: String, offset: u64, bit: bool) -> Option<bool> {
+        self.access_key(&key);
+        let (byte, mask) = ((offset / 8) as usize, 0x80u8 >> (offset % 8));
+        let old = match self.keyspace.entry(key.clone()) {
+            Entry::Occupied(mut e) => {
+                let Value::String(value) = e.get_mut() else {
+                    return None;
+                };
+                // the bytes are not copied unless a reader still shares them
+                let mut bytes = Vec::from(std::mem::take(value));
+                let before = bytes.len();
+                if bytes.len() <= byte {
+                    bytes.resize(byte + 1, 0);
+                }
+                let old = bytes[byte] & mask != 0;
+                if bit {
+                    bytes[byte] |= mask;
+                } else {
+                    bytes[byte] &= !mask;
+                }
+                self.account_resize(KeyType::String, before, bytes.len());
+                *value = bytes.into();
+                old
+            }
+            Entry::Vacant(e) => {
+                let mut bytes = vec![0; byte + 1];
+                if bit {
+                    bytes[byte] |= mask;
+                }
+                let value = Value::String(bytes.into());
+                self.created(e.key(), &value);
+                e.insert(value);
+                false
+            }
+        };
+        // only copy the whole value out if anyone is listening
+        self.notify(|| ChangeEvent::SetString {
+            value: self
+                .typed::<Bytes>(&key)
+                .ok()
+                .flatten()
+                .map(|v| v.clone())
+                .unwrap_or_default(),
+            key,
+        });
+        Some(old)
+    }
+
+    /// The bit at an offset of a string, bits past the end are 0.
+    ///
+    /// Returns None if the key holds a value that is not a string.
+    pub fn getbit(&self, key: &str, offset: u64) -> Option<bool> {
+        self.access_key(key);
+        match self.keyspace.get(key).as_deref() {
+            None => Some(false),
+            Some(Value::String(bytes)) => {
+                let byte = bytes.get((offset / 8) as usize).copied().unwrap_or(0);
+                Some(byte & (0x80 >> (offset % 8)) != 0)
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// Number of set bits of a string, optionally within an inclusive range.
+    ///
+    /// Negative range bounds count from the end. Returns None if the key holds a value that is
+    /// not a string.
+    pub fn bitcount(&self, key: &str, range: Option<(i64, i64, BitUnit)>) -> Option<usize> {
+        self.access_key(key);
+        let value = self.keyspace.get(key);
+        let bytes = match value.as_deref() {
+            None => return Some(0),
+            Some(Value::String(bytes)) => bytes,
+            Some(_) => return None,
+        };
+
+        let Some((start, end, unit)) = range else {
+            return Some(bytes.iter().map(|b| b.count_ones() as usize).sum());
+        };
+        let len = match unit {
+            BitUnit::Byte => bytes.len(),
+            BitUnit::Bit => bytes.len() * 8,
+        };
+        let Some((start, end)) = clamp_range(start, end, len) else {
+            return Some(0);
+        };
+        let count = match unit {
+            BitUnit::Byte => bytes[start..=end]
+                .iter()
+                .map(|b| b.count_ones() as usize)
+                .sum(),
+            BitUnit::Bit => (start..=end)
+                .filter(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+                .count(),
+        };
+        Some(count)
+    }
+}
+
+impl Backend {
+    /// Store the bitwise operation of the source strings at `dest`, returns the result length.
+    ///
+    /// Shorter sources are zero-padded to the longest one, missing keys are empty strings and
+    /// NOT takes a single source. An empty result deletes `dest`. Returns None if a source
+    /// holds a value that is not a string.
+    pub fn bitop(&self, op: BitwiseOp, dest: String, keys: &[String]) -> Option<usize> {
+        let mut sources = Vec::with_capacity(keys.len());
+        for key in keys {
+            self.access_key(key);
+            match self.keyspace.get(key).as_deref() {
+                None => sources.push(Vec::new()),
+                Some(Value::String(bytes)) => sources.push(bytes.to_vec()),
+                Some(_) => return None,
+            }
+        }
+
+        let len = sources.iter().map(|s| s.len()).max().unwrap_or(0);
+        let byte = |source: &Vec<u8>, i: usize| source.get(i).copied().unwrap_or(0);
+        let result: Vec<u8> = (0..len)
+            .map(|i| {
+                let mut bytes = sources.iter().map(|s| byte(s, i));
+                let first = bytes.next().unwrap_or(0);
+                match op {
+                    BitwiseOp::And => bytes.fold(first, |acc, b| acc & b),
+                    BitwiseOp::Or => bytes.fold(first, |acc, b| acc | b),
+                    BitwiseOp::Xor => bytes.fold(first, |acc, b| acc ^ b),
+                    BitwiseOp::Not => !first,
+                }
+            })
+            .collect();
+
+        if result.is_empty() {
+            self.expire_if_needed(&dest);
+            self.expire.remove(&dest);
+            if self.remove_value(&dest) {
+                self.notify(|| ChangeEvent::Deleted { key: dest });
+            }
+        } else {
+            self.set(dest, result);
+        }
+        Some(len)
+    }
+
+    /// Position of the first bit set to `bit` in a string, optionally within an inclusive range.
+    ///
+    /// Missing keys are empty strings. When looking for a clear bit without an explicit end,
+    /// the bits past the end of the string count as clear. Returns -1 if there is no such bit,
+    /// or None if the key holds a value that is not a string.
+    pub fn bitpos(
+        &self,
+        key: &str,
+        bit: bool,
+        range: Option<(i64, Option<i64>, BitUnit)>,
+    ) -> Option<i64> {
+        self.access_key(key);
+        let value = self.keyspace.get(key);
+        let bytes = match value.as_deref() {
+            None => return Some(if bit { -1 } else { 0 }),
+            Some(Value::String(bytes)) => bytes,
+            Some(_) => return None,
+        };
+
+        let (start, end, unit) = range.unwrap_or((0, None, BitUnit::Byte));
+        let len = match unit {
+            BitUnit::Byte => bytes.len(),
+            BitUnit::Bit => bytes.len() * 8,
+        };
+        let Some((start, end)) = clamp_range(start, end.unwrap_or(-1), len) else {
+            return Some(-1);
+        };
+        let (first, last) = match unit {
+            BitUnit::Byte => (start * 8, end * 8 + 7),
+            BitUnit::Bit => (start, end),
+        };
+
+        // whole bytes without the wanted bit are skipped at once
+        let skip = if bit { 0x00 } else { 0xff };
+        let mut i = first;
+        while i <= last {
+            if i % 8 == 0 && i + 7 <= last && bytes[i / 8] == skip {
+                i += 8;
+                continue;
+            }
+            if (bytes[i / 8] & (0x80 >> (i % 8)) != 0) == bit {
+                return Some(i as i64);
+            }
+            i += 1;
+        }
+
+        match (bit, range.and_then(|(_, end, _)| end)) {
+            (false, None) => Some(last as i64 + 1),
+            _ => Some(-1),
+        }
+    }
+}
+
+/// Resolve an inclusive range with negative bounds counting from the end, None if it is empty.
+pub(super) fn clamp_range(start: i64, end: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let resolve = |i: i64| if i < 0 { (len + i).max(0) } else { i };
+    let (start, end) = (resolve(start), resolve(end).min(len - 1));
+    if len == 0 || start > end {
+        return None;
+    }
+    Some((start as usize, end as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setbit_getbit() {
+        let backend = Backend::new();
+        assert_eq!(backend.setbit("key".to_string(), 7, true), Some(false));
+        assert_eq!(backend.setbit("key".to_string(), 7, true), Some(true));
+        assert_eq!(backend.get("key").unwrap(), Some(vec![0x01].into()));
+
+        assert_eq!(backend.setbit("key".to_string(), 17, true), Some(false));
+        assert_eq!(
+            backend.get("key").unwrap(),
+            Some(vec![0x01, 0x00, 0x40].into())
+        );
+        assert_eq!(backend.setbit("key".to_string(), 7, false), Some(true));
+        assert_eq!(backend.getbit("key", 7), Some(false));
+        assert_eq!(backend.getbit("key", 17), Some(true));
+        assert_eq!(backend.getbit("key", 1000), Some(false));
+        assert_eq!(backend.getbit("missing", 0), Some(false));
+
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        assert_eq!(backend.setbit("set".to_string(), 0, true), None);
+        assert_eq!(backend.getbit("set", 0), None);
+    }
+
+    #[test]
+    fn test_bitop() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), vec![0b1100, 0xff]);
+        backend.set("b".to_string(), vec![0b1010]);
+        let keys = |k: &[&str]| k.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            backend.bitop(BitwiseOp::And, "d".to_string(), &keys(&["a", "b"])),
+            Some(2)
+        );
+        assert_eq!(backend.get("d").unwrap(), Some(vec![0b1000, 0].into()));
+        backend.bitop(BitwiseOp::Or, "d".to_string(), &keys(&["a", "b"]));
+        assert_eq!(backend.get("d").unwrap(), Some(vec![0b1110, 0xff].into()));
+        backend.bitop(
+            BitwiseOp::Xor,
+            "d".to_string(),
+            &keys(&["a", "b", "missing"]),
+        );
+        assert_eq!(backend.get("d").unwrap(), Some(vec![0b0110, 0xff].into()));
+        backend.bitop(BitwiseOp::Not, "d".to_string(), &keys(&["b"]));
+        assert_eq!(backend.get("d").unwrap(), Some(vec![!0b1010].into()));
+
+        // an empty result deletes the destination
+        assert_eq!(
+            backend.bitop(BitwiseOp::Or, "d".to_string(), &keys(&["missing"])),
+            Some(0)
+        );
+        assert_eq!(backend.get("d").unwrap(), None);
+
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        assert_eq!(
+            backend.bitop(BitwiseOp::Or, "d".to_string(), &keys(&["a", "set"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bitpos() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), vec![0xff, 0xf0, 0x00]);
+        assert_eq!(backend.bitpos("key", false, None), Some(12));
+        assert_eq!(backend.bitpos("key", true, None), Some(0));
+        assert_eq!(
+            backend.bitpos("key", true, Some((2, None, BitUnit::Byte))),
+            Some(-1)
+        );
+        assert_eq!(
+            backend.bitpos("key", true, Some((1, None, BitUnit::Byte))),
+            Some(8)
+        );
+        assert_eq!(
+            backend.bitpos("key", true, Some((-2, Some(-1), BitUnit::Byte))),
+            Some(8)
+        );
+        assert_eq!(
+            backend.bitpos("key", false, Some((2, Some(11), BitUnit::Bit))),
+            Some(-1)
+        );
+        assert_eq!(
+            backend.bitpos("key", false, Some((2, Some(12), BitUnit::Bit))),
+            Some(12)
+        );
+
+        backend.set("ones".to_string(), vec![0xff, 0xff]);
+        assert_eq!(backend.bitpos("ones", false, None), Some(16));
+        assert_eq!(
+            backend.bitpos("ones", false, Some((0, Some(-1), BitUnit::Byte))),
+            Some(-1)
+        );
+
+        assert_eq!(backend.bitpos("missing", true, None), Some(-1));
+        assert_eq!(backend.bitpos("missing", false, None), Some(0));
+    }
+
+    #[test]
+    fn test_bitcount() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "foobar");
+        assert_eq!(backend.bitcount("key", None), Some(26));
+        assert_eq!(
+            backend.bitcount("key", Some((0, 0, BitUnit::Byte))),
+            Some(4)
+        );
+        assert_eq!(
+            backend.bitcount("key", Some((1, 1, BitUnit::Byte))),
+            Some(6)
+        );
+        assert_eq!(
+            backend.bitcount("key", Some((1, -1, BitUnit::Byte))),
+            Some(22)
+        );
+        assert_eq!(
+            backend.bitcount("key", Some((5, 30, BitUnit::Bit))),
+            Some(17)
+        );
+        assert_eq!(
+            backend.bitcount("key", Some((3, 1, BitUnit::Byte))),
+            Some(0)
+        );
+        assert_eq!(
+            backend.bitcount("key", Some((-100, 100, BitUnit::Byte))),
+            Some(26)
+        );
+        assert_eq!(backend.bitcount("missing", None), Some(0));
+    }
+}
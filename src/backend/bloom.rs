@@ -0,0 +1,175 @@
+use super::MemSize;
+use std::collections::hash_map::DefaultHasher;
+use std::f64::consts::LN_2;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+
+// each new layer of a scaling filter gets a tighter error rate by this ratio, so that the
+// compound error rate of the filter stays below the requested one
+const TIGHTENING_RATIO: f64 = 0.5;
+
+/// A scalable bloom filter, a new layer is added whenever the last one reaches its capacity.
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    layers: Vec<BloomLayer>,
+    error_rate: f64,
+    // growth factor of the capacity of new layers, 0 for a non scaling filter
+    expansion: u32,
+}
+
+#[derive(Debug, Clone)]
+struct BloomLayer {
+    bits: Vec<u64>,
+    hashes: u32,
+    capacity: usize,
+    count: usize,
+}
+
+impl BloomFilter {
+    pub const DEFAULT_ERROR_RATE: f64 = 0.01;
+    pub const DEFAULT_CAPACITY: usize = 100;
+    pub const DEFAULT_EXPANSION: u32 = 2;
+
+    /// Create a filter for `capacity` items, `expansion` is None for a non scaling filter.
+    pub fn new(error_rate: f64, capacity: usize, expansion: Option<u32>) -> Self {
+        Self {
+            layers: vec![BloomLayer::new(error_rate, capacity)],
+            error_rate,
+            expansion: expansion.unwrap_or(0),
+        }
+    }
+
+    /// Add an item, returns Some(false) if it may already be present and None if the filter is
+    /// full and not allowed to scale.
+    pub fn add(&mut self, item: &str) -> Option<bool> {
+        let (h1, h2) = item_hashes(item);
+        if self.layers.iter().any(|l| l.contains(h1, h2)) {
+            return Some(false);
+        }
+
+        let last = self.layers.last().expect("a filter has at least one layer");
+        if last.count >= last.capacity {
+            if self.expansion == 0 {
+                return None;
+            }
+            let error_rate = self.error_rate * TIGHTENING_RATIO.powi(self.layers.len() as i32);
+            let capacity = last.capacity.saturating_mul(self.expansion as usize);
+            self.layers.push(BloomLayer::new(error_rate, capacity));
+        }
+        self.layers
+            .last_mut()
+            .expect("a filter has at least one layer")
+            .insert(h1, h2);
+        Some(true)
+    }
+
+    /// Whether an item may have been added, false positives are possible.
+    pub fn contains(&self, item: &str) -> bool {
+        let (h1, h2) = item_hashes(item);
+        self.layers.iter().any(|l| l.contains(h1, h2))
+    }
+
+    /// Number of items added to the filter.
+    pub fn len(&self) -> usize {
+        self.layers.iter().map(|l| l.count).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl BloomLayer {
+    fn new(error_rate: f64, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let bits = (capacity as f64 * -error_rate.ln() / (LN_2 * LN_2)).ceil() as usize;
+        let hashes = (-error_rate.log2()).ceil().max(1.0) as u32;
+        Self {
+            bits: vec![0; bits.div_ceil(64).max(1)],
+            hashes,
+            capacity,
+            count: 0,
+        }
+    }
+
+    fn insert(&mut self, h1: u64, h2: u64) {
+        for i in 0..self.hashes {
+            let bit = self.position(h1, h2, i);
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+        self.count += 1;
+    }
+
+    fn contains(&self, h1: u64, h2: u64) -> bool {
+        (0..self.hashes).all(|i| {
+            let bit = self.position(h1, h2, i);
+            self.bits[bit / 64] & (1 << (bit % 64)) != 0
+        })
+    }
+
+    // double hashing, the i-th position is h1 + i * h2
+    fn position(&self, h1: u64, h2: u64, i: u32) -> usize {
+        let len = (self.bits.len() * 64) as u64;
+        (h1.wrapping_add((i as u64).wrapping_mul(h2)) % len) as usize
+    }
+}
+
+impl MemSize for BloomFilter {
+    fn mem_size(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .layers
+                .iter()
+                .map(|l| size_of::<BloomLayer>() + l.bits.capacity() * size_of::<u64>())
+                .sum::<usize>()
+    }
+}
+
+fn item_hashes(item: &str) -> (u64, u64) {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    let h1 = hasher.finish();
+    // chain a second hash off the first one, it must be odd to cycle through every bit
+    h1.hash(&mut hasher);
+    (h1, hasher.finish() | 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bloom_filter_add_contains() {
+        let mut filter = BloomFilter::new(0.01, 100, None);
+        assert_eq!(filter.add("hello"), Some(true));
+        assert_eq!(filter.add("hello"), Some(false));
+        assert!(filter.contains("hello"));
+        assert!(!filter.contains("world"));
+        assert_eq!(filter.len(), 1);
+    }
+
+    #[test]
+    fn test_bloom_filter_non_scaling_full() {
+        let mut filter = BloomFilter::new(0.01, 10, None);
+        let added = (0..20)
+            .filter_map(|i| filter.add(&format!("item{}", i)))
+            .count();
+        assert_eq!(added, 10);
+        assert_eq!(filter.len(), 10);
+    }
+
+    #[test]
+    fn test_bloom_filter_scaling_error_rate() {
+        let mut filter = BloomFilter::new(0.01, 100, Some(2));
+        for i in 0..1000 {
+            assert!(filter.add(&format!("item{}", i)).is_some());
+        }
+        assert!(filter.layers.len() > 1);
+        assert!((0..1000).all(|i| filter.contains(&format!("item{}", i))));
+
+        let false_positives = (0..10000)
+            .filter(|i| filter.contains(&format!("other{}", i)))
+            .count();
+        assert!(false_positives < 200, "{} false positives", false_positives);
+    }
+}
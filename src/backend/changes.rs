@@ -0,0 +1,29 @@
+use crate::RespFrame;
+use bytes::Bytes;
+
+// number of events buffered for each subscriber before it starts lagging
+pub(super) const CHANGE_CHANNEL_CAPACITY: usize = 1024;
+
+/// A change applied to the keyspace, published to change stream subscribers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeEvent {
+    SetString {
+        key: String,
+        value: Bytes,
+    },
+    HashFieldSet {
+        key: String,
+        field: String,
+        value: RespFrame,
+    },
+    SetMemberAdded {
+        key: String,
+        member: String,
+    },
+    Deleted {
+        key: String,
+    },
+    Expired {
+        key: String,
+    },
+}
@@ -0,0 +1,391 @@
+use super::Backend;
+use crate::glob::glob_match;
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicU64, AtomicU8, Ordering},
+    Mutex,
+};
+use thiserror::Error;
+
+/// Eviction policies accepted by `maxmemory-policy`, in the order of their stored index.
+const MAXMEMORY_POLICIES: &[&str] = &[
+    "noeviction",
+    "allkeys-lru",
+    "allkeys-lfu",
+    "allkeys-random",
+    "volatile-lru",
+    "volatile-lfu",
+    "volatile-random",
+    "volatile-ttl",
+];
+
+/// Type of a configuration parameter, values are validated against it before being applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigKind {
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    /// A byte count with an optional k, kb, m, mb, g or gb suffix.
+    Memory,
+    /// yes or no.
+    Bool,
+    Enum(&'static [&'static str]),
+    /// Pairs of a key prefix and a window in seconds, such as `session: 60 cart: 600`.
+    Windows,
+}
+
+// a parsed value: bools are stored as 0 or 1 and enums as an index
+#[derive(Debug, Clone, PartialEq)]
+enum ConfigValue {
+    Integer(i64),
+    Windows(Vec<(String, i64)>),
+}
+
+impl ConfigValue {
+    // the value of a parameter of any kind but Windows
+    fn integer(&self) -> i64 {
+        match self {
+            ConfigValue::Integer(value) => *value,
+            ConfigValue::Windows(_) => unreachable!("not an integer parameter"),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigError {
+    #[error("ERR Unknown option or number of arguments for CONFIG SET - '{0}'")]
+    Unknown(String),
+    #[error("ERR CONFIG SET failed (possibly related to argument '{name}') - {reason}")]
+    Invalid { name: String, reason: String },
+    #[error("ERR Bad directive or wrong number of arguments at line {line}: '{directive}'")]
+    BadDirective { line: usize, directive: String },
+    #[error("ERR The server is running without a config file")]
+    NoConfigFile,
+    #[error("ERR Config file '{path}': {reason}")]
+    File { path: String, reason: String },
+}
+
+struct ConfigParam {
+    name: &'static str,
+    kind: ConfigKind,
+    get: fn(&Backend) -> ConfigValue,
+    set: fn(&Backend, ConfigValue),
+}
+
+const PARAMS: &[ConfigParam] = &[
+    ConfigParam {
+        name: "hotkeys-sample-rate",
+        kind: ConfigKind::Integer {
+            min: 0,
+            max: 1_000_000,
+        },
+        get: |b| ConfigValue::Integer(b.access_sample_rate() as i64),
+        set: |b, v| b.set_access_sample_rate(v.integer() as u64),
+    },
+    ConfigParam {
+        name: "latency-tracking",
+        kind: ConfigKind::Bool,
+        get: |b| ConfigValue::Integer(b.stats().tracking() as i64),
+        set: |b, v| b.stats().set_tracking(v.integer() != 0),
+    },
+    ConfigParam {
+        name: "maxmemory",
+        kind: ConfigKind::Memory,
+        get: |b| ConfigValue::Integer(b.maxmemory() as i64),
+        set: |b, v| {
+            b.config
+                .maxmemory
+                .store(v.integer() as u64, Ordering::Relaxed)
+        },
+    },
+    ConfigParam {
+        name: "maxmemory-policy",
+        kind: ConfigKind::Enum(MAXMEMORY_POLICIES),
+        get: |b| ConfigValue::Integer(b.config.maxmemory_policy.load(Ordering::Relaxed) as i64),
+        set: |b, v| {
+            b.config
+                .maxmemory_policy
+                .store(v.integer() as u8, Ordering::Relaxed)
+        },
+    },
+    ConfigParam {
+        name: "sliding-ttl",
+        kind: ConfigKind::Windows,
+        get: |b| ConfigValue::Windows(b.sliding_ttls()),
+        set: |b, v| match v {
+            ConfigValue::Windows(windows) => b.set_sliding_ttls(windows),
+            ConfigValue::Integer(_) => unreachable!("not a windows parameter"),
+        },
+    },
+    ConfigParam {
+        name: "ttl-jitter",
+        kind: ConfigKind::Integer { min: 0, max: 100 },
+        get: |b| ConfigValue::Integer(b.ttl_jitter() as i64),
+        set: |b, v| b.set_ttl_jitter(v.integer() as u8),
+    },
+];
+
+/// Values of the parameters that are not owned by another part of the backend.
+#[derive(Debug, Default)]
+pub(super) struct ConfigValues {
+    maxmemory: AtomicU64,
+    maxmemory_policy: AtomicU8,
+    // the configuration file loaded at startup, rewritten by CONFIG REWRITE
+    pub(super) file: Mutex<Option<PathBuf>>,
+}
+
+impl ConfigKind {
+    fn parse(&self, value: &str) -> Result<ConfigValue, String> {
+        if let ConfigKind::Windows = self {
+            return parse_windows(value).map(ConfigValue::Windows);
+        }
+        let value = match self {
+            ConfigKind::Integer { min, max } => match value.parse::<i64>() {
+                Ok(v) if (*min..=*max).contains(&v) => Ok(v),
+                Ok(_) => Err(format!(
+                    "argument must be between {} and {} inclusive",
+                    min, max
+                )),
+                Err(_) => Err("argument couldn't be parsed into an integer".to_string()),
+            },
+            ConfigKind::Memory => parse_memory(value)
+                .and_then(|v| i64::try_from(v).ok())
+                .ok_or_else(|| "argument must be a memory value".to_string()),
+            ConfigKind::Bool => match value.to_ascii_lowercase().as_str() {
+                "yes" => Ok(1),
+                "no" => Ok(0),
+                _ => Err("argument must be 'yes' or 'no'".to_string()),
+            },
+            ConfigKind::Enum(names) => names
+                .iter()
+                .position(|n| n.eq_ignore_ascii_case(value))
+                .map(|i| i as i64)
+                .ok_or_else(|| {
+                    format!(
+                        "argument(s) must be one of the following: {}",
+                        names.join(", ")
+                    )
+                }),
+            ConfigKind::Windows => unreachable!(),
+        };
+        value.map(ConfigValue::Integer)
+    }
+
+    fn format(&self, value: &ConfigValue) -> String {
+        let value = match value {
+            ConfigValue::Windows(windows) => {
+                return windows
+                    .iter()
+                    .map(|(prefix, ms)| format!("{} {}", prefix, ms / 1000))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            ConfigValue::Integer(value) => *value,
+        };
+        match self {
+            ConfigKind::Integer { .. } | ConfigKind::Memory => value.to_string(),
+            ConfigKind::Bool if value != 0 => "yes".to_string(),
+            ConfigKind::Bool => "no".to_string(),
+            ConfigKind::Enum(names) => names[value as usize].to_string(),
+            ConfigKind::Windows => unreachable!(),
+        }
+    }
+}
+
+impl Backend {
+    /// The parameters whose name matches any of the glob patterns with their current values,
+    /// sorted by name.
+    pub fn config_get(&self, patterns: &[String]) -> Vec<(String, String)> {
+        PARAMS
+            .iter()
+            .filter(|p| {
+                patterns
+                    .iter()
+                    .any(|pattern| glob_match(pattern.as_bytes(), p.name.as_bytes()))
+            })
+            .map(|p| (p.name.to_string(), p.kind.format(&(p.get)(self))))
+            .collect()
+    }
+
+    /// The memory limit in bytes, 0 for none.
+    pub fn maxmemory(&self) -> u64 {
+        self.config.maxmemory.load(Ordering::Relaxed)
+    }
+
+    /// The name of the eviction policy applied once maxmemory is reached.
+    pub fn maxmemory_policy(&self) -> &'static str {
+        MAXMEMORY_POLICIES[self.config.maxmemory_policy.load(Ordering::Relaxed) as usize]
+    }
+
+    /// Set parameters from (name, value) pairs.
+    ///
+    /// Every value is validated before any is applied, so a failed call changes nothing.
+    pub fn config_set(&self, values: &[(String, String)]) -> Result<(), ConfigError> {
+        let mut parsed = Vec::with_capacity(values.len());
+        for (name, value) in values {
+            let param = PARAMS
+                .iter()
+                .find(|p| p.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| ConfigError::Unknown(name.clone()))?;
+            let value = param
+                .kind
+                .parse(value)
+                .map_err(|reason| ConfigError::Invalid {
+                    name: param.name.to_string(),
+                    reason,
+                })?;
+            parsed.push((param, value));
+        }
+        for (param, value) in parsed {
+            (param.set)(self, value);
+        }
+        Ok(())
+    }
+}
+
+// k and m are powers of 1000, kb, mb and gb powers of 1024, as in redis
+fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.to_ascii_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+// whitespace separated prefix and seconds pairs into (prefix, window in milliseconds), an empty
+// value clears every window
+fn parse_windows(value: &str) -> Result<Vec<(String, i64)>, String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    if !words.len().is_multiple_of(2) {
+        return Err("argument must be pairs of a key prefix and seconds".to_string());
+    }
+    words
+        .chunks(2)
+        .map(|pair| match pair[1].parse::<i64>() {
+            Ok(seconds) if (1..=i64::MAX / 1000).contains(&seconds) => {
+                Ok((pair[0].to_string(), seconds * 1000))
+            }
+            _ => Err(format!(
+                "invalid window '{}' for prefix '{}'",
+                pair[1], pair[0]
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_memory() {
+        assert_eq!(parse_memory("100"), Some(100));
+        assert_eq!(parse_memory("1k"), Some(1000));
+        assert_eq!(parse_memory("1KB"), Some(1024));
+        assert_eq!(parse_memory("2gb"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_memory("1tb"), None);
+        assert_eq!(parse_memory("-1"), None);
+        assert_eq!(parse_memory("mb"), None);
+    }
+
+    #[test]
+    fn test_config_set_get() {
+        let backend = Backend::new();
+        backend
+            .config_set(&pairs(&[
+                ("ttl-jitter", "10"),
+                ("MAXMEMORY", "1mb"),
+                ("maxmemory-policy", "allkeys-LRU"),
+                ("latency-tracking", "no"),
+            ]))
+            .unwrap();
+        assert_eq!(backend.ttl_jitter(), 10);
+        assert!(!backend.stats().tracking());
+        assert_eq!(
+            backend.config_get(&["maxmemory*".to_string()]),
+            pairs(&[
+                ("maxmemory", "1048576"),
+                ("maxmemory-policy", "allkeys-lru")
+            ])
+        );
+        assert_eq!(backend.config_get(&["*".to_string()]).len(), PARAMS.len());
+    }
+
+    #[test]
+    fn test_config_sliding_ttl() {
+        let backend = Backend::new();
+        backend
+            .config_set(&pairs(&[(
+                "sliding-ttl",
+                " session: 60  session:admin: 120 ",
+            )]))
+            .unwrap();
+        assert_eq!(
+            backend.sliding_ttls(),
+            vec![
+                ("session:admin:".to_string(), 120_000),
+                ("session:".to_string(), 60_000)
+            ]
+        );
+        assert_eq!(
+            backend.config_get(&["sliding-ttl".to_string()]),
+            pairs(&[("sliding-ttl", "session:admin: 120 session: 60")])
+        );
+
+        for value in ["session:", "session: 0", "session: soon"] {
+            assert!(backend
+                .config_set(&pairs(&[("sliding-ttl", value)]))
+                .is_err());
+        }
+        assert_eq!(backend.sliding_ttls().len(), 2);
+
+        // an empty value clears every window
+        backend.config_set(&pairs(&[("sliding-ttl", "")])).unwrap();
+        assert!(backend.sliding_ttls().is_empty());
+    }
+
+    #[test]
+    fn test_config_set_errors() {
+        let backend = Backend::new();
+        assert_eq!(
+            backend.config_set(&pairs(&[("ttl-jitter", "5"), ("nope", "1")])),
+            Err(ConfigError::Unknown("nope".to_string()))
+        );
+        // nothing is applied when any value is rejected
+        assert_eq!(backend.ttl_jitter(), 0);
+
+        let err = backend
+            .config_set(&pairs(&[("ttl-jitter", "101")]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR CONFIG SET failed (possibly related to argument 'ttl-jitter') - argument must be between 0 and 100 inclusive"
+        );
+        assert!(backend
+            .config_set(&pairs(&[("latency-tracking", "maybe")]))
+            .is_err());
+        assert!(backend
+            .config_set(&pairs(&[("maxmemory-policy", "lru")]))
+            .is_err());
+        assert!(backend.config_set(&pairs(&[("maxmemory", "1x")])).is_err());
+    }
+}
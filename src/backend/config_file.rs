@@ -0,0 +1,326 @@
+use super::{Backend, ConfigError};
+use crate::repl::split_args;
+use std::{fs, path::Path, path::PathBuf};
+use tracing::warn;
+
+// redis directives that are accepted so that an existing redis.conf can be used, they have no
+// effect and are kept as they are by CONFIG REWRITE
+const IGNORED_DIRECTIVES: &[&str] = &[
+    "appendonly",
+    "appendfsync",
+    "databases",
+    "daemonize",
+    "dbfilename",
+    "dir",
+    "logfile",
+    "loglevel",
+    "pidfile",
+    "save",
+    "tcp-backlog",
+    "tcp-keepalive",
+    "timeout",
+];
+
+// marks the parameters appended by CONFIG REWRITE because the file did not set them
+const REWRITE_MARKER: &str = "# Generated by CONFIG REWRITE";
+
+/// Settings of a configuration file that apply to the server rather than the backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub port: u16,
+    /// Dynamic libraries of the modules to load at startup, in the order of the file.
+    #[cfg(feature = "dylib")]
+    pub modules: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0".to_string(),
+            port: 6379,
+            #[cfg(feature = "dylib")]
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The address to listen on.
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.bind, self.port)
+    }
+}
+
+// a directive of a configuration file: its name in lower case and its arguments
+fn parse_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let mut args = split_args(line)?
+        .into_iter()
+        .map(|arg| String::from_utf8_lossy(&arg).into_owned());
+    let name = args.next()?.to_ascii_lowercase();
+    Some((name, args.collect()))
+}
+
+// a directive line that parse_line reads back, values that are empty or hold spaces are quoted
+fn config_line(name: &str, value: &str) -> String {
+    if !value.is_empty()
+        && !value.contains(|c: char| c.is_ascii_whitespace() || c == '"' || c == '\'')
+    {
+        return format!("{} {}", name, value);
+    }
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("{} \"{}\"", name, escaped)
+}
+
+impl Backend {
+    /// Apply a redis.conf style configuration file, one `directive argument ...` per line.
+    ///
+    /// `port`, `bind` and `loadmodule` are returned for the server, the runtime parameters of CONFIG SET are
+    /// applied to the backend and a few redis directives without effect here are ignored with a
+    /// warning. `requirepass` and `protected-mode yes` fail the file, as there is no
+    /// authentication to enforce them. Any other directive fails the whole file. The file is
+    /// remembered for CONFIG REWRITE.
+    pub fn load_config_file(&self, path: impl AsRef<Path>) -> Result<ServerConfig, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| ConfigError::File {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+
+        let mut server = ServerConfig::default();
+        let mut values = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let bad_directive = || ConfigError::BadDirective {
+                line: i + 1,
+                directive: line.trim().to_string(),
+            };
+            if line.trim().is_empty() || line.trim().starts_with('#') {
+                continue;
+            }
+            let (name, args) = parse_line(line).ok_or_else(bad_directive)?;
+            match (name.as_str(), args.as_slice()) {
+                ("port", [port]) => server.port = port.parse().map_err(|_| bad_directive())?,
+                // only the first of several addresses is listened on
+                ("bind", [addr, ..]) => server.bind = addr.clone(),
+                // there is no authentication, ignoring these would leave the server open to
+                // clients the operator expects to be refused
+                ("requirepass", _) => return Err(bad_directive()),
+                ("protected-mode", [mode]) if !mode.eq_ignore_ascii_case("no") => {
+                    return Err(bad_directive())
+                }
+                ("protected-mode", [_]) => {}
+                // the arguments redis passes to the module on load are not supported
+                #[cfg(feature = "dylib")]
+                ("loadmodule", [path]) => server.modules.push(path.clone()),
+                (name, _) if IGNORED_DIRECTIVES.contains(&name) => {
+                    warn!("Config file directive '{}' is not supported, ignored", name);
+                }
+                (name, [value]) if self.is_config_param(name) => {
+                    values.push((i + 1, line, name.to_string(), value.clone()));
+                }
+                _ => return Err(bad_directive()),
+            }
+        }
+
+        // the parameters are set at once, so that a rejected value applies none of them
+        let pairs: Vec<_> = values
+            .iter()
+            .map(|(_, _, name, value)| (name.clone(), value.clone()))
+            .collect();
+        if let Err(e) = self.config_set(&pairs) {
+            // point at the line of the rejected value
+            return Err(match e {
+                ConfigError::Invalid { ref name, .. } => values
+                    .iter()
+                    .find(|(_, _, n, _)| n == name)
+                    .map(|(line, directive, _, _)| ConfigError::BadDirective {
+                        line: *line,
+                        directive: directive.trim().to_string(),
+                    })
+                    .unwrap_or(e),
+                e => e,
+            });
+        }
+        *self.config.file.lock().unwrap() = Some(path.to_path_buf());
+        Ok(server)
+    }
+
+    /// Write the current runtime parameters back to the configuration file loaded at startup.
+    ///
+    /// The lines setting a parameter are updated in place, the parameters the file does not
+    /// set are appended, and every other line, comments included, is kept as it is.
+    pub fn config_rewrite(&self) -> Result<(), ConfigError> {
+        let path: PathBuf = self
+            .config
+            .file
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(ConfigError::NoConfigFile)?;
+        let file_error = |e: std::io::Error| ConfigError::File {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        };
+        let content = fs::read_to_string(&path).map_err(file_error)?;
+
+        let mut params = self.config_get(&["*".to_string()]);
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            match parse_line(line) {
+                Some((name, _)) if self.is_config_param(&name) => {
+                    // the first line setting a parameter is updated, later ones are dropped
+                    if let Some(i) = params.iter().position(|(n, _)| *n == name) {
+                        let (name, value) = params.remove(i);
+                        lines.push(config_line(&name, &value));
+                    }
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+        if !params.is_empty() {
+            if !lines.iter().any(|line| line == REWRITE_MARKER) {
+                lines.push(REWRITE_MARKER.to_string());
+            }
+            lines.extend(params.iter().map(|(n, v)| config_line(n, v)));
+        }
+
+        // write aside and rename, so the file is never left half written
+        let tmp = path.with_extension("rewrite.tmp");
+        fs::write(&tmp, lines.join("\n") + "\n").map_err(file_error)?;
+        fs::rename(&tmp, &path).map_err(file_error)
+    }
+
+    fn is_config_param(&self, name: &str) -> bool {
+        !self.config_get(&[name.to_ascii_lowercase()]).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("simple-redis-test-{}.conf", name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_config_file() -> anyhow::Result<()> {
+        let path = temp_config(
+            "load",
+            "# comment\nport 7000\nbind 127.0.0.1 ::1\n\nmaxmemory 1mb\nappendonly yes\nttl-jitter \"5\"\n",
+        );
+        let backend = Backend::new();
+        let server = backend.load_config_file(&path)?;
+        assert_eq!(server.addr(), "127.0.0.1:7000");
+        assert_eq!(backend.ttl_jitter(), 5);
+        assert_eq!(
+            backend.config_get(&["maxmemory".to_string()]),
+            vec![("maxmemory".to_string(), "1048576".to_string())]
+        );
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_config_file_errors() {
+        let backend = Backend::new();
+        let path = temp_config("errors", "port 6379\nnope 1\n");
+        assert_eq!(
+            backend.load_config_file(&path),
+            Err(ConfigError::BadDirective {
+                line: 2,
+                directive: "nope 1".to_string(),
+            })
+        );
+        let path = temp_config("errors", "ttl-jitter 500\n");
+        assert!(backend.load_config_file(&path).is_err());
+        let path = temp_config("errors", "port http\n");
+        assert!(backend.load_config_file(&path).is_err());
+
+        // the server cannot refuse unauthenticated clients, so it does not start pretending to
+        let path = temp_config("errors", "port 6379\nrequirepass secret\n");
+        assert_eq!(
+            backend.load_config_file(&path),
+            Err(ConfigError::BadDirective {
+                line: 2,
+                directive: "requirepass secret".to_string(),
+            })
+        );
+        let path = temp_config("errors", "protected-mode yes\n");
+        assert_eq!(
+            backend.load_config_file(&path),
+            Err(ConfigError::BadDirective {
+                line: 1,
+                directive: "protected-mode yes".to_string(),
+            })
+        );
+        let path = temp_config("errors", "protected-mode no\n");
+        assert!(Backend::new().load_config_file(&path).is_ok());
+        fs::remove_file(&path).unwrap();
+
+        assert!(backend.load_config_file("/nonexistent/redis.conf").is_err());
+        assert_eq!(backend.config_rewrite(), Err(ConfigError::NoConfigFile));
+    }
+
+    #[cfg(feature = "dylib")]
+    #[test]
+    fn test_load_config_file_modules() -> anyhow::Result<()> {
+        let path = temp_config(
+            "modules",
+            "loadmodule /opt/modules/first.so\nloadmodule \"/opt/modules/second.so\"\n",
+        );
+        let server = Backend::new().load_config_file(&path)?;
+        assert_eq!(
+            server.modules,
+            ["/opt/modules/first.so", "/opt/modules/second.so"]
+        );
+
+        let path = temp_config("modules", "loadmodule /opt/modules/first.so arg\n");
+        assert!(Backend::new().load_config_file(&path).is_err());
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_rewrite() -> anyhow::Result<()> {
+        let path = temp_config(
+            "rewrite",
+            "# keep me\nport 7000\nttl-jitter 5\nTTL-JITTER 6\nsave 900 1\n",
+        );
+        let backend = Backend::new();
+        backend.load_config_file(&path)?;
+        backend.set_ttl_jitter(20);
+        backend.set_sliding_ttl("session:", 60_000);
+        backend.set_sliding_ttl("cart:", 600_000);
+        backend.config_rewrite()?;
+
+        let content = fs::read_to_string(&path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(
+            lines[..4],
+            ["# keep me", "port 7000", "ttl-jitter 20", "save 900 1"]
+        );
+        assert_eq!(lines[4], REWRITE_MARKER);
+        assert!(lines.contains(&"maxmemory-policy noeviction"));
+        assert!(lines.contains(&"sliding-ttl \"session: 60 cart: 600\""));
+
+        // a rewritten file loads back to the same values and rewrites to itself
+        let reloaded = Backend::new();
+        reloaded.load_config_file(&path)?;
+        assert_eq!(reloaded.ttl_jitter(), 20);
+        assert_eq!(reloaded.sliding_ttls(), backend.sliding_ttls());
+        reloaded.config_rewrite()?;
+        assert_eq!(fs::read_to_string(&path)?, content);
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use super::{Backend, ChangeEvent, Value};
+
+impl Backend {
+    /// Copy the value of a key, along with its time to live, to another key.
+    ///
+    /// Returns false without copying anything if the source does not exist, or if the
+    /// destination exists and `replace` is false. A copied time series is not wired to the
+    /// downsampling rules of the source. Reading the source counts as an access to it.
+    pub fn copy(&self, src: &str, dest: String, replace: bool) -> bool {
+        self.access_key(src);
+        self.expire_if_needed(&dest);
+        if !self.contains(src) {
+            return false;
+        }
+        if self.contains(&dest) {
+            if !replace {
+                return false;
+            }
+            self.expire.remove(&dest);
+            self.remove_value(&dest);
+            self.notify(|| ChangeEvent::Deleted { key: dest.clone() });
+        }
+
+        // the value is cloned out before inserting, both keys may share a shard lock
+        let Some(value) = self.keyspace.get(src).map(|v| match v.value() {
+            Value::TimeSeries(series) => Value::TimeSeries(series.detached()),
+            value => value.clone(),
+        }) else {
+            return false;
+        };
+        self.notify_value(&dest, &value);
+        self.insert_value(dest.clone(), value);
+
+        let expire_at = self.expire.get(src).map(|at| *at);
+        if let Some(at) = expire_at {
+            self.expire.insert(dest, at);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::now_ms;
+    use crate::WrongTypeError;
+
+    #[test]
+    fn test_copy() {
+        let backend = Backend::new();
+        backend.set("string".to_string(), "value");
+        backend.expire_at("string", i64::MAX);
+        backend
+            .hset("hash".to_string(), "field".to_string(), 1.into())
+            .unwrap();
+
+        assert!(backend.copy("string", "copy".to_string(), false));
+        assert_eq!(backend.get("copy").unwrap(), Some("value".into()));
+        assert_eq!(backend.expire_time("copy"), Some(i64::MAX));
+
+        // an existing destination is only overwritten on request
+        assert!(!backend.copy("hash", "copy".to_string(), false));
+        assert!(backend.copy("hash", "copy".to_string(), true));
+        assert_eq!(backend.get("copy"), Err(WrongTypeError));
+        assert_eq!(backend.expire_time("copy"), None);
+        assert_eq!(backend.hget("copy", "field").unwrap(), Some(1.into()));
+
+        // the copy is independent of the source
+        backend
+            .hset("copy".to_string(), "field".to_string(), 2.into())
+            .unwrap();
+        assert_eq!(backend.hget("hash", "field").unwrap(), Some(1.into()));
+
+        assert!(!backend.copy("missing", "copy".to_string(), true));
+    }
+
+    #[test]
+    fn test_copy_accesses_the_source() {
+        let backend = Backend::new();
+        backend.set("src".to_string(), "value");
+        backend.access_times.insert("src", now_ms() - 5_000);
+
+        assert!(backend.copy("src", "dest".to_string(), false));
+        assert_eq!(backend.object_idletime("src"), Some(0));
+    }
+}
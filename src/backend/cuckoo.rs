@@ -0,0 +1,238 @@
+use super::MemSize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+
+// fingerprints are never 0, an empty slot holds 0
+const EMPTY: u16 = 0;
+
+/// A scalable cuckoo filter, unlike a bloom filter items can be deleted.
+///
+/// A new sub filter is added whenever an item cannot be placed within `max_iterations` kicks.
+#[derive(Debug, Clone)]
+pub struct CuckooFilter {
+    filters: Vec<SubFilter>,
+    bucket_size: usize,
+    max_iterations: usize,
+    // growth factor of the capacity of new sub filters, 0 for a non scaling filter
+    expansion: u32,
+}
+
+#[derive(Debug, Clone)]
+struct SubFilter {
+    // `buckets * bucket_size` fingerprint slots, the number of buckets is a power of two
+    slots: Vec<u16>,
+    buckets: usize,
+    count: usize,
+}
+
+impl CuckooFilter {
+    pub const DEFAULT_CAPACITY: usize = 1024;
+    pub const DEFAULT_BUCKET_SIZE: usize = 2;
+    pub const DEFAULT_MAX_ITERATIONS: usize = 20;
+    pub const DEFAULT_EXPANSION: u32 = 1;
+
+    pub fn new(capacity: usize, bucket_size: usize, max_iterations: usize, expansion: u32) -> Self {
+        let bucket_size = bucket_size.max(1);
+        Self {
+            filters: vec![SubFilter::new(capacity, bucket_size)],
+            bucket_size,
+            max_iterations,
+            expansion,
+        }
+    }
+
+    /// Add an item, duplicates are stored again. Returns false if the filter is full.
+    pub fn add(&mut self, item: &str) -> bool {
+        let (hash, fp) = item_hash(item);
+        let last = self
+            .filters
+            .last_mut()
+            .expect("a filter has at least one sub filter");
+        if last.insert(hash, fp, self.bucket_size, self.max_iterations) {
+            return true;
+        }
+        if self.expansion == 0 {
+            return false;
+        }
+
+        let capacity = (last.buckets * self.bucket_size).saturating_mul(self.expansion as usize);
+        let mut filter = SubFilter::new(capacity, self.bucket_size);
+        let inserted = filter.insert(hash, fp, self.bucket_size, self.max_iterations);
+        self.filters.push(filter);
+        inserted
+    }
+
+    /// Whether an item may have been added, false positives are possible.
+    pub fn contains(&self, item: &str) -> bool {
+        let (hash, fp) = item_hash(item);
+        self.filters
+            .iter()
+            .any(|f| f.find(hash, fp, self.bucket_size).is_some())
+    }
+
+    /// Remove one occurrence of an item, returns false if it was not found.
+    pub fn remove(&mut self, item: &str) -> bool {
+        let (hash, fp) = item_hash(item);
+        // newest sub filters first, as RedisBloom does
+        for filter in self.filters.iter_mut().rev() {
+            if let Some(slot) = filter.find(hash, fp, self.bucket_size) {
+                filter.slots[slot] = EMPTY;
+                filter.count -= 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Number of items in the filter.
+    pub fn len(&self) -> usize {
+        self.filters.iter().map(|f| f.count).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SubFilter {
+    fn new(capacity: usize, bucket_size: usize) -> Self {
+        let buckets = capacity.div_ceil(bucket_size).max(1).next_power_of_two();
+        Self {
+            slots: vec![EMPTY; buckets * bucket_size],
+            buckets,
+            count: 0,
+        }
+    }
+
+    fn insert(&mut self, hash: u64, fp: u16, bucket_size: usize, max_iterations: usize) -> bool {
+        let i1 = self.index(hash);
+        let i2 = self.alt_index(i1, fp);
+        for bucket in [i1, i2] {
+            if self.put(bucket, fp, bucket_size) {
+                return true;
+            }
+        }
+
+        // relocate existing fingerprints to their alternate bucket to make room, every kick is
+        // recorded so that a failed insertion can be rolled back
+        let mut kicks = Vec::with_capacity(max_iterations);
+        let (mut bucket, mut fp) = (i2, fp);
+        for n in 0..max_iterations {
+            let slot = bucket * bucket_size + n % bucket_size;
+            kicks.push((slot, fp));
+            fp = std::mem::replace(&mut self.slots[slot], fp);
+            bucket = self.alt_index(bucket, fp);
+            if self.put(bucket, fp, bucket_size) {
+                return true;
+            }
+        }
+        for (slot, old) in kicks.into_iter().rev() {
+            fp = std::mem::replace(&mut self.slots[slot], fp);
+            debug_assert_eq!(fp, old);
+        }
+        false
+    }
+
+    fn put(&mut self, bucket: usize, fp: u16, bucket_size: usize) -> bool {
+        let slots = &mut self.slots[bucket * bucket_size..(bucket + 1) * bucket_size];
+        match slots.iter_mut().find(|s| **s == EMPTY) {
+            Some(slot) => {
+                *slot = fp;
+                self.count += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn find(&self, hash: u64, fp: u16, bucket_size: usize) -> Option<usize> {
+        let i1 = self.index(hash);
+        let i2 = self.alt_index(i1, fp);
+        [i1, i2].into_iter().find_map(|bucket| {
+            (bucket * bucket_size..(bucket + 1) * bucket_size).find(|&slot| self.slots[slot] == fp)
+        })
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        hash as usize & (self.buckets - 1)
+    }
+
+    // the alternate bucket only depends on the bucket and the fingerprint, so it can be computed
+    // back and forth when relocating
+    fn alt_index(&self, bucket: usize, fp: u16) -> usize {
+        (bucket ^ fingerprint_hash(fp) as usize) & (self.buckets - 1)
+    }
+}
+
+impl MemSize for CuckooFilter {
+    fn mem_size(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .filters
+                .iter()
+                .map(|f| size_of::<SubFilter>() + f.slots.capacity() * size_of::<u16>())
+                .sum::<usize>()
+    }
+}
+
+// the bucket hash and the fingerprint of an item
+fn item_hash(item: &str) -> (u64, u16) {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    let hash = hasher.finish();
+    let fp = (hash >> 48) as u16;
+    (hash, if fp == EMPTY { 1 } else { fp })
+}
+
+fn fingerprint_hash(fp: u16) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    fp.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cuckoo_filter_add_remove() {
+        let mut filter = CuckooFilter::new(100, 2, 20, 1);
+        assert!(filter.add("hello"));
+        assert!(filter.add("hello"));
+        assert!(filter.contains("hello"));
+        assert!(!filter.contains("world"));
+        assert_eq!(filter.len(), 2);
+
+        assert!(filter.remove("hello"));
+        assert!(filter.contains("hello"));
+        assert!(filter.remove("hello"));
+        assert!(!filter.contains("hello"));
+        assert!(!filter.remove("hello"));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn test_cuckoo_filter_non_scaling_full() {
+        let mut filter = CuckooFilter::new(8, 2, 20, 0);
+        let added: Vec<_> = (0..100)
+            .map(|i| format!("item{}", i))
+            .filter(|item| filter.add(item))
+            .collect();
+        assert!(added.len() <= 8);
+        assert_eq!(filter.len(), added.len());
+        // a failed insertion leaves the previous items in place
+        assert!(added.iter().all(|item| filter.contains(item)));
+    }
+
+    #[test]
+    fn test_cuckoo_filter_scaling() {
+        let mut filter = CuckooFilter::new(64, 2, 20, 2);
+        for i in 0..1000 {
+            assert!(filter.add(&format!("item{}", i)));
+        }
+        assert!(filter.filters.len() > 1);
+        assert_eq!(filter.len(), 1000);
+        assert!((0..1000).all(|i| filter.contains(&format!("item{}", i))));
+    }
+}
@@ -0,0 +1,297 @@
+use super::{now_ms, Backend, ChangeEvent, Value};
+use crate::{BulkString, RespDecode, RespEncode, RespFrame};
+use bytes::BytesMut;
+use dashmap::{DashMap, DashSet};
+use thiserror::Error;
+
+// version of the payload format, payloads of another version are rejected
+const DUMP_VERSION: u16 = 2;
+// reflected polynomial of CRC-64/Jones, the checksum used by redis
+const CRC64_POLY: u64 = 0x95ac_9329_ac4b_c9b5;
+
+// a payload is a section holding the value of the key, followed by the version and the
+// checksum of everything before it; lengths are u32 little endian
+const TAG_STRING: u8 = 0;
+const TAG_HASH: u8 = 1;
+const TAG_SET: u8 = 2;
+#[cfg(feature = "json")]
+const TAG_JSON: u8 = 3;
+
+// hash field values are usually bulk strings, stored raw, other frames are stored RESP encoded
+const FRAME_BULK: u8 = 0;
+const FRAME_RESP: u8 = 1;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DumpError {
+    #[error("ERR DUMP payload version or checksum are wrong")]
+    BadPayload,
+    #[error("BUSYKEY Target key name already exists.")]
+    BusyKey,
+    #[error("ERR DUMP is not supported for the type of this key")]
+    Unsupported,
+}
+
+impl Backend {
+    /// Serialize the value of a key, None if the key does not exist.
+    ///
+    /// Strings, hashes, sets and JSON documents are supported, the probabilistic structures and
+    /// time series are not. The time to live is not part of the payload.
+    pub fn dump(&self, key: &str) -> Result<Option<Vec<u8>>, DumpError> {
+        self.access_key(key);
+        let Some(value) = self.keyspace.get(key) else {
+            return Ok(None);
+        };
+
+        let mut out = Vec::new();
+        match value.value() {
+            Value::String(value) => {
+                out.push(TAG_STRING);
+                write_bytes(&mut out, value);
+            }
+            Value::Hash(hash) => {
+                out.push(TAG_HASH);
+                write_len(&mut out, hash.len());
+                for field in hash.iter() {
+                    write_bytes(&mut out, field.key().as_bytes());
+                    write_frame(&mut out, field.value());
+                }
+            }
+            Value::Set(set) => {
+                out.push(TAG_SET);
+                write_len(&mut out, set.len());
+                for member in set.iter() {
+                    write_bytes(&mut out, member.key().as_bytes());
+                }
+            }
+            #[cfg(feature = "json")]
+            Value::Json(json) => {
+                out.push(TAG_JSON);
+                write_bytes(&mut out, json.to_string().as_bytes());
+            }
+            _ => return Err(DumpError::Unsupported),
+        }
+
+        out.extend_from_slice(&DUMP_VERSION.to_le_bytes());
+        let checksum = crc64(&out);
+        out.extend_from_slice(&checksum.to_le_bytes());
+        Ok(Some(out))
+    }
+
+    /// Recreate a key from a payload of [`Backend::dump`], expiring at `expire_at` if given.
+    ///
+    /// An existing key is only replaced if `replace` is true. A payload expiring in the past is
+    /// accepted but creates no key.
+    pub fn restore(
+        &self,
+        key: String,
+        expire_at: Option<i64>,
+        payload: &[u8],
+        replace: bool,
+    ) -> Result<(), DumpError> {
+        let value = parse_payload(payload).ok_or(DumpError::BadPayload)?;
+
+        self.expire_if_needed(&key);
+        if self.contains(&key) {
+            if !replace {
+                return Err(DumpError::BusyKey);
+            }
+            self.expire.remove(&key);
+            self.remove_value(&key);
+            self.notify(|| ChangeEvent::Deleted { key: key.clone() });
+        }
+        if expire_at.is_some_and(|at| at <= now_ms()) {
+            return Ok(());
+        }
+
+        self.notify_value(&key, &value);
+        self.insert_value(key.clone(), value);
+        if let Some(at) = expire_at {
+            self.expire_at(&key, at);
+        }
+        Ok(())
+    }
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_len(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+fn write_frame(out: &mut Vec<u8>, frame: &RespFrame) {
+    match frame {
+        RespFrame::BulkString(BulkString(Some(bytes))) => {
+            out.push(FRAME_BULK);
+            write_bytes(out, bytes);
+        }
+        frame => {
+            out.push(FRAME_RESP);
+            write_bytes(out, &frame.clone().encode());
+        }
+    }
+}
+
+// None if the payload is corrupted or of another version
+fn parse_payload(payload: &[u8]) -> Option<Value> {
+    let (body, checksum) = payload.split_at_checked(payload.len().checked_sub(8)?)?;
+    if crc64(body).to_le_bytes() != checksum {
+        return None;
+    }
+    let (body, version) = body.split_at_checked(body.len().checked_sub(2)?)?;
+    if version != DUMP_VERSION.to_le_bytes() {
+        return None;
+    }
+
+    let mut reader = Reader { buf: body };
+    let value = match reader.u8()? {
+        TAG_STRING => Value::String(reader.bytes()?.to_vec().into()),
+        TAG_HASH => {
+            let len = reader.len()?;
+            let hash = DashMap::new();
+            for _ in 0..len {
+                hash.insert(reader.string()?, reader.frame()?);
+            }
+            Value::Hash(hash)
+        }
+        TAG_SET => {
+            let len = reader.len()?;
+            let set = DashSet::new();
+            for _ in 0..len {
+                set.insert(reader.string()?);
+            }
+            Value::Set(set)
+        }
+        #[cfg(feature = "json")]
+        TAG_JSON => Value::Json(serde_json::from_slice(reader.bytes()?).ok()?),
+        _ => return None,
+    };
+    reader.buf.is_empty().then_some(value)
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Option<u8> {
+        let (first, rest) = self.buf.split_first()?;
+        self.buf = rest;
+        Some(*first)
+    }
+
+    fn len(&mut self) -> Option<usize> {
+        let (len, rest) = self.buf.split_first_chunk::<4>()?;
+        self.buf = rest;
+        Some(u32::from_le_bytes(*len) as usize)
+    }
+
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.len()?;
+        let (bytes, rest) = self.buf.split_at_checked(len)?;
+        self.buf = rest;
+        Some(bytes)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?.to_vec()).ok()
+    }
+
+    fn frame(&mut self) -> Option<RespFrame> {
+        match self.u8()? {
+            FRAME_BULK => Some(BulkString::new(self.bytes()?).into()),
+            FRAME_RESP => {
+                let mut buf = BytesMut::from(self.bytes()?);
+                let frame = RespFrame::decode(&mut buf).ok()?;
+                buf.is_empty().then_some(frame)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn crc64(data: &[u8]) -> u64 {
+    let mut crc = 0u64;
+    for byte in data {
+        crc ^= *byte as u64;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC64_POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc64() {
+        assert_eq!(crc64(b"123456789"), 0xe9c6_d914_c4b8_d9ca);
+    }
+
+    #[test]
+    fn test_dump_restore() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        backend.set("string".to_string(), "a\r\nb");
+        backend.set("int".to_string(), "42");
+        backend
+            .hset("hash".to_string(), "field".to_string(), 1.into())
+            .unwrap();
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+
+        for key in ["string", "int", "hash", "set"] {
+            let payload = backend.dump(key)?.unwrap();
+            backend.restore(format!("{}-copy", key), None, &payload, false)?;
+        }
+        assert_eq!(backend.get("string-copy").unwrap(), Some("a\r\nb".into()));
+        assert_eq!(backend.get("int-copy").unwrap(), Some("42".into()));
+        assert_eq!(backend.hget("hash-copy", "field").unwrap(), Some(1.into()));
+        assert!(backend.sismember("set-copy", "member").unwrap());
+
+        let payload = backend.dump("string")?.unwrap();
+        assert_eq!(
+            backend.restore("string-copy".to_string(), None, &payload, false),
+            Err(DumpError::BusyKey)
+        );
+        backend.restore("int".to_string(), Some(i64::MAX), &payload, true)?;
+        assert_eq!(backend.get("int").unwrap(), Some("a\r\nb".into()));
+        assert_eq!(backend.expire_time("int"), Some(i64::MAX));
+
+        // expired payloads create nothing
+        backend.restore("expired".to_string(), Some(1), &payload, false)?;
+        assert!(!backend.exists("expired"));
+
+        assert_eq!(backend.dump("missing")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_rejects_bad_payloads() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        let mut payload = backend.dump("key").unwrap().unwrap();
+
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+        assert_eq!(
+            backend.restore("other".to_string(), None, &payload, false),
+            Err(DumpError::BadPayload)
+        );
+        assert_eq!(
+            backend.restore("other".to_string(), None, b"short", false),
+            Err(DumpError::BadPayload)
+        );
+
+        backend.cms_init("cms".to_string(), crate::CountMinSketch::new(10, 2));
+        assert_eq!(backend.dump("cms"), Err(DumpError::Unsupported));
+    }
+}
@@ -0,0 +1,228 @@
+use super::{now_ms, Backend};
+use dashmap::DashMap;
+use rand::Rng;
+use std::cmp::Reverse;
+use thiserror::Error;
+
+// keys sampled to pick each key to evict, the default of maxmemory-samples in redis
+const EVICTION_SAMPLES: usize = 5;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EvictionError {
+    #[error("OOM command not allowed when used memory > 'maxmemory'.")]
+    OutOfMemory,
+}
+
+impl Backend {
+    /// Evict keys as maxmemory-policy says until the used memory is within maxmemory, returns
+    /// the number of keys evicted.
+    ///
+    /// Each key evicted is the best of a few sampled ones: the least recently accessed for the
+    /// lru policies, the least frequently accessed for the lfu ones, the closest to expire for
+    /// volatile-ttl. Access frequencies are only counted while hot keys are tracked, without
+    /// them the lfu policies evict as the lru ones. Fails if the memory is still above the
+    /// limit and nothing can be evicted, always so under noeviction.
+    ///
+    /// The used memory is kept up to date by every write, see [`Backend::used_memory`], so the
+    /// check is cheap enough to run before each command and catches every write since the last.
+    pub fn free_memory(&self) -> Result<usize, EvictionError> {
+        let limit = self.maxmemory() as usize;
+        if limit == 0 {
+            return Ok(0);
+        }
+        let policy = self.maxmemory_policy();
+        let mut rng = rand::thread_rng();
+        let mut evicted = 0;
+        while self.used_memory() > limit {
+            let key = self
+                .eviction_candidate(policy, &mut rng)
+                .ok_or(EvictionError::OutOfMemory)?;
+            self.unlink(std::slice::from_ref(&key));
+            evicted += 1;
+        }
+        Ok(evicted)
+    }
+
+    // the key to evict out of a sample of keys, None if there is nothing to evict
+    fn eviction_candidate(&self, policy: &str, rng: &mut impl Rng) -> Option<String> {
+        let (scope, order) = policy.split_once('-')?;
+        // a key drawn twice is only compared with itself, there is no need to dedup
+        let samples: Vec<String> = (0..EVICTION_SAMPLES)
+            .filter_map(|_| match scope {
+                "allkeys" => random_table_key(&self.keyspace, rng),
+                "volatile" => random_table_key(&self.expire, rng),
+                _ => None,
+            })
+            .collect();
+
+        let now = now_ms();
+        let idle = |key: &String| now - self.access_times.get(key).unwrap_or(now);
+        match order {
+            "lru" => samples.into_iter().max_by_key(idle),
+            "lfu" => samples
+                .into_iter()
+                .min_by_key(|key| (self.access.count(key), Reverse(idle(key)))),
+            "ttl" => samples
+                .into_iter()
+                .min_by_key(|key| self.expire.get(key).map(|at| *at).unwrap_or(i64::MAX)),
+            "random" => samples.into_iter().next(),
+            _ => None,
+        }
+    }
+}
+
+// the key in a random bucket of a random non-empty shard, or in the first full bucket after it;
+// as redis does, this costs a few bucket probes instead of a walk of the shard, and every key
+// can be drawn
+fn random_table_key<V>(table: &DashMap<String, V>, rng: &mut impl Rng) -> Option<String> {
+    let shards = table.shards();
+    let start = rng.gen_range(0..shards.len());
+    (0..shards.len()).find_map(|i| {
+        let shard = shards[(start + i) % shards.len()].read();
+        if shard.is_empty() {
+            return None;
+        }
+        let raw = shard.raw_table();
+        let buckets = raw.buckets();
+        let first = rng.gen_range(0..buckets);
+        (0..buckets)
+            .map(|j| (first + j) % buckets)
+            .find_map(|index| {
+                // SAFETY: the index is below the number of buckets, and the shard is locked for
+                // reading while the key is cloned out of its bucket
+                unsafe {
+                    raw.is_bucket_full(index)
+                        .then(|| raw.bucket(index).as_ref().0.clone())
+                }
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashSet;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn fill(backend: &Backend, count: usize) {
+        for i in 0..count {
+            backend.set(format!("key:{}", i), vec![b'x'; 100]);
+        }
+    }
+
+    #[test]
+    fn test_free_memory_noeviction() {
+        let backend = Backend::new();
+        fill(&backend, 10);
+        assert_eq!(backend.free_memory(), Ok(0));
+
+        backend.config_set(&pairs(&[("maxmemory", "100")])).unwrap();
+        assert_eq!(backend.free_memory(), Err(EvictionError::OutOfMemory));
+        assert_eq!(backend.keys("*").len(), 10);
+    }
+
+    #[test]
+    fn test_free_memory_allkeys_lru() {
+        let backend = Backend::new();
+        fill(&backend, 100);
+        let used = backend.used_memory();
+        assert!(used > 100 * 100);
+
+        let limit = (used / 2).to_string();
+        backend
+            .config_set(&pairs(&[
+                ("maxmemory", &limit),
+                ("maxmemory-policy", "allkeys-lru"),
+            ]))
+            .unwrap();
+        let evicted = backend.free_memory().unwrap();
+        assert!((45..=55).contains(&evicted));
+        assert_eq!(backend.keys("*").len(), 100 - evicted);
+        assert!(backend.used_memory() <= used / 2);
+    }
+
+    #[test]
+    fn test_free_memory_after_burst() {
+        let backend = Backend::new();
+        fill(&backend, 10);
+        let limit = backend.used_memory();
+        backend
+            .config_set(&pairs(&[
+                ("maxmemory", &limit.to_string()),
+                ("maxmemory-policy", "allkeys-random"),
+            ]))
+            .unwrap();
+        assert_eq!(backend.free_memory(), Ok(0));
+
+        // the writes are accounted for as they happen, nothing waits for a new measure
+        for i in 0..10 {
+            backend.set(format!("burst:{}", i), vec![b'x'; 1000]);
+        }
+        assert!(backend.used_memory() > limit);
+        assert!(backend.free_memory().unwrap() > 0);
+        assert!(backend.used_memory() <= limit);
+    }
+
+    #[test]
+    fn test_free_memory_volatile_lru() {
+        let backend = Backend::new();
+        fill(&backend, 10);
+        backend.expire_at("key:3", i64::MAX);
+        backend
+            .config_set(&pairs(&[
+                ("maxmemory", "1"),
+                ("maxmemory-policy", "volatile-lru"),
+            ]))
+            .unwrap();
+
+        // only the key with a time to live can be evicted
+        assert_eq!(backend.free_memory(), Err(EvictionError::OutOfMemory));
+        assert!(!backend.exists("key:3"));
+        assert_eq!(backend.keys("*").len(), 9);
+    }
+
+    #[test]
+    fn test_eviction_candidate_is_least_recently_used() {
+        let backend = Backend::new();
+        fill(&backend, 100);
+        // key:i was last accessed 100 - i seconds ago
+        let now = now_ms();
+        for i in 0..100 {
+            backend
+                .access_times
+                .insert(&format!("key:{}", i), now - (100 - i) * 1000);
+        }
+
+        // the best of 5 samples is in the older half most of the time, and never the newest key
+        let mut rng = StdRng::seed_from_u64(1);
+        let candidates: Vec<String> = (0..100)
+            .map(|_| backend.eviction_candidate("allkeys-lru", &mut rng).unwrap())
+            .collect();
+        let older = |key: &String| key["key:".len()..].parse::<i64>().unwrap() < 50;
+        assert!(candidates.iter().filter(|key| older(key)).count() >= 80);
+        assert!(!candidates.contains(&"key:99".to_string()));
+
+        assert_eq!(backend.eviction_candidate("volatile-lru", &mut rng), None);
+        assert_eq!(backend.eviction_candidate("noeviction", &mut rng), None);
+    }
+
+    #[test]
+    fn test_eviction_candidates_cover_the_keyspace() {
+        let backend = Backend::new();
+        fill(&backend, 100);
+
+        // sampling by bucket reaches keys anywhere in their shard
+        let mut rng = StdRng::seed_from_u64(1);
+        let drawn: HashSet<String> = (0..1000)
+            .filter_map(|_| backend.eviction_candidate("allkeys-random", &mut rng))
+            .collect();
+        assert!(drawn.len() >= 90);
+    }
+}
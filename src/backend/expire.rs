@@ -0,0 +1,106 @@
+use super::{now_ms, Backend, ChangeEvent};
+
+/// Conditions of EXPIRE and its variants on the current expiration of the key.
+///
+/// A key without expiration counts as one with an infinite time to live, no flag always sets
+/// the expiration. NX cannot be combined with any other flag, nor GT with LT.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExpireCondition {
+    /// NX, only if the key has no expiration
+    pub nx: bool,
+    /// XX, only if the key has an expiration
+    pub xx: bool,
+    /// GT, only if the new expiration is later than the current one
+    pub gt: bool,
+    /// LT, only if the new expiration is earlier than the current one
+    pub lt: bool,
+}
+
+impl ExpireCondition {
+    fn allows(&self, current: Option<i64>, at: i64) -> bool {
+        (!self.nx || current.is_none())
+            && (!self.xx || current.is_some())
+            && (!self.gt || current.is_some_and(|c| at > c))
+            && (!self.lt || current.is_none_or(|c| at < c))
+    }
+}
+
+impl Backend {
+    /// Set the absolute expiration time (unix milliseconds) of an existing key if its current
+    /// expiration satisfies `condition`, returns false if the key does not exist or it does not.
+    ///
+    /// A time in the past deletes the key right away.
+    pub fn expire_with(&self, key: &str, at: i64, condition: ExpireCondition) -> bool {
+        self.expire_if_needed(key);
+        if !self.contains(key) {
+            return false;
+        }
+        let current = self.expire.get(key).map(|at| *at);
+        if !condition.allows(current, at) {
+            return false;
+        }
+
+        if at <= now_ms() {
+            self.expire.remove(key);
+            self.remove_value(key);
+            self.access.remove(key);
+            self.notify(|| ChangeEvent::Deleted {
+                key: key.to_string(),
+            });
+        } else {
+            self.expire.insert(key.to_string(), at);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expire_conditions() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        let later = now_ms() + 60_000;
+        let nx = ExpireCondition {
+            nx: true,
+            ..Default::default()
+        };
+        let xx = ExpireCondition {
+            xx: true,
+            ..Default::default()
+        };
+        let gt = ExpireCondition {
+            gt: true,
+            ..Default::default()
+        };
+        let lt = ExpireCondition {
+            lt: true,
+            ..Default::default()
+        };
+
+        // without expiration the time to live is infinite
+        assert!(!backend.expire_with("key", later, xx));
+        assert!(!backend.expire_with("key", later, gt));
+        assert!(backend.expire_with("key", later, lt));
+        assert_eq!(backend.expire_time("key"), Some(later));
+
+        assert!(!backend.expire_with("key", later + 1, nx));
+        assert!(!backend.expire_with("key", later - 1, gt));
+        assert!(backend.expire_with("key", later + 1, gt));
+        assert!(!backend.expire_with("key", later + 2, lt));
+        assert!(backend.expire_with("key", later, ExpireCondition::default()));
+        assert_eq!(backend.expire_time("key"), Some(later));
+
+        assert!(!backend.expire_with("missing", later, ExpireCondition::default()));
+    }
+
+    #[test]
+    fn test_expire_in_the_past_deletes() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        assert!(backend.expire_with("key", now_ms() - 1, ExpireCondition::default()));
+        assert!(!backend.exists("key"));
+    }
+}
@@ -0,0 +1,498 @@
+use super::Backend;
+use crate::{BulkString, RespFrame};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+use thiserror::Error;
+use wasmi::{Caller, Config, Engine, Extern, Linker, Module, Store};
+
+// instructions, roughly, a function call may execute before it is stopped
+const FUEL_LIMIT: u64 = 10_000_000;
+// module name of the host functions imported by libraries
+const HOST_MODULE: &str = "redis";
+// engine name of the library metadata line, `#!wasm name=<library>`
+const ENGINE_NAME: &str = "wasm";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum FunctionError {
+    #[error("ERR Missing library metadata")]
+    MissingMetadata,
+    #[error("ERR Engine '{0}' not found")]
+    UnknownEngine(String),
+    #[error("ERR Invalid metadata value given: {0}")]
+    InvalidMetadata(String),
+    #[error("ERR Library names can only contain letters, numbers, or underscores(_) and must be at least one character long")]
+    InvalidLibraryName,
+    #[error("ERR Library '{0}' already exists")]
+    LibraryExists(String),
+    #[error("ERR Function {0} already exists")]
+    FunctionExists(String),
+    #[error("ERR No functions registered")]
+    NoFunctions,
+    #[error("ERR Library not found")]
+    NoSuchLibrary,
+    #[error("ERR Function not found")]
+    NoSuchFunction,
+    #[error("ERR Error compiling function: {0}")]
+    Compile(String),
+    #[error("ERR Error running function {name}: {reason}")]
+    Runtime { name: String, reason: String },
+}
+
+/// The WebAssembly libraries loaded by FUNCTION LOAD and the functions they export.
+pub(super) struct Libraries {
+    engine: Engine,
+    loaded: RwLock<Loaded>,
+}
+
+#[derive(Default)]
+struct Loaded {
+    libraries: HashMap<String, Library>,
+    // library of each function, names are unique across libraries
+    functions: HashMap<String, String>,
+}
+
+struct Library {
+    module: Arc<Module>,
+    functions: Vec<String>,
+}
+
+// what the host functions of a call see and produce
+struct HostState {
+    backend: Backend,
+    keys: Vec<String>,
+    args: Vec<Vec<u8>>,
+    reply: Option<Vec<u8>>,
+}
+
+impl Default for Libraries {
+    fn default() -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Self {
+            engine: Engine::new(&config),
+            loaded: RwLock::new(Loaded::default()),
+        }
+    }
+}
+
+impl fmt::Debug for Libraries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let loaded = self.loaded.read().unwrap();
+        f.debug_struct("Libraries")
+            .field("libraries", &loaded.libraries.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Backend {
+    /// Load a library of functions, returns the name of the library.
+    ///
+    /// The code is a metadata line `#!wasm name=<library>` followed by a WebAssembly module.
+    /// Every export of the module taking no parameter and returning an i64 is a function that
+    /// FCALL can call. Replacing a loaded library requires `replace`.
+    ///
+    /// Functions reach the caller and the keyspace through host functions imported from the
+    /// `redis` module, all pointers and lengths being into the exported `memory`:
+    ///
+    /// - `key(index, ptr, cap) -> len` and `arg(index, ptr, cap) -> len` copy up to `cap` bytes
+    ///   of a key or an argument of FCALL, and return its length or -1 past the last one;
+    /// - `get(key_ptr, key_len, ptr, cap) -> len` copies a string value the same way, -1 if the
+    ///   key does not exist;
+    /// - `set(key_ptr, key_len, value_ptr, value_len)` sets a string value;
+    /// - `del(key_ptr, key_len) -> removed` removes a key;
+    /// - `reply(ptr, len)` replies with a bulk string instead of the returned integer.
+    pub fn function_load(&self, code: &[u8], replace: bool) -> Result<String, FunctionError> {
+        let (name, wasm) = parse_metadata(code)?;
+        let module = Module::new(&self.libraries.engine, wasm)
+            .map_err(|e| FunctionError::Compile(e.to_string()))?;
+        let mut functions: Vec<String> = module
+            .exports()
+            .filter(|export| {
+                export.ty().func().is_some_and(|ty| {
+                    ty.params().is_empty() && ty.results() == [wasmi::core::ValType::I64]
+                })
+            })
+            .map(|export| export.name().to_string())
+            .collect();
+        if functions.is_empty() {
+            return Err(FunctionError::NoFunctions);
+        }
+        functions.sort();
+
+        let mut loaded = self.libraries.loaded.write().unwrap();
+        if loaded.libraries.contains_key(&name) && !replace {
+            return Err(FunctionError::LibraryExists(name));
+        }
+        if let Some(function) = functions.iter().find(|function| {
+            loaded
+                .functions
+                .get(*function)
+                .is_some_and(|library| *library != name)
+        }) {
+            return Err(FunctionError::FunctionExists(function.clone()));
+        }
+        loaded.remove(&name);
+        for function in &functions {
+            loaded.functions.insert(function.clone(), name.clone());
+        }
+        loaded.libraries.insert(
+            name.clone(),
+            Library {
+                module: Arc::new(module),
+                functions,
+            },
+        );
+        Ok(name)
+    }
+
+    /// Unload a library and its functions.
+    pub fn function_delete(&self, library: &str) -> Result<(), FunctionError> {
+        let mut loaded = self.libraries.loaded.write().unwrap();
+        loaded
+            .remove(library)
+            .then_some(())
+            .ok_or(FunctionError::NoSuchLibrary)
+    }
+
+    /// Unload every library.
+    pub fn function_flush(&self) {
+        *self.libraries.loaded.write().unwrap() = Loaded::default();
+    }
+
+    /// The loaded libraries and their functions, sorted by name.
+    pub fn function_list(&self) -> Vec<(String, Vec<String>)> {
+        let loaded = self.libraries.loaded.read().unwrap();
+        let mut libraries: Vec<_> = loaded
+            .libraries
+            .iter()
+            .map(|(name, library)| (name.clone(), library.functions.clone()))
+            .collect();
+        libraries.sort();
+        libraries
+    }
+
+    /// Call a function of a loaded library, see [`Backend::function_load`].
+    ///
+    /// The call runs in a fresh instance of the library and is stopped once it runs out of fuel.
+    /// It is not isolated from the commands run meanwhile by other connections.
+    pub fn fcall(
+        &self,
+        function: &str,
+        keys: Vec<String>,
+        args: Vec<Vec<u8>>,
+    ) -> Result<RespFrame, FunctionError> {
+        let module = {
+            let loaded = self.libraries.loaded.read().unwrap();
+            let library = loaded
+                .functions
+                .get(function)
+                .ok_or(FunctionError::NoSuchFunction)?;
+            loaded.libraries[library].module.clone()
+        };
+        let runtime_error = |e: wasmi::Error| FunctionError::Runtime {
+            name: function.to_string(),
+            reason: e.to_string(),
+        };
+
+        let state = HostState {
+            backend: self.clone(),
+            keys,
+            args,
+            reply: None,
+        };
+        let mut store = Store::new(&self.libraries.engine, state);
+        store
+            .set_fuel(FUEL_LIMIT)
+            .map_err(|e| runtime_error(wasmi::Error::new(e.to_string())))?;
+        let linker = host_functions(&self.libraries.engine).map_err(runtime_error)?;
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(runtime_error)?;
+        let ret = instance
+            .get_typed_func::<(), i64>(&store, function)
+            .and_then(|f| f.call(&mut store, ()))
+            .map_err(runtime_error)?;
+
+        Ok(match store.into_data().reply {
+            Some(reply) => BulkString::new(reply).into(),
+            None => ret.into(),
+        })
+    }
+}
+
+impl Loaded {
+    // unload a library, returns false if it is not loaded
+    fn remove(&mut self, name: &str) -> bool {
+        let Some(library) = self.libraries.remove(name) else {
+            return false;
+        };
+        for function in &library.functions {
+            self.functions.remove(function);
+        }
+        true
+    }
+}
+
+// split the metadata line from the module, returns the library name
+fn parse_metadata(code: &[u8]) -> Result<(String, &[u8]), FunctionError> {
+    let end = code
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or(FunctionError::MissingMetadata)?;
+    let line = std::str::from_utf8(&code[..end]).map_err(|_| FunctionError::MissingMetadata)?;
+    let mut fields = line
+        .strip_prefix("#!")
+        .ok_or(FunctionError::MissingMetadata)?
+        .split_whitespace();
+
+    let engine = fields.next().ok_or(FunctionError::MissingMetadata)?;
+    if !engine.eq_ignore_ascii_case(ENGINE_NAME) {
+        return Err(FunctionError::UnknownEngine(engine.to_string()));
+    }
+    let mut name = None;
+    for field in fields {
+        match field.strip_prefix("name=") {
+            Some(value) => name = Some(value.to_string()),
+            None => return Err(FunctionError::InvalidMetadata(field.to_string())),
+        }
+    }
+    let name = name.ok_or(FunctionError::MissingMetadata)?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(FunctionError::InvalidLibraryName);
+    }
+    Ok((name, &code[end + 1..]))
+}
+
+fn host_functions(engine: &Engine) -> Result<Linker<HostState>, wasmi::Error> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(
+        HOST_MODULE,
+        "key",
+        |mut caller: Caller<'_, HostState>, index: i32, ptr: i32, cap: i32| {
+            let key = usize::try_from(index)
+                .ok()
+                .and_then(|i| caller.data().keys.get(i))
+                .map(|key| key.clone().into_bytes());
+            match key {
+                Some(key) => copy_out(&mut caller, &key, ptr, cap),
+                None => Ok(-1),
+            }
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "arg",
+        |mut caller: Caller<'_, HostState>, index: i32, ptr: i32, cap: i32| {
+            let arg = usize::try_from(index)
+                .ok()
+                .and_then(|i| caller.data().args.get(i))
+                .cloned();
+            match arg {
+                Some(arg) => copy_out(&mut caller, &arg, ptr, cap),
+                None => Ok(-1),
+            }
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "get",
+        |mut caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32, ptr: i32, cap: i32| {
+            let key = read_string(&caller, key_ptr, key_len)?;
+            let Ok(Some(value)) = caller.data().backend.get(&key) else {
+                return Ok(-1);
+            };
+            copy_out(&mut caller, &value, ptr, cap)
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "set",
+        |caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32, ptr: i32, len: i32| {
+            let key = read_string(&caller, key_ptr, key_len)?;
+            let value = read(&caller, ptr, len)?;
+            caller.data().backend.set(key, value);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "del",
+        |caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32| {
+            let key = read_string(&caller, key_ptr, key_len)?;
+            Ok(caller.data().backend.unlink(&[key]) as i32)
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "reply",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let reply = read(&caller, ptr, len)?;
+            caller.data_mut().reply = Some(reply);
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
+
+fn memory(caller: &Caller<'_, HostState>) -> Result<wasmi::Memory, wasmi::Error> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("the library exports no memory"))
+}
+
+fn read(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Result<Vec<u8>, wasmi::Error> {
+    let (ptr, len) = match (usize::try_from(ptr), usize::try_from(len)) {
+        (Ok(ptr), Ok(len)) => (ptr, len),
+        _ => return Err(wasmi::Error::new("negative pointer or length")),
+    };
+    let mut buf = vec![0; len];
+    memory(caller)?
+        .read(caller, ptr, &mut buf)
+        .map_err(|e| wasmi::Error::new(e.to_string()))?;
+    Ok(buf)
+}
+
+fn read_string(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, wasmi::Error> {
+    String::from_utf8(read(caller, ptr, len)?).map_err(|_| wasmi::Error::new("key is not UTF-8"))
+}
+
+// copy up to `cap` bytes of `data` to the memory at `ptr`, returns the length of `data`
+fn copy_out(
+    caller: &mut Caller<'_, HostState>,
+    data: &[u8],
+    ptr: i32,
+    cap: i32,
+) -> Result<i32, wasmi::Error> {
+    let (ptr, cap) = match (usize::try_from(ptr), usize::try_from(cap)) {
+        (Ok(ptr), Ok(cap)) => (ptr, cap),
+        _ => return Err(wasmi::Error::new("negative pointer or length")),
+    };
+    let len = i32::try_from(data.len()).map_err(|_| wasmi::Error::new("value too large"))?;
+    memory(caller)?
+        .write(caller, ptr, &data[..data.len().min(cap)])
+        .map_err(|e| wasmi::Error::new(e.to_string()))?;
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a library with its metadata line
+    fn library(name: &str, wat: &str) -> Vec<u8> {
+        let mut code = format!("#!wasm name={}\n", name).into_bytes();
+        code.extend(wat::parse_str(wat).unwrap());
+        code
+    }
+
+    const COPY: &str = r#"
+        (module
+          (import "redis" "key" (func $key (param i32 i32 i32) (result i32)))
+          (import "redis" "get" (func $get (param i32 i32 i32 i32) (result i32)))
+          (import "redis" "set" (func $set (param i32 i32 i32 i32)))
+          (import "redis" "reply" (func $reply (param i32 i32)))
+          (memory (export "memory") 1)
+          ;; copy the value of the first key to the second one and reply with it
+          (func (export "copy") (result i64)
+            (local $src i32) (local $dst i32) (local $len i32)
+            (local.set $src (call $key (i32.const 0) (i32.const 0) (i32.const 256)))
+            (local.set $dst (call $key (i32.const 1) (i32.const 256) (i32.const 256)))
+            (local.set $len (call $get (i32.const 0) (local.get $src) (i32.const 512) (i32.const 512)))
+            (if (i32.lt_s (local.get $len) (i32.const 0)) (then (return (i64.const 0))))
+            (call $set (i32.const 256) (local.get $dst) (i32.const 512) (local.get $len))
+            (call $reply (i32.const 512) (local.get $len))
+            (i64.const 1))
+          (func (export "spin") (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0))
+          (func (export "helper") (param i32) (result i32) (local.get 0)))
+    "#;
+
+    #[test]
+    fn test_function_load_and_call() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        assert_eq!(backend.function_load(&library("lib", COPY), false)?, "lib");
+        assert_eq!(
+            backend.function_list(),
+            vec![(
+                "lib".to_string(),
+                vec!["copy".to_string(), "spin".to_string()]
+            )]
+        );
+
+        backend.set("a".to_string(), "value");
+        let keys = vec!["a".to_string(), "b".to_string()];
+        let ret = backend.fcall("copy", keys.clone(), vec![])?;
+        assert_eq!(ret, BulkString::new("value").into());
+        assert_eq!(backend.get("b").unwrap(), Some("value".into()));
+
+        let keys = vec!["missing".to_string(), "b".to_string()];
+        assert_eq!(backend.fcall("copy", keys, vec![])?, 0.into());
+
+        assert!(matches!(
+            backend.fcall("spin", vec![], vec![]),
+            Err(FunctionError::Runtime { .. })
+        ));
+        assert_eq!(
+            backend.fcall("helper", vec![], vec![]),
+            Err(FunctionError::NoSuchFunction)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_load_errors() {
+        let backend = Backend::new();
+        let code = library("lib", COPY);
+        backend.function_load(&code, false).unwrap();
+        assert_eq!(
+            backend.function_load(&code, false),
+            Err(FunctionError::LibraryExists("lib".to_string()))
+        );
+        assert_eq!(backend.function_load(&code, true), Ok("lib".to_string()));
+        assert_eq!(
+            backend.function_load(&library("other", COPY), false),
+            Err(FunctionError::FunctionExists("copy".to_string()))
+        );
+
+        let no_functions = library("empty", "(module)");
+        assert_eq!(
+            backend.function_load(&no_functions, false),
+            Err(FunctionError::NoFunctions)
+        );
+        assert_eq!(
+            backend.function_load(b"\0asm", false),
+            Err(FunctionError::MissingMetadata)
+        );
+        assert_eq!(
+            backend.function_load(b"#!lua name=lib\n", false),
+            Err(FunctionError::UnknownEngine("lua".to_string()))
+        );
+        assert_eq!(
+            backend.function_load(b"#!wasm name=a-b\n", false),
+            Err(FunctionError::InvalidLibraryName)
+        );
+        assert!(matches!(
+            backend.function_load(b"#!wasm name=lib\nnot wasm", false),
+            Err(FunctionError::Compile(_))
+        ));
+
+        assert_eq!(
+            backend.function_delete("other"),
+            Err(FunctionError::NoSuchLibrary)
+        );
+        assert_eq!(backend.function_delete("lib"), Ok(()));
+        assert_eq!(
+            backend.fcall("copy", vec![], vec![]),
+            Err(FunctionError::NoSuchFunction)
+        );
+        backend.function_load(&code, false).unwrap();
+        backend.function_flush();
+        assert!(backend.function_list().is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use super::{Backend, KeySnapshot};
+use dashmap::DashMap;
+use std::{
+    cmp::Reverse,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Sampled per-key access counters, tracking is disabled until a sample rate is set.
+#[derive(Debug, Default)]
+pub(super) struct AccessCounters {
+    counts: DashMap<String, u64>,
+    // one in this many accesses is counted, 0 disables tracking
+    sample_rate: AtomicU64,
+    accesses: AtomicU64,
+}
+
+impl AccessCounters {
+    pub(super) fn record(&self, key: &str) {
+        let rate = self.sample_rate.load(Ordering::Relaxed);
+        if rate == 0
+            || !self
+                .accesses
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(rate)
+        {
+            return;
+        }
+        match self.counts.get_mut(key) {
+            Some(mut count) => *count += 1,
+            None => *self.counts.entry(key.to_string()).or_default() += 1,
+        }
+    }
+
+    // sampled accesses to a key, 0 when not tracked
+    pub(super) fn count(&self, key: &str) -> u64 {
+        self.counts.get(key).map(|c| *c).unwrap_or_default()
+    }
+
+    pub(super) fn remove(&self, key: &str) {
+        self.counts.remove(key);
+    }
+}
+
+impl Backend {
+    /// Count one in `rate` key accesses, 1 counts every access exactly and 0 stops tracking.
+    ///
+    /// Changing the rate discards the counts collected so far.
+    pub fn set_access_sample_rate(&self, rate: u64) {
+        self.access.sample_rate.store(rate, Ordering::Relaxed);
+        self.access.accesses.store(0, Ordering::Relaxed);
+        self.access.counts.clear();
+    }
+
+    pub fn access_sample_rate(&self) -> u64 {
+        self.access.sample_rate.load(Ordering::Relaxed)
+    }
+
+    /// The most accessed live keys with their estimated access counts, most accessed first.
+    pub fn hotkeys(&self, count: usize) -> Vec<(String, u64)> {
+        let rate = self.access_sample_rate().max(1);
+        // only live keys are counted, but some were deleted or expired since, forget them
+        self.access.counts.retain(|key, _| self.contains(key));
+        let mut hot: Vec<(String, u64)> = self
+            .access
+            .counts
+            .iter()
+            .map(|e| (e.key().clone(), e.value() * rate))
+            .collect();
+        hot.sort_by_key(|(key, count)| (Reverse(*count), key.clone()));
+        hot.truncate(count);
+        hot
+    }
+
+    /// The largest values by estimated size, largest first.
+    pub fn bigkeys(&self, count: usize) -> Vec<KeySnapshot> {
+        let mut big: Vec<KeySnapshot> = self.snapshot_iter().collect();
+        big.sort_by_key(|s| (Reverse(s.size), s.key.clone()));
+        big.truncate(count);
+        big
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hotkeys() {
+        let backend = Backend::new();
+        backend.set("cold".to_string(), "v");
+        backend.set("hot".to_string(), "v");
+        backend.get("hot").unwrap();
+        assert!(backend.hotkeys(10).is_empty());
+
+        backend.set_access_sample_rate(1);
+        for _ in 0..3 {
+            backend.get("hot").unwrap();
+        }
+        backend.get("cold").unwrap();
+        backend.get("missing").unwrap();
+        assert_eq!(
+            backend.hotkeys(10),
+            vec![("hot".to_string(), 3), ("cold".to_string(), 1)]
+        );
+        assert_eq!(backend.hotkeys(1), vec![("hot".to_string(), 3)]);
+
+        // sampled counts are scaled up by the sample rate
+        backend.set_access_sample_rate(2);
+        for _ in 0..4 {
+            backend.get("hot").unwrap();
+        }
+        assert_eq!(backend.hotkeys(10), vec![("hot".to_string(), 4)]);
+    }
+
+    #[test]
+    fn test_bigkeys() {
+        let backend = Backend::new();
+        backend.set("small".to_string(), "v");
+        backend.set("big".to_string(), vec![b'x'; 1000]);
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+
+        let big = backend.bigkeys(2);
+        assert_eq!(big.len(), 2);
+        assert_eq!(big[0].key, "big");
+        assert!(big[0].size > big[1].size);
+    }
+}
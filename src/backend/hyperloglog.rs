@@ -0,0 +1,204 @@
+use super::{Backend, ChangeEvent, Value};
+use bytes::Bytes;
+use dashmap::mapref::entry::Entry;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// header of a string holding a HyperLogLog, followed by one byte per register
+const HLL_MAGIC: &[u8] = b"HYLL";
+// the low bits of a hash select the register
+const HLL_INDEX_BITS: u32 = 14;
+const HLL_REGISTERS: usize = 1 << HLL_INDEX_BITS;
+
+impl Backend {
+    /// Add elements to the HyperLogLog of a key, creating it if needed.
+    ///
+    /// Returns whether the estimated cardinality may have changed, true when the key is created,
+    /// or None if the key holds a value that is not a HyperLogLog.
+    pub fn pfadd(&self, key: String, elements: &[String]) -> Option<bool> {
+        self.access_key(&key);
+        let changed = match self.keyspace.entry(key.clone()) {
+            Entry::Occupied(mut e) => {
+                let Value::String(value) = e.get_mut() else {
+                    return None;
+                };
+                registers(value)?;
+                // the bytes are not copied unless a reader still shares them
+                let mut bytes = Vec::from(std::mem::take(value));
+                let mut changed = false;
+                for element in elements {
+                    changed |= add(&mut bytes[HLL_MAGIC.len()..], element);
+                }
+                *value = bytes.into();
+                changed
+            }
+            Entry::Vacant(e) => {
+                let mut bytes = empty_hll();
+                for element in elements {
+                    add(&mut bytes[HLL_MAGIC.len()..], element);
+                }
+                let value = Value::String(bytes.into());
+                self.created(e.key(), &value);
+                e.insert(value);
+                true
+            }
+        };
+        if changed {
+            // only copy the whole value out if anyone is listening
+            self.notify(|| ChangeEvent::SetString {
+                value: self
+                    .typed::<Bytes>(&key)
+                    .ok()
+                    .flatten()
+                    .map(|v| v.clone())
+                    .unwrap_or_default(),
+                key,
+            });
+        }
+        Some(changed)
+    }
+
+    /// Estimated number of distinct elements added to the union of the HyperLogLogs of keys.
+    ///
+    /// Missing keys are empty. Returns None if a key holds a value that is not a HyperLogLog.
+    pub fn pfcount(&self, keys: &[String]) -> Option<u64> {
+        let registers = self.hll_union(keys.iter())?;
+        Some(estimate(&registers))
+    }
+
+    /// Store the union of the HyperLogLogs of `dest` and `keys` at `dest`, retaining its time
+    /// to live.
+    ///
+    /// Returns None if a key holds a value that is not a HyperLogLog.
+    pub fn pfmerge(&self, dest: String, keys: &[String]) -> Option<()> {
+        let registers = self.hll_union(std::iter::once(&dest).chain(keys))?;
+        let mut value = HLL_MAGIC.to_vec();
+        value.extend_from_slice(&registers);
+        self.set_keepttl(dest, value);
+        Some(())
+    }
+
+    // the register-wise maximum of the HyperLogLogs of keys
+    fn hll_union<'a>(&self, keys: impl Iterator<Item = &'a String>) -> Option<Vec<u8>> {
+        let mut union = vec![0; HLL_REGISTERS];
+        for key in keys {
+            self.access_key(key);
+            let Some(value) = self.keyspace.get(key) else {
+                continue;
+            };
+            let Value::String(value) = value.value() else {
+                return None;
+            };
+            let registers = registers(value)?;
+            for (max, register) in union.iter_mut().zip(registers) {
+                *max = (*max).max(*register);
+            }
+        }
+        Some(union)
+    }
+}
+
+fn empty_hll() -> Vec<u8> {
+    let mut bytes = HLL_MAGIC.to_vec();
+    bytes.resize(HLL_MAGIC.len() + HLL_REGISTERS, 0);
+    bytes
+}
+
+fn registers(bytes: &[u8]) -> Option<&[u8]> {
+    (bytes.len() == HLL_MAGIC.len() + HLL_REGISTERS && bytes.starts_with(HLL_MAGIC))
+        .then(|| &bytes[HLL_MAGIC.len()..])
+}
+
+// returns true if the register of the element grew
+fn add(registers: &mut [u8], element: &str) -> bool {
+    let mut hasher = DefaultHasher::new();
+    element.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let index = (hash as usize) & (HLL_REGISTERS - 1);
+    // position of the first set bit of the remaining bits, the sentinel caps the run of zeros
+    let rest = (hash >> HLL_INDEX_BITS) | (1 << (64 - HLL_INDEX_BITS));
+    let rank = (rest.trailing_zeros() + 1) as u8;
+    if rank > registers[index] {
+        registers[index] = rank;
+        true
+    } else {
+        false
+    }
+}
+
+// raw HyperLogLog estimate, with linear counting for small cardinalities
+fn estimate(registers: &[u8]) -> u64 {
+    let m = HLL_REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let sum: f64 = registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+    let raw = alpha * m * m / sum;
+
+    let zeros = registers.iter().filter(|r| **r == 0).count();
+    if raw <= 2.5 * m && zeros > 0 {
+        (m * (m / zeros as f64).ln()).round() as u64
+    } else {
+        raw.round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|i| format!("element:{}", i)).collect()
+    }
+
+    fn assert_close(estimate: u64, actual: u64) {
+        let error = (estimate as f64 - actual as f64).abs() / actual as f64;
+        assert!(error < 0.03, "estimate {} of {}", estimate, actual);
+    }
+
+    #[test]
+    fn test_pfadd_pfcount() {
+        let backend = Backend::new();
+        assert_eq!(backend.pfadd("hll".to_string(), &[]), Some(true));
+        assert_eq!(backend.pfcount(&["hll".to_string()]), Some(0));
+
+        assert_eq!(
+            backend.pfadd("hll".to_string(), &elements(0..3)),
+            Some(true)
+        );
+        assert_eq!(
+            backend.pfadd("hll".to_string(), &elements(0..3)),
+            Some(false)
+        );
+        assert_eq!(backend.pfcount(&["hll".to_string()]), Some(3));
+
+        backend.pfadd("hll".to_string(), &elements(0..100_000));
+        assert_close(backend.pfcount(&["hll".to_string()]).unwrap(), 100_000);
+        assert_eq!(backend.pfcount(&["missing".to_string()]), Some(0));
+
+        backend.set("string".to_string(), "value");
+        assert_eq!(backend.pfadd("string".to_string(), &elements(0..1)), None);
+        assert_eq!(backend.pfcount(&["string".to_string()]), None);
+    }
+
+    #[test]
+    fn test_pfmerge() {
+        let backend = Backend::new();
+        backend.pfadd("a".to_string(), &elements(0..6_000));
+        backend.pfadd("b".to_string(), &elements(4_000..10_000));
+        let keys = ["a".to_string(), "b".to_string()];
+        assert_close(backend.pfcount(&keys).unwrap(), 10_000);
+
+        backend.pfadd("dest".to_string(), &elements(20_000..30_000));
+        assert_eq!(backend.pfmerge("dest".to_string(), &keys), Some(()));
+        assert_close(backend.pfcount(&["dest".to_string()]).unwrap(), 20_000);
+
+        assert_eq!(backend.pfmerge("new".to_string(), &[]), Some(()));
+        assert_eq!(backend.pfcount(&["new".to_string()]), Some(0));
+
+        backend.set("int".to_string(), "1");
+        assert_eq!(
+            backend.pfmerge("dest".to_string(), &["int".to_string()]),
+            None
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use super::{MemSize, WrongTypeError};
+use serde_json::Value;
+use std::fmt;
+use std::mem::size_of;
+use thiserror::Error;
+
+/// A path into a JSON document, the subset of JSONPath without wildcards, filters or slices.
+///
+/// Both JSONPath (`$.a[0]`) and the legacy RedisJSON syntax (`.a[0]`, `a`) are accepted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    raw: String,
+    segments: Vec<Segment>,
+    legacy: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    // negative indexes count from the end of the array
+    Index(i64),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum JsonError {
+    #[error("ERR Path '{0}' does not exist")]
+    PathMissing(String),
+    #[error("ERR wrong type of path value at '{0}'")]
+    WrongType(String),
+    #[error("ERR new objects must be created at the root")]
+    NotAtRoot,
+    #[error("ERR could not perform this operation on a key that doesn't exist")]
+    KeyMissing,
+    #[error(transparent)]
+    WrongKeyType(#[from] WrongTypeError),
+}
+
+/// Condition of JSON.SET on the existence of the value at the path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonSetMode {
+    Always,
+    IfMissing,
+    IfExists,
+}
+
+impl JsonPath {
+    pub fn root() -> Self {
+        Self {
+            raw: "$".to_string(),
+            segments: Vec::new(),
+            legacy: false,
+        }
+    }
+
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let (legacy, mut rest) = match path.strip_prefix('$') {
+            Some(rest) => (false, rest),
+            None => (true, path),
+        };
+        if legacy && rest == "." {
+            rest = "";
+        } else if legacy && !rest.is_empty() && !rest.starts_with(['.', '[']) {
+            // `a.b` is a shorthand for `.a.b`
+            return Self::parse(&format!(".{}", path)).map(|p| Self {
+                raw: path.to_string(),
+                ..p
+            });
+        }
+
+        let mut segments = Vec::new();
+        let invalid = || format!("invalid JSON path '{}'", path);
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                let field = &after[..end];
+                if field.is_empty() || field == "*" {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Field(field.to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(invalid)?;
+                let inner = after[..end].trim();
+                let segment = match inner.chars().next() {
+                    Some(quote @ ('\'' | '"')) if inner.len() >= 2 && inner.ends_with(quote) => {
+                        Segment::Field(inner[1..inner.len() - 1].to_string())
+                    }
+                    _ => Segment::Index(inner.parse().map_err(|_| invalid())?),
+                };
+                segments.push(segment);
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Ok(Self {
+            raw: path.to_string(),
+            segments,
+            legacy,
+        })
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Whether the path uses the legacy syntax, which changes the shape of some replies.
+    pub fn is_legacy(&self) -> bool {
+        self.legacy
+    }
+
+    pub fn get<'a>(&self, doc: &'a Value) -> Option<&'a Value> {
+        self.segments.iter().try_fold(doc, |v, s| s.get(v))
+    }
+
+    pub fn get_mut<'a>(&self, doc: &'a mut Value) -> Option<&'a mut Value> {
+        self.segments.iter().try_fold(doc, |v, s| s.get_mut(v))
+    }
+
+    /// Set the value at the path, its parent must exist. Returns false if the condition failed.
+    pub fn set(&self, doc: &mut Value, value: Value, mode: JsonSetMode) -> Result<bool, JsonError> {
+        let Some((last, parents)) = self.segments.split_last() else {
+            if mode == JsonSetMode::IfMissing {
+                return Ok(false);
+            }
+            *doc = value;
+            return Ok(true);
+        };
+        let parent = parents
+            .iter()
+            .try_fold(doc, |v, s| s.get_mut(v))
+            .ok_or_else(|| JsonError::PathMissing(self.raw.clone()))?;
+
+        match (last, parent) {
+            (Segment::Field(field), Value::Object(map)) => {
+                let ok = match mode {
+                    JsonSetMode::Always => true,
+                    JsonSetMode::IfMissing => !map.contains_key(field),
+                    JsonSetMode::IfExists => map.contains_key(field),
+                };
+                if ok {
+                    map.insert(field.clone(), value);
+                }
+                Ok(ok)
+            }
+            // arrays only have existing elements to replace
+            (Segment::Index(i), Value::Array(array)) => {
+                let Some(i) = resolve_index(*i, array.len()) else {
+                    return Err(JsonError::PathMissing(self.raw.clone()));
+                };
+                if mode == JsonSetMode::IfMissing {
+                    return Ok(false);
+                }
+                array[i] = value;
+                Ok(true)
+            }
+            _ => Err(JsonError::WrongType(self.raw.clone())),
+        }
+    }
+
+    /// Remove the value at the path, the root can not be removed this way.
+    pub fn delete(&self, doc: &mut Value) -> bool {
+        let Some((last, parents)) = self.segments.split_last() else {
+            return false;
+        };
+        let Some(parent) = parents.iter().try_fold(doc, |v, s| s.get_mut(v)) else {
+            return false;
+        };
+        match (last, parent) {
+            (Segment::Field(field), Value::Object(map)) => map.remove(field).is_some(),
+            (Segment::Index(i), Value::Array(array)) => match resolve_index(*i, array.len()) {
+                Some(i) => {
+                    array.remove(i);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl Segment {
+    fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        match (self, value) {
+            (Segment::Field(field), Value::Object(map)) => map.get(field),
+            (Segment::Index(i), Value::Array(array)) => array.get(resolve_index(*i, array.len())?),
+            _ => None,
+        }
+    }
+
+    fn get_mut<'a>(&self, value: &'a mut Value) -> Option<&'a mut Value> {
+        match (self, value) {
+            (Segment::Field(field), Value::Object(map)) => map.get_mut(field),
+            (Segment::Index(i), Value::Array(array)) => {
+                let i = resolve_index(*i, array.len())?;
+                array.get_mut(i)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn resolve_index(i: i64, len: usize) -> Option<usize> {
+    let i = if i < 0 { len as i64 + i } else { i };
+    (0..len as i64).contains(&i).then_some(i as usize)
+}
+
+impl MemSize for Value {
+    fn mem_size(&self) -> usize {
+        let heap = match self {
+            Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+            Value::String(s) => s.capacity(),
+            Value::Array(array) => {
+                array.capacity() * size_of::<Value>()
+                    + array
+                        .iter()
+                        .map(|v| v.mem_size() - size_of::<Value>())
+                        .sum::<usize>()
+            }
+            Value::Object(map) => map
+                .iter()
+                .map(|(k, v)| k.mem_size() + v.mem_size())
+                .sum::<usize>(),
+        };
+        size_of::<Value>() + heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_path_parse() {
+        let path = JsonPath::parse("$.a['b c'][-1]").unwrap();
+        assert!(!path.is_legacy());
+        assert_eq!(
+            path.segments,
+            vec![
+                Segment::Field("a".to_string()),
+                Segment::Field("b c".to_string()),
+                Segment::Index(-1)
+            ]
+        );
+
+        for legacy in [".a.b", "a.b"] {
+            let path = JsonPath::parse(legacy).unwrap();
+            assert!(path.is_legacy());
+            assert_eq!(path.to_string(), legacy);
+            assert_eq!(
+                path.segments,
+                vec![
+                    Segment::Field("a".to_string()),
+                    Segment::Field("b".to_string())
+                ]
+            );
+        }
+
+        assert!(JsonPath::parse("$").unwrap().is_root());
+        assert!(JsonPath::parse(".").unwrap().is_root());
+        assert!(JsonPath::parse("$.a.*").is_err());
+        assert!(JsonPath::parse("$[x]").is_err());
+        assert!(JsonPath::parse("$.a[0").is_err());
+    }
+
+    #[test]
+    fn test_json_path_get_set_delete() {
+        let mut doc = json!({"a": {"b": [1, 2, 3]}});
+        let path = JsonPath::parse("$.a.b[-1]").unwrap();
+        assert_eq!(path.get(&doc), Some(&json!(3)));
+
+        assert_eq!(
+            path.set(&mut doc, json!(4), JsonSetMode::IfMissing),
+            Ok(false)
+        );
+        assert_eq!(path.set(&mut doc, json!(4), JsonSetMode::Always), Ok(true));
+        let path = JsonPath::parse("$.a.c").unwrap();
+        assert_eq!(
+            path.set(&mut doc, json!("x"), JsonSetMode::IfExists),
+            Ok(false)
+        );
+        assert_eq!(
+            path.set(&mut doc, json!("x"), JsonSetMode::IfMissing),
+            Ok(true)
+        );
+        assert_eq!(doc, json!({"a": {"b": [1, 2, 4], "c": "x"}}));
+
+        let path = JsonPath::parse("$.x.y").unwrap();
+        assert_eq!(
+            path.set(&mut doc, json!(1), JsonSetMode::Always),
+            Err(JsonError::PathMissing("$.x.y".to_string()))
+        );
+        let path = JsonPath::parse("$.a.c.d").unwrap();
+        assert_eq!(
+            path.set(&mut doc, json!(1), JsonSetMode::Always),
+            Err(JsonError::WrongType("$.a.c.d".to_string()))
+        );
+
+        assert!(JsonPath::parse("$.a.b[0]").unwrap().delete(&mut doc));
+        assert!(JsonPath::parse("$.a.c").unwrap().delete(&mut doc));
+        assert!(!JsonPath::parse("$.a.c").unwrap().delete(&mut doc));
+        assert_eq!(doc, json!({"a": {"b": [2, 4]}}));
+    }
+}
@@ -0,0 +1,116 @@
+use super::{Backend, ChangeEvent, Value};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+// values made of more elements than this are dropped on the lazyfree thread by UNLINK
+const LAZYFREE_THRESHOLD: usize = 64;
+
+type Garbage = Vec<Box<dyn Send>>;
+
+/// Background thread dropping the large values removed by UNLINK, started on first use.
+#[derive(Debug, Default)]
+pub(super) struct LazyFree {
+    sender: Mutex<Option<mpsc::Sender<Garbage>>>,
+}
+
+impl LazyFree {
+    fn free(&self, garbage: Garbage) {
+        let mut sender = self.sender.lock().unwrap();
+        let sender = sender.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<Garbage>();
+            // the thread exits once the backend, holding the sender, is dropped
+            thread::spawn(move || receiver.into_iter().for_each(drop));
+            sender
+        });
+        // sending only fails if the thread panicked, the values are then dropped here
+        let _ = sender.send(garbage);
+    }
+}
+
+impl Backend {
+    /// Remove keys along with their time to live, returns the number of keys removed.
+    ///
+    /// The keys are gone as soon as this returns, but the values made of many elements are
+    /// dropped on a background thread so that freeing them does not delay the caller.
+    pub fn unlink(&self, keys: &[String]) -> usize {
+        let mut removed = 0;
+        let mut garbage: Garbage = Vec::new();
+        let mut effort = 0;
+        for key in keys {
+            self.expire_if_needed(key);
+            let Some(value) = self.take_value(key) else {
+                continue;
+            };
+            effort += match &value {
+                Value::Hash(hash) => hash.len(),
+                Value::Set(set) => set.len(),
+                Value::TimeSeries(series) => series.len(),
+                _ => 1,
+            };
+            garbage.push(Box::new(value));
+
+            removed += 1;
+            self.expire.remove(key);
+            self.access.remove(key);
+            self.access_times.remove(key);
+            self.notify(|| ChangeEvent::Deleted { key: key.clone() });
+        }
+
+        if effort > LAZYFREE_THRESHOLD {
+            self.lazyfree.free(garbage);
+        }
+        removed
+    }
+
+    /// Count an access to each of the keys, returns the number of keys that exist.
+    ///
+    /// The access resets the idle time of each live key, as any read of the key would.
+    pub fn touch(&self, keys: &[String]) -> usize {
+        keys.iter()
+            .filter(|key| {
+                self.access_key(key);
+                self.contains(key)
+            })
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::now_ms;
+
+    #[test]
+    fn test_unlink() {
+        let backend = Backend::new();
+        backend.set("string".to_string(), "value");
+        backend.expire_at("string", i64::MAX);
+        for i in 0..1000 {
+            backend.sadd("set".to_string(), i.to_string()).unwrap();
+        }
+
+        let keys = ["string", "set", "missing"].map(String::from);
+        assert_eq!(backend.unlink(&keys), 2);
+        assert!(!backend.exists("string"));
+        assert!(!backend.exists("set"));
+        assert_eq!(backend.expire_time("string"), None);
+        assert_eq!(backend.unlink(&keys), 0);
+    }
+
+    #[test]
+    fn test_touch() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        backend.set("expired".to_string(), "value");
+        backend.expire_at("expired", 1);
+
+        let keys = ["key", "expired", "missing", "key"].map(String::from);
+        assert_eq!(backend.touch(&keys), 2);
+
+        // touching an idle key resets its idle time
+        backend.access_times.insert("key", now_ms() - 5_000);
+        assert_eq!(backend.object_idletime("key"), Some(5));
+        assert_eq!(backend.touch(&["key".to_string()]), 1);
+        assert_eq!(backend.object_idletime("key"), Some(0));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    BulkString, RespArray, RespFrame, RespMap, RespNull, RespSet, SimpleError, SimpleString,
+};
+use bytes::Bytes;
+use dashmap::{DashMap, DashSet};
+use std::mem::{size_of, size_of_val};
+
+// estimated bookkeeping bytes of a hash table entry besides the key and value themselves
+pub(super) const HASH_ENTRY_OVERHEAD: usize = 16;
+// estimated bookkeeping bytes of a btree entry besides the key and value themselves
+const BTREE_ENTRY_OVERHEAD: usize = 8;
+
+/// Deep size estimate of a value, in bytes, including the value itself.
+pub trait MemSize {
+    fn mem_size(&self) -> usize;
+}
+
+impl MemSize for String {
+    fn mem_size(&self) -> usize {
+        size_of::<String>() + self.capacity()
+    }
+}
+
+impl MemSize for Vec<u8> {
+    fn mem_size(&self) -> usize {
+        size_of::<Vec<u8>>() + self.capacity()
+    }
+}
+
+impl MemSize for Bytes {
+    fn mem_size(&self) -> usize {
+        size_of::<Bytes>() + self.len()
+    }
+}
+
+impl MemSize for RespFrame {
+    fn mem_size(&self) -> usize {
+        // the payload is stored inline in the frame, only add what it owns on the heap
+        let heap = match self {
+            RespFrame::SimpleString(v) => v.mem_size() - size_of_val(v),
+            RespFrame::Error(v) => v.mem_size() - size_of_val(v),
+            RespFrame::BulkString(v) => v.mem_size() - size_of_val(v),
+            RespFrame::Array(v) => v.mem_size() - size_of_val(v),
+            RespFrame::Map(v) => v.mem_size() - size_of_val(v),
+            RespFrame::Set(v) => v.mem_size() - size_of_val(v),
+            RespFrame::Null(_)
+            | RespFrame::Integer(_)
+            | RespFrame::Boolean(_)
+            | RespFrame::Double(_) => 0,
+        };
+        size_of::<RespFrame>() + heap
+    }
+}
+
+impl MemSize for SimpleString {
+    fn mem_size(&self) -> usize {
+        self.0.mem_size()
+    }
+}
+
+impl MemSize for SimpleError {
+    fn mem_size(&self) -> usize {
+        self.0.mem_size()
+    }
+}
+
+impl MemSize for BulkString {
+    fn mem_size(&self) -> usize {
+        match self.0 {
+            Some(ref v) => size_of::<BulkString>() + v.capacity(),
+            None => size_of::<BulkString>(),
+        }
+    }
+}
+
+impl MemSize for RespNull {
+    fn mem_size(&self) -> usize {
+        size_of::<RespNull>()
+    }
+}
+
+impl MemSize for RespArray {
+    fn mem_size(&self) -> usize {
+        match self.0 {
+            Some(ref v) => {
+                let spare = (v.capacity() - v.len()) * size_of::<RespFrame>();
+                size_of::<RespArray>() + spare + v.iter().map(|f| f.mem_size()).sum::<usize>()
+            }
+            None => size_of::<RespArray>(),
+        }
+    }
+}
+
+impl MemSize for RespMap {
+    fn mem_size(&self) -> usize {
+        size_of::<RespMap>()
+            + self
+                .iter()
+                .map(|(k, v)| k.mem_size() + v.mem_size() + BTREE_ENTRY_OVERHEAD)
+                .sum::<usize>()
+    }
+}
+
+impl MemSize for RespSet {
+    fn mem_size(&self) -> usize {
+        let spare = (self.capacity() - self.len()) * size_of::<RespFrame>();
+        size_of::<RespSet>() + spare + self.iter().map(|f| f.mem_size()).sum::<usize>()
+    }
+}
+
+impl MemSize for DashMap<String, RespFrame> {
+    fn mem_size(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .iter()
+                .map(|e| e.key().mem_size() + e.value().mem_size() + HASH_ENTRY_OVERHEAD)
+                .sum::<usize>()
+    }
+}
+
+impl MemSize for DashSet<String> {
+    fn mem_size(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .iter()
+                .map(|e| e.key().mem_size() + HASH_ENTRY_OVERHEAD)
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_within(actual: usize, expected: usize, tolerance: usize) {
+        assert!(
+            actual.abs_diff(expected) <= tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_scalar_frame_mem_size() {
+        let frame: RespFrame = 123.into();
+        assert_eq!(frame.mem_size(), size_of::<RespFrame>());
+
+        let frame: RespFrame = RespNull.into();
+        assert_eq!(frame.mem_size(), size_of::<RespFrame>());
+    }
+
+    #[test]
+    fn test_string_frame_mem_size() {
+        let frame: RespFrame = BulkString::new(vec![b'x'; 1000]).into();
+        assert_within(frame.mem_size(), 1000, 64);
+
+        let frame: RespFrame = SimpleString::new("x".repeat(1000)).into();
+        assert_within(frame.mem_size(), 1000, 64);
+    }
+
+    #[test]
+    fn test_nested_frame_mem_size() {
+        let item: RespFrame = BulkString::new(vec![b'x'; 100]).into();
+        let frame: RespFrame = RespArray::new(vec![item.clone(); 10]).into();
+        assert_within(frame.mem_size(), 10 * item.mem_size(), 64);
+
+        let mut map = RespMap::new();
+        for i in 0..10 {
+            map.insert(format!("key{}", i), item.clone());
+        }
+        let frame: RespFrame = map.into();
+        assert_within(frame.mem_size(), 10 * (item.mem_size() + 32), 10 * 32);
+    }
+
+    #[test]
+    fn test_hash_and_set_mem_size() {
+        let hash: DashMap<String, RespFrame> = DashMap::new();
+        for i in 0..100 {
+            hash.insert(
+                format!("field{:03}", i),
+                BulkString::new(vec![b'x'; 100]).into(),
+            );
+        }
+        assert_within(hash.mem_size(), 100 * 200, 100 * 50);
+
+        let set: DashSet<String> = DashSet::new();
+        for i in 0..100 {
+            set.insert(format!("member{:03}", i));
+        }
+        assert_within(set.mem_size(), 100 * 50, 100 * 20);
+    }
+}
@@ -0,0 +1,225 @@
+use super::{value::Value, Backend, KeyType};
+use crate::MemSize;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+/// Memory used by the keyspace, as reported by MEMORY STATS and INFO memory.
+///
+/// The keyspace is a single database, db 0, these are its figures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryStats {
+    /// Number of keys.
+    pub keys: usize,
+    /// Bytes used by the keys and values of each type, in the order of [`KeyType::ALL`].
+    pub types: Vec<(KeyType, usize)>,
+    /// Bytes used by the times to live.
+    pub expires: usize,
+    /// Bytes used by the access times of OBJECT IDLETIME and LRU eviction.
+    pub access_times: usize,
+}
+
+/// Bytes used by the keys and values of each type, in the order of [`KeyType::ALL`].
+///
+/// The counters are maintained as values are stored, changed in place and removed.
+#[derive(Debug)]
+pub(super) struct UsedMemory {
+    types: Vec<AtomicI64>,
+}
+
+impl Default for UsedMemory {
+    fn default() -> Self {
+        Self {
+            types: KeyType::ALL.iter().map(|_| AtomicI64::new(0)).collect(),
+        }
+    }
+}
+
+impl UsedMemory {
+    fn add(&self, kind: KeyType, bytes: i64) {
+        if let Some(i) = KeyType::ALL.iter().position(|k| *k == kind) {
+            self.types[i].fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+}
+
+impl MemoryStats {
+    /// Bytes used by the whole dataset.
+    pub fn total(&self) -> usize {
+        self.types.iter().map(|(_, bytes)| bytes).sum::<usize>() + self.expires + self.access_times
+    }
+}
+
+impl Backend {
+    /// The memory used by the keys and values of each type, as estimated by MEMORY USAGE.
+    pub fn memory_stats(&self) -> MemoryStats {
+        let types = KeyType::ALL
+            .iter()
+            .zip(&self.used_memory.types)
+            .map(|(kind, bytes)| (*kind, bytes.load(Ordering::Relaxed).max(0) as usize))
+            .collect();
+        MemoryStats {
+            keys: self.keyspace.len(),
+            types,
+            expires: self.expire.len() * size_of::<i64>(),
+            access_times: self.access_times.mem_size(),
+        }
+    }
+
+    /// Approximate number of bytes used by the keys and their values, see
+    /// [`Backend::memory_stats`].
+    pub fn used_memory(&self) -> usize {
+        self.memory_stats().total()
+    }
+
+    // account for a value stored under a key
+    pub(super) fn account_insert(&self, key: &str, value: &Value) {
+        self.used_memory
+            .add(value.kind(), entry_size(key, value) as i64);
+    }
+
+    // account for a value removed from a key
+    pub(super) fn account_remove(&self, key: &str, value: &Value) {
+        self.used_memory
+            .add(value.kind(), -(entry_size(key, value) as i64));
+    }
+
+    // account for a value of a type changed in place from `before` to `after` bytes
+    pub(super) fn account_resize(&self, kind: KeyType, before: usize, after: usize) {
+        self.used_memory.add(kind, after as i64 - before as i64);
+    }
+}
+
+// the bytes used by a key and its value, as reported by MEMORY USAGE without the time to live
+fn entry_size(key: &str, value: &Value) -> usize {
+    size_of::<String>() + key.len() + value.mem_size()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_memory_stats() {
+        let backend = Backend::new();
+        backend.set("string".to_string(), vec![b'x'; 1000]);
+        backend
+            .hset("hash".to_string(), "field".to_string(), 1.into())
+            .unwrap();
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        backend.expire_at("string", i64::MAX);
+
+        let stats = backend.memory_stats();
+        assert_eq!(stats.keys, 3);
+        assert_eq!(stats.types.len(), KeyType::ALL.len());
+        assert_eq!(stats.expires, size_of::<i64>());
+        assert_eq!(
+            bytes_of(&stats, KeyType::String) + stats.expires,
+            backend.memory_usage("string").unwrap()
+        );
+        assert!(bytes_of(&stats, KeyType::Hash) > 0);
+        assert!(bytes_of(&stats, KeyType::Set) > 0);
+        assert_eq!(bytes_of(&stats, KeyType::Bloom), 0);
+        assert!(stats.access_times > 0);
+        assert_eq!(stats.total(), backend.used_memory());
+
+        backend.unlink(&["string".to_string()]);
+        let before = stats.access_times;
+        let stats = backend.memory_stats();
+        assert_eq!(stats.keys, 2);
+        assert_eq!(stats.expires, 0);
+        assert_eq!(
+            stats.access_times,
+            before - (size_of::<String>() + "string".len() + size_of::<i64>())
+        );
+        assert_eq!(bytes_of(&stats, KeyType::String), 0);
+    }
+
+    #[test]
+    fn test_memory_follows_writes() {
+        let backend = Backend::new();
+        backend.set("string".to_string(), "short");
+        backend.set("string".to_string(), vec![b'x'; 1000]);
+        backend.setbit("bits".to_string(), 7, true);
+        backend.setbit("bits".to_string(), 8000, true);
+        backend.pfadd("hll".to_string(), &["a".to_string()]);
+        for i in 0..10 {
+            let field = format!("field{}", i);
+            backend.hset("hash".to_string(), field, i.into()).unwrap();
+            backend
+                .sadd("set".to_string(), format!("member{}", i))
+                .unwrap();
+        }
+        backend
+            .hset(
+                "hash".to_string(),
+                "field0".to_string(),
+                BulkString::new(vec![b'x'; 100]).into(),
+            )
+            .unwrap();
+        backend
+            .hsetnx("hash".to_string(), "other".to_string(), 1.into())
+            .unwrap();
+        backend.bf_add("bloom".to_string(), "item").unwrap();
+        backend.ts_add("series".to_string(), 1, 1.0, 0).unwrap();
+        backend.ts_add("series".to_string(), 2, 2.0, 0).unwrap();
+        assert_eq!(backend.memory_stats(), recount(&backend));
+
+        assert!(backend.copy("hash", "hash2".to_string(), false));
+        let payload = backend.dump("set").unwrap().unwrap();
+        backend
+            .restore("string".to_string(), None, &payload, true)
+            .unwrap();
+        backend.unlink(&["bits".to_string(), "series".to_string()]);
+        backend.expire_at("hll", 1);
+        assert_eq!(backend.memory_stats(), recount(&backend));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_memory_follows_json_writes() {
+        use crate::backend::{JsonPath, JsonSetMode};
+        use serde_json::json;
+
+        let backend = Backend::new();
+        let root = JsonPath::root();
+        let list = JsonPath::parse("$.list").unwrap();
+        backend
+            .json_set(
+                "doc".to_string(),
+                &root,
+                json!({"list": []}),
+                JsonSetMode::Always,
+            )
+            .unwrap();
+        backend
+            .json_arrappend("doc", &list, vec![json!("x".repeat(100)); 10])
+            .unwrap();
+        assert_eq!(backend.memory_stats(), recount(&backend));
+
+        backend.json_del("doc", &list).unwrap();
+        assert_eq!(backend.memory_stats(), recount(&backend));
+    }
+
+    // the stats measured from scratch over the whole keyspace
+    fn recount(backend: &Backend) -> MemoryStats {
+        let mut types: Vec<_> = KeyType::ALL.iter().map(|kind| (*kind, 0)).collect();
+        for entry in backend.keyspace.iter() {
+            let kind = entry.value().kind();
+            let bytes = entry_size(entry.key(), entry.value());
+            types.iter_mut().find(|(k, _)| *k == kind).unwrap().1 += bytes;
+        }
+        MemoryStats {
+            keys: backend.keyspace.len(),
+            types,
+            expires: backend.expire.len() * size_of::<i64>(),
+            access_times: backend.access_times.recount(),
+        }
+    }
+
+    fn bytes_of(stats: &MemoryStats, kind: KeyType) -> usize {
+        stats.types.iter().find(|(k, _)| *k == kind).unwrap().1
+    }
+}
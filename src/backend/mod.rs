@@ -1,16 +1,98 @@
-use crate::RespFrame;
-use dashmap::{DashMap, DashSet};
+mod bigkeys;
+mod bitmap;
+mod bloom;
+mod changes;
+mod config;
+mod config_file;
+mod copy;
+mod cuckoo;
+mod dump;
+mod eviction;
+mod expire;
+#[cfg(feature = "wasm")]
+mod function;
+mod hotkeys;
+mod hyperloglog;
+#[cfg(feature = "json")]
+mod json;
+mod lazyfree;
+mod mem_size;
+mod memory;
+mod object;
+mod pubsub;
+mod scan;
+mod shutdown;
+mod sketch;
+mod sliding;
+mod snapshot;
+mod stats;
+mod timeseries;
+mod transaction;
+mod value;
+
+use crate::{glob::glob_match, RespFrame};
+use bytes::Bytes;
+use dashmap::{mapref::entry::Entry, DashMap, DashSet};
+use std::collections::HashSet;
+use std::mem::size_of;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc, Mutex,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+pub use bigkeys::{BigKeysReport, TypeStats};
+pub use bitmap::{BitUnit, BitwiseOp, MAX_BIT_OFFSET};
+pub use bloom::BloomFilter;
+pub use changes::ChangeEvent;
+pub use config::{ConfigError, ConfigKind};
+pub use config_file::ServerConfig;
+pub use cuckoo::CuckooFilter;
+pub use dump::DumpError;
+pub use eviction::EvictionError;
+pub use expire::ExpireCondition;
+#[cfg(feature = "wasm")]
+pub use function::FunctionError;
+#[cfg(feature = "json")]
+pub use json::{JsonError, JsonPath, JsonSetMode};
+pub use mem_size::MemSize;
+use mem_size::HASH_ENTRY_OVERHEAD;
+pub use memory::MemoryStats;
+pub use sketch::{CountMinSketch, TopK};
+pub use snapshot::{KeySnapshot, KeyType, SnapshotIter};
+pub use stats::{LatencyHistogram, ServerStats, WarmupStats};
+pub use timeseries::{Aggregation, TimeSeries, TimeSeriesError};
+pub use value::WrongTypeError;
+
+use value::Value;
 
 #[derive(Debug, Clone)]
 pub struct Backend(Arc<BackendInner>);
 
 #[derive(Debug)]
 pub struct BackendInner {
-    map: DashMap<String, RespFrame>,
-    hmap: DashMap<String, DashMap<String, RespFrame>>,
-    hset: DashMap<String, DashSet<String>>,
+    keyspace: DashMap<String, Value>,
+    // absolute expiration time of a key, in unix milliseconds
+    expire: DashMap<String, i64>,
+    changes: broadcast::Sender<ChangeEvent>,
+    access: hotkeys::AccessCounters,
+    access_times: object::AccessTimes,
+    bigkeys: Mutex<BigKeysReport>,
+    // percent by which relative times to live are randomly extended
+    ttl_jitter: AtomicU8,
+    sliding: sliding::SlidingTtl,
+    stats: ServerStats,
+    config: config::ConfigValues,
+    lazyfree: lazyfree::LazyFree,
+    used_memory: memory::UsedMemory,
+    exec_lock: transaction::ExecLock,
+    shard_channels: pubsub::ShardChannels,
+    #[cfg(feature = "wasm")]
+    libraries: function::Libraries,
+    shutdown: shutdown::ShutdownSignal,
 }
 
 impl Deref for Backend {
@@ -24,9 +106,23 @@ impl Deref for Backend {
 impl Default for BackendInner {
     fn default() -> Self {
         Self {
-            map: DashMap::new(),
-            hmap: DashMap::new(),
-            hset: DashMap::new(),
+            keyspace: DashMap::new(),
+            expire: DashMap::new(),
+            changes: broadcast::channel(changes::CHANGE_CHANNEL_CAPACITY).0,
+            access: hotkeys::AccessCounters::default(),
+            access_times: object::AccessTimes::default(),
+            bigkeys: Mutex::new(BigKeysReport::default()),
+            ttl_jitter: AtomicU8::new(0),
+            sliding: sliding::SlidingTtl::default(),
+            stats: ServerStats::default(),
+            config: config::ConfigValues::default(),
+            lazyfree: lazyfree::LazyFree::default(),
+            used_memory: memory::UsedMemory::default(),
+            exec_lock: transaction::ExecLock::default(),
+            shard_channels: pubsub::ShardChannels::default(),
+            #[cfg(feature = "wasm")]
+            libraries: function::Libraries::default(),
+            shutdown: shutdown::ShutdownSignal::default(),
         }
     }
 }
@@ -42,42 +138,839 @@ impl Backend {
         Self::default()
     }
 
-    pub fn get(&self, key: &str) -> Option<RespFrame> {
-        self.map.get(key).map(|v| v.value().clone())
+    /// Subscribe to the changes applied to the keyspace from now on.
+    ///
+    /// A subscriber falling more than a channel capacity behind receives a lagged error and
+    /// misses the oldest events.
+    pub fn subscribe_changes(&self) -> BroadcastStream<ChangeEvent> {
+        BroadcastStream::new(self.changes.subscribe())
+    }
+
+    /// Latency statistics of the server serving this backend.
+    pub fn stats(&self) -> &ServerStats {
+        &self.stats
+    }
+
+    /// The string value of a key, fails if the key holds a value that is not a string.
+    pub fn get(&self, key: &str) -> Result<Option<Bytes>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self.typed::<Bytes>(key)?.map(|v| v.clone()))
+    }
+
+    /// Set a string value, replacing any value of any type and discarding any previous time to
+    /// live of the key.
+    pub fn set(&self, key: String, value: impl Into<Bytes>) {
+        let value = value.into();
+        self.access_key(&key);
+        self.expire.remove(&key);
+        self.notify(|| ChangeEvent::SetString {
+            key: key.clone(),
+            value: value.clone(),
+        });
+        self.insert_value(key, Value::String(value));
+    }
+
+    /// Set a string value, replacing any value of any type but retaining the time to live of the key.
+    pub fn set_keepttl(&self, key: String, value: impl Into<Bytes>) {
+        let value = value.into();
+        self.access_key(&key);
+        self.notify(|| ChangeEvent::SetString {
+            key: key.clone(),
+            value: value.clone(),
+        });
+        self.insert_value(key, Value::String(value));
+    }
+
+    /// Whether a key exists, without counting an access to it.
+    pub fn exists(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        self.contains(key)
+    }
+
+    // whether any value is stored under a key, regardless of its expiration
+    fn contains(&self, key: &str) -> bool {
+        self.keyspace.contains_key(key)
+    }
+
+    /// Set the absolute expiration time (unix milliseconds) of an existing key.
+    pub fn expire_at(&self, key: &str, at: i64) -> bool {
+        if !self.exists(key) {
+            return false;
+        }
+        self.expire.insert(key.to_string(), at);
+        true
+    }
+
+    /// Randomly extend the relative times to live set by commands by up to `percent` percent.
+    pub fn set_ttl_jitter(&self, percent: u8) {
+        self.ttl_jitter.store(percent.min(100), Ordering::Relaxed);
+    }
+
+    pub fn ttl_jitter(&self) -> u8 {
+        self.ttl_jitter.load(Ordering::Relaxed)
+    }
+
+    /// Remove the time to live of a key, returns true if a timeout was removed.
+    pub fn persist(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        self.expire.remove(key).is_some()
+    }
+
+    /// Absolute expiration time (unix milliseconds) of a key, if any.
+    pub fn expire_time(&self, key: &str) -> Option<i64> {
+        self.expire_if_needed(key);
+        self.expire.get(key).map(|v| *v.value())
+    }
+
+    /// All live keys matching a glob pattern, sorted.
+    pub fn keys(&self, pattern: &str) -> Vec<String> {
+        let pattern = pattern.as_bytes();
+        let mut keys: Vec<String> = self
+            .keyspace
+            .iter()
+            .map(|e| e.key().clone())
+            .filter(|k| glob_match(pattern, k.as_bytes()))
+            .collect();
+        keys.sort();
+
+        let now = now_ms();
+        keys.retain(|k| self.expire.get(k).map(|at| *at > now).unwrap_or(true));
+        keys
+    }
+
+    /// Estimated number of bytes used by a key and its value, None if the key does not exist.
+    pub fn memory_usage(&self, key: &str) -> Option<usize> {
+        self.expire_if_needed(key);
+        self.value_size(key)
+    }
+
+    // the size reported by MEMORY USAGE, without counting an access to the key
+    fn value_size(&self, key: &str) -> Option<usize> {
+        let mut size = self.keyspace.get(key).map(|v| v.mem_size());
+        if self.expire.contains_key(key) {
+            size = size.map(|s| s + size_of::<i64>());
+        }
+        size.map(|s| s + size_of::<String>() + key.len())
+    }
+
+    /// The value of a hash field, fails if the key holds a value that is not a hash, as do the
+    /// other hash and set reads for their type.
+    pub fn hget(&self, key: &str, field: &str) -> Result<Option<RespFrame>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<DashMap<String, RespFrame>>(key)?
+            .and_then(|m| m.get(field).map(|v| v.value().clone())))
+    }
+
+    /// Set a hash field, fails if the key holds a value that is not a hash.
+    pub fn hset(&self, key: String, field: String, value: RespFrame) -> Result<(), WrongTypeError> {
+        self.access_key(&key);
+        let inner = self.typed_or_insert_with(key.clone(), DashMap::new)?;
+        self.notify(|| ChangeEvent::HashFieldSet {
+            key,
+            field: field.clone(),
+            value: value.clone(),
+        });
+        // a replaced field keeps its name, only the value changes
+        let (field_size, value_size) = (field.mem_size(), value.mem_size());
+        match inner.insert(field, value) {
+            Some(old) => self.account_resize(KeyType::Hash, old.mem_size(), value_size),
+            None => self.account_resize(
+                KeyType::Hash,
+                0,
+                field_size + value_size + HASH_ENTRY_OVERHEAD,
+            ),
+        }
+        Ok(())
     }
 
-    pub fn set(&self, key: String, value: RespFrame) {
-        self.map.insert(key, value);
+    /// Set a hash field only if it does not exist yet, returns true if the field was set.
+    pub fn hsetnx(
+        &self,
+        key: String,
+        field: String,
+        value: RespFrame,
+    ) -> Result<bool, WrongTypeError> {
+        self.access_key(&key);
+        let inner = self.typed_or_insert_with(key.clone(), DashMap::new)?;
+        let inserted = match inner.entry(field) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                self.notify(|| ChangeEvent::HashFieldSet {
+                    key,
+                    field: entry.key().clone(),
+                    value: value.clone(),
+                });
+                let size = entry.key().mem_size() + value.mem_size() + HASH_ENTRY_OVERHEAD;
+                self.account_resize(KeyType::Hash, 0, size);
+                entry.insert(value);
+                true
+            }
+        };
+        Ok(inserted)
     }
 
-    pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
-        self.hmap
-            .get(key)
-            .and_then(|m| m.get(field).map(|v| v.value().clone()))
+    pub fn hexists(&self, key: &str, field: &str) -> Result<bool, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<DashMap<String, RespFrame>>(key)?
+            .is_some_and(|m| m.contains_key(field)))
     }
 
-    pub fn hset(&self, key: String, field: String, value: RespFrame) {
-        let inner = self.hmap.entry(key).or_default();
-        inner.insert(field, value);
+    pub fn hlen(&self, key: &str) -> Result<usize, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<DashMap<String, RespFrame>>(key)?
+            .map(|m| m.len())
+            .unwrap_or(0))
     }
 
-    pub fn hgetall(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
-        self.hmap.get(key).map(|m| m.clone())
+    pub fn hgetall(&self, key: &str) -> Result<Option<DashMap<String, RespFrame>>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<DashMap<String, RespFrame>>(key)?
+            .map(|m| m.clone()))
     }
 
-    pub fn sadd(&self, key: String, member: String) -> usize {
-        let inner = self.hset.entry(key).or_default();
+    /// Add a member to a set, returns the number of members added or fails if the key holds a
+    /// value that is not a set.
+    pub fn sadd(&self, key: String, member: String) -> Result<usize, WrongTypeError> {
+        self.access_key(&key);
+        let inner = self.typed_or_insert_with(key.clone(), DashSet::new)?;
         if inner.contains(&member) {
-            return 0;
+            return Ok(0);
         }
+        self.notify(|| ChangeEvent::SetMemberAdded {
+            key,
+            member: member.clone(),
+        });
+        self.account_resize(KeyType::Set, 0, member.mem_size() + HASH_ENTRY_OVERHEAD);
         inner.insert(member);
-        1
+        Ok(1)
+    }
+
+    pub fn scard(&self, key: &str) -> Result<usize, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<DashSet<String>>(key)?
+            .map(|s| s.len())
+            .unwrap_or(0))
+    }
+
+    pub fn smembers(&self, key: &str) -> Result<Option<DashSet<String>>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self.typed::<DashSet<String>>(key)?.map(|s| s.clone()))
+    }
+
+    pub fn sunion(&self, keys: &[String]) -> Result<HashSet<String>, WrongTypeError> {
+        let mut ret = HashSet::new();
+        for key in keys {
+            if let Some(set) = self.smembers(key)? {
+                ret.extend(set);
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn sinter(&self, keys: &[String]) -> Result<HashSet<String>, WrongTypeError> {
+        let mut sets = Vec::with_capacity(keys.len());
+        for key in keys {
+            match self.smembers(key)? {
+                Some(set) => sets.push(set),
+                // the intersection with a missing (empty) set is empty
+                None => return Ok(HashSet::new()),
+            }
+        }
+        let (first, rest) = match sets.split_first() {
+            Some(split) => split,
+            None => return Ok(HashSet::new()),
+        };
+        Ok(first
+            .iter()
+            .filter(|m| rest.iter().all(|s| s.contains(m.key())))
+            .map(|m| m.key().clone())
+            .collect())
+    }
+
+    /// Cardinality of the intersection, stops counting at `limit` unless it is 0.
+    pub fn sintercard(&self, keys: &[String], limit: usize) -> Result<usize, WrongTypeError> {
+        let mut sets = Vec::with_capacity(keys.len());
+        for key in keys {
+            match self.smembers(key)? {
+                Some(set) => sets.push(set),
+                None => return Ok(0),
+            }
+        }
+        // probe the members of the smallest set against the others
+        sets.sort_by_key(|s| s.len());
+        let (first, rest) = match sets.split_first() {
+            Some(split) => split,
+            None => return Ok(0),
+        };
+
+        let mut count = 0;
+        for member in first.iter() {
+            if rest.iter().all(|s| s.contains(member.key())) {
+                count += 1;
+                if count == limit {
+                    break;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    pub fn sdiff(&self, keys: &[String]) -> Result<HashSet<String>, WrongTypeError> {
+        let (first, rest) = match keys.split_first() {
+            Some(split) => split,
+            None => return Ok(HashSet::new()),
+        };
+        let mut ret: HashSet<String> = self
+            .smembers(first)?
+            .map(|s| s.into_iter().collect())
+            .unwrap_or_default();
+        for key in rest {
+            if let Some(set) = self.smembers(key)? {
+                ret.retain(|m| !set.contains(m));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Store a set under a key, replacing any value of any type. An empty set removes the key.
+    pub fn sstore(&self, key: String, members: HashSet<String>) -> usize {
+        self.expire.remove(&key);
+        if self.remove_value(&key) {
+            self.notify(|| ChangeEvent::Deleted { key: key.clone() });
+        }
+
+        let len = members.len();
+        if len > 0 {
+            for member in &members {
+                self.notify(|| ChangeEvent::SetMemberAdded {
+                    key: key.clone(),
+                    member: member.clone(),
+                });
+            }
+            self.insert_value(key, Value::Set(members.into_iter().collect()));
+        }
+        len
+    }
+
+    pub fn sismember(&self, key: &str, member: &str) -> Result<bool, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<DashSet<String>>(key)?
+            .is_some_and(|s| s.contains(member)))
+    }
+
+    /// Create an empty bloom filter, returns false if the key already exists.
+    pub fn bf_reserve(&self, key: String, filter: BloomFilter) -> bool {
+        self.access_key(&key);
+        self.insert_new(key, Value::Bloom(filter))
+    }
+
+    /// Add an item to a bloom filter, created with the default options if missing.
+    ///
+    /// Returns Some(false) if the item may already be present and None if the filter is full.
+    pub fn bf_add(&self, key: String, item: &str) -> Result<Option<bool>, WrongTypeError> {
+        self.access_key(&key);
+        let mut filter = self.typed_or_insert_with(key, || {
+            BloomFilter::new(
+                BloomFilter::DEFAULT_ERROR_RATE,
+                BloomFilter::DEFAULT_CAPACITY,
+                Some(BloomFilter::DEFAULT_EXPANSION),
+            )
+        })?;
+        Ok(self.update(&mut *filter, |filter| filter.add(item)))
+    }
+
+    pub fn bf_exists(&self, key: &str, item: &str) -> Result<bool, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<BloomFilter>(key)?
+            .is_some_and(|f| f.contains(item)))
+    }
+
+    /// Create an empty cuckoo filter, returns false if the key already exists.
+    pub fn cf_reserve(&self, key: String, filter: CuckooFilter) -> bool {
+        self.access_key(&key);
+        self.insert_new(key, Value::Cuckoo(filter))
+    }
+
+    /// Add an item to a cuckoo filter, created with the default options if missing.
+    ///
+    /// Returns false if the filter is full.
+    pub fn cf_add(&self, key: String, item: &str) -> Result<bool, WrongTypeError> {
+        self.access_key(&key);
+        let mut filter = self.typed_or_insert_with(key, || {
+            CuckooFilter::new(
+                CuckooFilter::DEFAULT_CAPACITY,
+                CuckooFilter::DEFAULT_BUCKET_SIZE,
+                CuckooFilter::DEFAULT_MAX_ITERATIONS,
+                CuckooFilter::DEFAULT_EXPANSION,
+            )
+        })?;
+        Ok(self.update(&mut *filter, |filter| filter.add(item)))
+    }
+
+    pub fn cf_exists(&self, key: &str, item: &str) -> Result<bool, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<CuckooFilter>(key)?
+            .is_some_and(|f| f.contains(item)))
+    }
+
+    /// Remove one occurrence of an item from a cuckoo filter, None if there is no such filter.
+    pub fn cf_del(&self, key: &str, item: &str) -> Result<Option<bool>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed_mut::<CuckooFilter>(key)?
+            .map(|mut f| self.update(&mut *f, |f| f.remove(item))))
     }
 
-    pub fn sismember(&self, key: &str, member: &str) -> bool {
-        self.hset
-            .get(key)
-            .map(|s| s.contains(member))
-            .unwrap_or(false)
+    /// Create a count-min sketch, returns false if the key already exists.
+    pub fn cms_init(&self, key: String, sketch: CountMinSketch) -> bool {
+        self.access_key(&key);
+        self.insert_new(key, Value::CountMin(sketch))
+    }
+
+    /// Increase the counts of items, returns their new estimated counts or None if there is no
+    /// such sketch.
+    pub fn cms_incrby(
+        &self,
+        key: &str,
+        items: &[(String, u64)],
+    ) -> Result<Option<Vec<u64>>, WrongTypeError> {
+        self.access_key(key);
+        let Some(mut sketch) = self.typed_mut::<CountMinSketch>(key)? else {
+            return Ok(None);
+        };
+        Ok(Some(self.update(&mut *sketch, |sketch| {
+            items
+                .iter()
+                .map(|(item, by)| sketch.increment(item, *by))
+                .collect()
+        })))
+    }
+
+    /// Estimated counts of items, None if there is no such sketch.
+    pub fn cms_query(
+        &self,
+        key: &str,
+        items: &[String],
+    ) -> Result<Option<Vec<u64>>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<CountMinSketch>(key)?
+            .map(|sketch| items.iter().map(|item| sketch.query(item)).collect()))
+    }
+
+    /// The (width, depth, total count) of a count-min sketch.
+    pub fn cms_info(&self, key: &str) -> Result<Option<(usize, usize, u64)>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<CountMinSketch>(key)?
+            .map(|s| (s.width(), s.depth(), s.count())))
+    }
+
+    /// Create a top-k, returns false if the key already exists.
+    pub fn topk_reserve(&self, key: String, topk: TopK) -> bool {
+        self.access_key(&key);
+        self.insert_new(key, Value::TopK(topk))
+    }
+
+    /// Count items, returns the item each one expelled from the top list or None if there is no
+    /// such top-k.
+    pub fn topk_add(
+        &self,
+        key: &str,
+        items: &[String],
+    ) -> Result<Option<Vec<Option<String>>>, WrongTypeError> {
+        self.access_key(key);
+        let Some(mut topk) = self.typed_mut::<TopK>(key)? else {
+            return Ok(None);
+        };
+        Ok(Some(self.update(&mut *topk, |topk| {
+            items.iter().map(|item| topk.add(item)).collect()
+        })))
+    }
+
+    /// Whether items are in the top list, None if there is no such top-k.
+    pub fn topk_query(
+        &self,
+        key: &str,
+        items: &[String],
+    ) -> Result<Option<Vec<bool>>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<TopK>(key)?
+            .map(|topk| items.iter().map(|item| topk.contains(item)).collect()))
+    }
+
+    /// The heavy hitters with their estimated count, highest first.
+    pub fn topk_list(&self, key: &str) -> Result<Option<Vec<(String, u64)>>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self.typed::<TopK>(key)?.map(|t| t.list().to_vec()))
+    }
+
+    /// The (k, width, depth, decay) of a top-k.
+    pub fn topk_info(
+        &self,
+        key: &str,
+    ) -> Result<Option<(usize, usize, usize, f64)>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<TopK>(key)?
+            .map(|t| (t.k(), t.width(), t.depth(), t.decay())))
+    }
+
+    /// Create an empty time series, returns false if the key already exists.
+    pub fn ts_create(&self, key: String, retention: i64) -> bool {
+        self.access_key(&key);
+        self.insert_new(key, Value::TimeSeries(TimeSeries::new(retention)))
+    }
+
+    /// Append a sample to a time series, created with the given retention if missing.
+    ///
+    /// Buckets closed by the downsampling rules of the series are appended to their destination.
+    pub fn ts_add(
+        &self,
+        key: String,
+        ts: i64,
+        value: f64,
+        retention: i64,
+    ) -> Result<i64, TimeSeriesError> {
+        self.access_key(&key);
+        let mut series = self.typed_or_insert_with(key, || TimeSeries::new(retention))?;
+        let closed = self.update(&mut *series, |series| series.add(ts, value))?;
+        drop(series);
+        // the source entry is released, destinations may live in the same shard
+        for (dest, ts, value) in closed {
+            if let Ok(Some(mut series)) = self.typed_mut::<TimeSeries>(&dest) {
+                // a destination has no rules of its own, nothing cascades
+                let _ = self.update(&mut *series, |series| series.add(ts, value));
+            }
+        }
+        Ok(ts)
+    }
+
+    /// The latest sample of a time series, None if there is no such series.
+    pub fn ts_get(&self, key: &str) -> Result<Option<Option<(i64, f64)>>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self.typed::<TimeSeries>(key)?.map(|s| s.last()))
+    }
+
+    /// Samples of a time series within [from, to], optionally aggregated in buckets of the
+    /// given duration. None if there is no such series.
+    pub fn ts_range(
+        &self,
+        key: &str,
+        from: i64,
+        to: i64,
+        aggregation: Option<(Aggregation, i64)>,
+    ) -> Result<Option<Vec<(i64, f64)>>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<TimeSeries>(key)?
+            .map(|series| match aggregation {
+                Some((aggregation, bucket)) => {
+                    series.range_aggregated(from, to, aggregation, bucket)
+                }
+                None => series.range(from, to).collect(),
+            }))
+    }
+
+    /// Downsample a time series into another one.
+    pub fn ts_createrule(
+        &self,
+        src: &str,
+        dest: &str,
+        aggregation: Aggregation,
+        bucket: i64,
+    ) -> Result<(), TimeSeriesError> {
+        self.access_key(src);
+        self.access_key(dest);
+        // only hold one entry at a time, both keys may live in the same shard
+        self.typed::<TimeSeries>(src)?
+            .ok_or(TimeSeriesError::KeyMissing)?
+            .check_rule_source(src, dest)?;
+        self.typed::<TimeSeries>(dest)?
+            .ok_or(TimeSeriesError::KeyMissing)?
+            .check_rule_destination()?;
+
+        if let Some(mut series) = self.typed_mut::<TimeSeries>(src)? {
+            self.update(&mut *series, |series| {
+                series.add_rule(dest.to_string(), aggregation, bucket)
+            });
+        }
+        if let Some(mut series) = self.typed_mut::<TimeSeries>(dest)? {
+            self.update(&mut *series, |series| series.set_source(src.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Set the value at a path of a JSON document, a new document can only be set at the root.
+    ///
+    /// Returns false if the value was not set because of the mode.
+    #[cfg(feature = "json")]
+    pub fn json_set(
+        &self,
+        key: String,
+        path: &JsonPath,
+        value: serde_json::Value,
+        mode: JsonSetMode,
+    ) -> Result<bool, JsonError> {
+        self.access_key(&key);
+        match self.keyspace.entry(key) {
+            // the whole document is measured again, a path may replace any part of it
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                Value::Json(doc) => self.update(doc, |doc| path.set(doc, value, mode)),
+                _ => Err(WrongTypeError.into()),
+            },
+            Entry::Vacant(_) if !path.is_root() => Err(JsonError::NotAtRoot),
+            Entry::Vacant(_) if mode == JsonSetMode::IfExists => Ok(false),
+            Entry::Vacant(entry) => {
+                let value = Value::Json(value);
+                self.created(entry.key(), &value);
+                entry.insert(value);
+                Ok(true)
+            }
+        }
+    }
+
+    /// The values at the paths of a JSON document, None if there is no such document.
+    #[cfg(feature = "json")]
+    pub fn json_get(
+        &self,
+        key: &str,
+        paths: &[JsonPath],
+    ) -> Result<Option<Vec<Option<serde_json::Value>>>, WrongTypeError> {
+        self.access_key(key);
+        Ok(self
+            .typed::<serde_json::Value>(key)?
+            .map(|doc| paths.iter().map(|p| p.get(&doc).cloned()).collect()))
+    }
+
+    /// Remove the value at a path of a JSON document, the root removes the whole document.
+    #[cfg(feature = "json")]
+    pub fn json_del(&self, key: &str, path: &JsonPath) -> Result<usize, WrongTypeError> {
+        self.access_key(key);
+        if path.is_root() {
+            if self.typed::<serde_json::Value>(key)?.is_none() {
+                return Ok(0);
+            }
+            self.expire.remove(key);
+            self.remove_value(key);
+            self.notify(|| ChangeEvent::Deleted {
+                key: key.to_string(),
+            });
+            return Ok(1);
+        }
+        Ok(self
+            .typed_mut::<serde_json::Value>(key)?
+            .map(|mut doc| self.update(&mut *doc, |doc| path.delete(doc)) as usize)
+            .unwrap_or(0))
+    }
+
+    /// Append values to the array at a path of a JSON document, returns its new length.
+    #[cfg(feature = "json")]
+    pub fn json_arrappend(
+        &self,
+        key: &str,
+        path: &JsonPath,
+        values: Vec<serde_json::Value>,
+    ) -> Result<usize, JsonError> {
+        self.access_key(key);
+        let mut doc = self
+            .typed_mut::<serde_json::Value>(key)?
+            .ok_or(JsonError::KeyMissing)?;
+        self.update(&mut *doc, |doc| {
+            let target = path
+                .get_mut(doc)
+                .ok_or_else(|| JsonError::PathMissing(path.to_string()))?;
+            let array = target
+                .as_array_mut()
+                .ok_or_else(|| JsonError::WrongType(path.to_string()))?;
+            array.extend(values);
+            Ok(array.len())
+        })
+    }
+
+    // lazily remove a key whose time to live has elapsed, without counting an access to it
+    fn expire_if_needed(&self, key: &str) {
+        if self
+            .expire
+            .remove_if(key, |_, at| *at <= now_ms())
+            .is_some()
+        {
+            self.remove_value(key);
+            self.access.remove(key);
+            self.notify(|| ChangeEvent::Expired {
+                key: key.to_string(),
+            });
+        }
+    }
+
+    // lazily expire a key a command reads or writes, an access to a live key counts towards the
+    // hot keys, resets its idle time and slides its expiration
+    fn access_key(&self, key: &str) {
+        self.expire_if_needed(key);
+        if self.contains(key) {
+            self.access.record(key);
+            self.touch_access_time(key);
+            self.slide_expiration(key);
+        }
+    }
+
+    // publish a change, the event is only built if anyone is listening
+    fn notify(&self, event: impl FnOnce() -> ChangeEvent) {
+        if self.changes.receiver_count() > 0 {
+            // sending only fails if every subscriber went away meanwhile
+            let _ = self.changes.send(event());
+        }
+    }
+
+    // remove the value stored under a key, returns true if there was one
+    fn remove_value(&self, key: &str) -> bool {
+        self.access_times.remove(key);
+        self.take_value(key).is_some()
+    }
+
+    // remove and return the value stored under a key
+    fn take_value(&self, key: &str) -> Option<Value> {
+        let (key, value) = self.keyspace.remove(key)?;
+        self.account_remove(&key, &value);
+        Some(value)
+    }
+
+    // account for a key created with a value, a new key counts as accessed now
+    fn created(&self, key: &str, value: &Value) {
+        self.account_insert(key, value);
+        self.touch_access_time(key);
+    }
+
+    // store a value under a key, replacing and returning any previous value
+    fn insert_value(&self, key: String, value: Value) -> Option<Value> {
+        match self.keyspace.entry(key) {
+            Entry::Occupied(mut entry) => {
+                self.account_remove(entry.key(), entry.get());
+                self.account_insert(entry.key(), &value);
+                Some(entry.insert(value))
+            }
+            Entry::Vacant(entry) => {
+                self.created(entry.key(), &value);
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    // store a value under a key that does not exist yet, returns false if it exists
+    fn insert_new(&self, key: String, value: Value) -> bool {
+        match self.keyspace.entry(key) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                self.created(entry.key(), &value);
+                entry.insert(value);
+                true
+            }
+        }
+    }
+}
+
+/// Current unix time in milliseconds.
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_subscribe_changes() -> Result<()> {
+        let backend = Backend::new();
+        // changes before subscribing are not delivered
+        backend.set("before".to_string(), "value");
+
+        let mut changes = backend.subscribe_changes();
+        backend.set("key".to_string(), "value");
+        backend
+            .hset("hash".to_string(), "field".to_string(), 1.into())
+            .unwrap();
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        backend.sstore("key".to_string(), HashSet::new());
+        backend.expire_at("hash", 1);
+        backend.hget("hash", "field").unwrap();
+
+        let expected = vec![
+            ChangeEvent::SetString {
+                key: "key".to_string(),
+                value: "value".into(),
+            },
+            ChangeEvent::HashFieldSet {
+                key: "hash".to_string(),
+                field: "field".to_string(),
+                value: 1.into(),
+            },
+            ChangeEvent::SetMemberAdded {
+                key: "set".to_string(),
+                member: "member".to_string(),
+            },
+            ChangeEvent::Deleted {
+                key: "key".to_string(),
+            },
+            ChangeEvent::Expired {
+                key: "hash".to_string(),
+            },
+        ];
+        for event in expected {
+            assert_eq!(changes.next().await.unwrap()?, event);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_json_del_root_notifies() -> Result<()> {
+        let backend = Backend::new();
+        backend.json_set(
+            "doc".to_string(),
+            &JsonPath::root(),
+            serde_json::json!({"a": 1}),
+            JsonSetMode::Always,
+        )?;
+
+        let mut changes = backend.subscribe_changes();
+        assert_eq!(backend.json_del("doc", &JsonPath::root()), Ok(1));
+        assert_eq!(backend.json_del("doc", &JsonPath::root()), Ok(0));
+        backend.set("key".to_string(), "value");
+
+        let expected = vec![
+            ChangeEvent::Deleted {
+                key: "doc".to_string(),
+            },
+            ChangeEvent::SetString {
+                key: "key".to_string(),
+                value: "value".into(),
+            },
+        ];
+        for event in expected {
+            assert_eq!(changes.next().await.unwrap()?, event);
+        }
+
+        Ok(())
     }
 }
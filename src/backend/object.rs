@@ -0,0 +1,158 @@
+use super::{now_ms, Backend, Value};
+use dashmap::DashMap;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Time of the last access to each live key, in unix milliseconds.
+#[derive(Debug, Default)]
+pub(super) struct AccessTimes {
+    times: DashMap<String, i64>,
+    // bytes used by the keys and times of the map, counted in the used memory
+    bytes: AtomicUsize,
+}
+
+impl AccessTimes {
+    pub(super) fn get(&self, key: &str) -> Option<i64> {
+        self.times.get(key).map(|at| *at)
+    }
+
+    pub(super) fn insert(&self, key: &str, at: i64) {
+        match self.times.get_mut(key) {
+            Some(mut time) => *time = at,
+            None => {
+                if self.times.insert(key.to_string(), at).is_none() {
+                    self.bytes.fetch_add(entry_size(key), Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    pub(super) fn remove(&self, key: &str) {
+        if self.times.remove(key).is_some() {
+            self.bytes.fetch_sub(entry_size(key), Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn mem_size(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    // the bytes used measured from scratch, to check the maintained count against
+    #[cfg(test)]
+    pub(super) fn recount(&self) -> usize {
+        self.times.iter().map(|entry| entry_size(entry.key())).sum()
+    }
+}
+
+// the bytes used by the access time of a key, the key is stored apart from the keyspace's copy
+fn entry_size(key: &str) -> usize {
+    size_of::<String>() + key.len() + size_of::<i64>()
+}
+
+impl Backend {
+    // record an access to a live key or its creation, the time is removed along with the key
+    pub(super) fn touch_access_time(&self, key: &str) {
+        self.access_times.insert(key, now_ms());
+    }
+
+    /// The internal representation of the value of a key, as reported by OBJECT ENCODING.
+    ///
+    /// Integers are "int" and other strings "raw", hashes and sets are always "hashtable" and
+    /// module types are "raw". Does not count as an access to the key.
+    pub fn object_encoding(&self, key: &str) -> Option<&'static str> {
+        self.expire_if_needed(key);
+        let encoding = match self.keyspace.get(key)?.value() {
+            Value::String(bytes) if is_integer(bytes) => "int",
+            Value::Hash(_) | Value::Set(_) => "hashtable",
+            _ => "raw",
+        };
+        Some(encoding)
+    }
+
+    /// The number of references to the value of a key, as reported by OBJECT REFCOUNT.
+    ///
+    /// Values are never shared between keys, so this is always 1 for a live key. Does not count
+    /// as an access to the key.
+    pub fn object_refcount(&self, key: &str) -> Option<i64> {
+        self.expire_if_needed(key);
+        self.contains(key).then_some(1)
+    }
+
+    /// Seconds since the last access to a key, as reported by OBJECT IDLETIME.
+    ///
+    /// A key is idle since it was created if it was never accessed since. Does not count as an
+    /// access to the key.
+    pub fn object_idletime(&self, key: &str) -> Option<i64> {
+        self.expire_if_needed(key);
+        if !self.contains(key) {
+            return None;
+        }
+        let now = now_ms();
+        let at = self.access_times.get(key).unwrap_or(now);
+        Some((now - at).max(0) / 1000)
+    }
+}
+
+// whether a string is the canonical decimal form of a 64 bit integer, as redis encodes them
+fn is_integer(bytes: &[u8]) -> bool {
+    bytes.len() <= 20
+        && std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .is_some_and(|i| i.to_string().as_bytes() == bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_encoding() {
+        let backend = Backend::new();
+        backend.set("int".to_string(), "42");
+        backend.set("string".to_string(), "value");
+        backend
+            .hset("hash".to_string(), "field".to_string(), 1.into())
+            .unwrap();
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        backend.set("expired".to_string(), "1");
+        backend.expire_at("expired", 1);
+
+        assert_eq!(backend.object_encoding("int"), Some("int"));
+        assert_eq!(backend.object_encoding("string"), Some("raw"));
+        assert_eq!(backend.object_encoding("hash"), Some("hashtable"));
+        assert_eq!(backend.object_encoding("set"), Some("hashtable"));
+        assert_eq!(backend.object_encoding("expired"), None);
+        assert_eq!(backend.object_encoding("missing"), None);
+    }
+
+    #[test]
+    fn test_object_idletime() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        assert_eq!(backend.object_idletime("key"), Some(0));
+        assert_eq!(backend.object_idletime("missing"), None);
+
+        backend.access_times.insert("key", now_ms() - 5_000);
+        assert_eq!(backend.object_idletime("key"), Some(5));
+        backend.get("key").unwrap();
+        assert_eq!(backend.object_idletime("key"), Some(0));
+
+        // expired keys forget their access time
+        backend.expire_at("key", 1);
+        backend.get("key").unwrap();
+        assert!(!backend.access_times.times.contains_key("key"));
+    }
+
+    #[test]
+    fn test_access_times_of_missing_keys_are_not_kept() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        backend.get("missing").unwrap();
+        assert_eq!(backend.object_idletime("missing"), None);
+        assert!(backend.access_times.times.contains_key("key"));
+        assert!(!backend.access_times.times.contains_key("missing"));
+    }
+}
@@ -0,0 +1,80 @@
+use super::Backend;
+use crate::{BulkString, RespArray, RespFrame};
+use std::{collections::HashMap, sync::Mutex};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Subscribers of the shard channels, by channel then by connection id.
+#[derive(Debug, Default)]
+pub(super) struct ShardChannels {
+    channels: Mutex<HashMap<String, HashMap<u64, UnboundedSender<RespFrame>>>>,
+}
+
+impl Backend {
+    /// Deliver the messages published to a shard channel to `sender` until unsubscribed.
+    pub(crate) fn ssubscribe(&self, channel: &str, id: u64, sender: UnboundedSender<RespFrame>) {
+        let mut channels = self.shard_channels.channels.lock().unwrap();
+        channels
+            .entry(channel.to_string())
+            .or_default()
+            .insert(id, sender);
+    }
+
+    pub(crate) fn sunsubscribe(&self, channel: &str, id: u64) {
+        let mut channels = self.shard_channels.channels.lock().unwrap();
+        if let Some(subscribers) = channels.get_mut(channel) {
+            subscribers.remove(&id);
+            if subscribers.is_empty() {
+                channels.remove(channel);
+            }
+        }
+    }
+
+    /// Publish a message to a shard channel, returns the number of subscribers it was sent to.
+    pub fn spublish(&self, channel: &str, message: &[u8]) -> usize {
+        let channels = self.shard_channels.channels.lock().unwrap();
+        let Some(subscribers) = channels.get(channel) else {
+            return 0;
+        };
+        let frame: RespFrame = RespArray::new(vec![
+            BulkString::new("smessage").into(),
+            BulkString::new(channel).into(),
+            BulkString::new(message).into(),
+        ])
+        .into();
+        // sending only fails if the connection is closing, it unsubscribes meanwhile
+        subscribers
+            .values()
+            .filter(|sender| sender.send(frame.clone()).is_ok())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_spublish() {
+        let backend = Backend::new();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        backend.ssubscribe("news", 1, sender.clone());
+        backend.ssubscribe("news", 2, sender);
+
+        assert_eq!(backend.spublish("news", b"hello"), 2);
+        assert_eq!(backend.spublish("other", b"hello"), 0);
+        let expected: RespFrame = RespArray::new(vec![
+            BulkString::new("smessage").into(),
+            BulkString::new("news").into(),
+            BulkString::new("hello").into(),
+        ])
+        .into();
+        assert_eq!(receiver.try_recv(), Ok(expected));
+
+        backend.sunsubscribe("news", 1);
+        assert_eq!(backend.spublish("news", b"hello"), 1);
+        backend.sunsubscribe("news", 2);
+        assert_eq!(backend.spublish("news", b"hello"), 0);
+        assert!(backend.shard_channels.channels.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use super::{now_ms, Backend, KeyType};
+use crate::glob::glob_match;
+
+// a cursor packs the shard of the keyspace and the bucket of the shard's hash table to resume
+// at, so a call starts right where the previous one stopped instead of walking the shard again
+const BUCKET_BITS: u32 = 32;
+
+// keys of a shard with their type
+type ScanBatch = Vec<(String, KeyType)>;
+
+impl Backend {
+    /// Incrementally iterate over the live keys, examining at most `count` keys per call.
+    ///
+    /// Start with cursor 0 and pass the returned cursor to the next call until it is 0 again.
+    /// Keys whose name does not match the glob `pattern` or whose value is not of type `kind`
+    /// are examined but not returned, so a call may return fewer keys than `count`, or none.
+    /// A key present during the whole iteration is returned at least once as long as its shard
+    /// is not resized meanwhile.
+    pub fn scan(
+        &self,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&str>,
+        kind: Option<KeyType>,
+    ) -> (u64, Vec<String>) {
+        let (mut shard, mut bucket) = decode_cursor(cursor);
+        let mut examined = 0;
+        let mut keys = Vec::new();
+        let now = now_ms();
+
+        while examined < count.max(1) {
+            let Some((batch, next)) = self.scan_shard(shard, bucket, count.max(1) - examined)
+            else {
+                return (0, keys);
+            };
+
+            examined += batch.len();
+            match next {
+                Some(next) => bucket = next,
+                None => (shard, bucket) = (shard + 1, 0),
+            }
+
+            // filter after the shard lock is released
+            keys.extend(
+                batch
+                    .into_iter()
+                    .filter(|(key, key_kind)| {
+                        pattern.is_none_or(|p| glob_match(p.as_bytes(), key.as_bytes()))
+                            && kind.is_none_or(|k| k == *key_kind)
+                            && self.expire.get(key).is_none_or(|at| *at > now)
+                    })
+                    .map(|(key, _)| key),
+            );
+        }
+
+        if shard >= self.keyspace.shards().len() {
+            (0, keys)
+        } else {
+            (encode_cursor(shard, bucket), keys)
+        }
+    }
+
+    // up to `limit` keys of a shard of the keyspace with their type from the full buckets
+    // starting at `bucket`, and the bucket to resume at, None once the end of the shard is
+    // reached; None past the last shard
+    fn scan_shard(
+        &self,
+        shard: usize,
+        mut bucket: usize,
+        limit: usize,
+    ) -> Option<(ScanBatch, Option<usize>)> {
+        let shard = self.keyspace.shards().get(shard)?.read();
+        let raw = shard.raw_table();
+        let mut keys = Vec::new();
+        while bucket < raw.buckets() && keys.len() < limit {
+            // SAFETY: the index is below the number of buckets, and the shard is locked for
+            // reading while the entry is cloned out of its bucket
+            unsafe {
+                if raw.is_bucket_full(bucket) {
+                    let (key, value) = raw.bucket(bucket).as_ref();
+                    keys.push((key.clone(), value.get().kind()));
+                }
+            }
+            bucket += 1;
+        }
+        Some((keys, (bucket < raw.buckets()).then_some(bucket)))
+    }
+}
+
+fn encode_cursor(shard: usize, bucket: usize) -> u64 {
+    ((shard as u64) << BUCKET_BITS) | bucket as u64
+}
+
+fn decode_cursor(cursor: u64) -> (usize, usize) {
+    (
+        (cursor >> BUCKET_BITS) as usize,
+        (cursor & ((1 << BUCKET_BITS) - 1)) as usize,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_all(backend: &Backend, count: usize, kind: Option<KeyType>) -> Vec<String> {
+        let (mut cursor, mut keys) = (0, Vec::new());
+        loop {
+            let (next, batch) = backend.scan(cursor, count, None, kind);
+            assert!(batch.len() <= count);
+            keys.extend(batch);
+            if next == 0 {
+                keys.sort();
+                return keys;
+            }
+            cursor = next;
+        }
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        assert_eq!(decode_cursor(encode_cursor(17, 1234)), (17, 1234));
+        assert_eq!(decode_cursor(0), (0, 0));
+    }
+
+    #[test]
+    fn test_scan() {
+        let backend = Backend::new();
+        for i in 0..100 {
+            backend.set(format!("key{:03}", i), "v");
+        }
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        backend.sstore("key000".to_string(), ["member".to_string()].into());
+        backend.set("expired".to_string(), "v");
+        backend.expire_at("expired", 1);
+
+        let keys = scan_all(&backend, 7, None);
+        assert_eq!(keys.len(), 101);
+        assert_eq!(keys[0], "key000");
+        assert_eq!(keys[100], "set");
+
+        assert_eq!(
+            scan_all(&backend, 3, Some(KeyType::Set)),
+            vec!["key000", "set"]
+        );
+        assert!(scan_all(&backend, 3, Some(KeyType::Hash)).is_empty());
+
+        let (cursor, keys) = backend.scan(0, 1000, Some("key01?"), None);
+        assert_eq!(cursor, 0);
+        assert_eq!(keys.len(), 10);
+    }
+
+    #[test]
+    fn test_scan_survives_deletions() {
+        let backend = Backend::new();
+        for i in 0..200 {
+            backend.set(format!("key{:03}", i), "v");
+        }
+
+        // deleting the keys already returned does not make the cursor skip the others
+        let (mut cursor, mut seen) = (0, Vec::new());
+        loop {
+            let (next, batch) = backend.scan(cursor, 10, None, None);
+            backend.unlink(&batch);
+            seen.extend(batch);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 200);
+    }
+}
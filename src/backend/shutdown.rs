@@ -0,0 +1,42 @@
+use super::Backend;
+use tokio::sync::watch;
+
+/// Request to stop the server serving a backend, as made by SHUTDOWN.
+#[derive(Debug)]
+pub(super) struct ShutdownSignal(watch::Sender<bool>);
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        Self(watch::channel(false).0)
+    }
+}
+
+impl Backend {
+    /// Ask the server to stop accepting and serving connections.
+    pub fn shutdown(&self) {
+        self.shutdown.0.send_replace(true);
+    }
+
+    /// Wait until [`Backend::shutdown`] is called, returns at once if it already was.
+    pub async fn shutdown_requested(&self) {
+        let mut requested = self.shutdown.0.subscribe();
+        // the sender lives as long as the backend, waiting cannot fail
+        let _ = requested.wait_for(|requested| *requested).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_shutdown_requested() {
+        let backend = Backend::new();
+        let waiting = tokio::time::timeout(Duration::from_millis(10), backend.shutdown_requested());
+        assert!(waiting.await.is_err());
+
+        backend.shutdown();
+        backend.shutdown_requested().await;
+    }
+}
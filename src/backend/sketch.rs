@@ -0,0 +1,200 @@
+use super::MemSize;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+
+/// A count-min sketch, estimates the frequency of items without ever undercounting.
+#[derive(Debug, Clone)]
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    // `depth` rows of `width` counters
+    counters: Vec<u64>,
+    // total of all the increments
+    count: u64,
+}
+
+/// Heavy hitters, the `k` items with the highest estimated frequency.
+///
+/// Frequencies are estimated with a count-min sketch, the decay of RedisBloom's HeavyKeeper is
+/// only kept to report it back.
+#[derive(Debug, Clone)]
+pub struct TopK {
+    k: usize,
+    decay: f64,
+    sketch: CountMinSketch,
+    // heavy hitters with their estimated count, highest first
+    top: Vec<(String, u64)>,
+}
+
+impl CountMinSketch {
+    pub fn new(width: usize, depth: usize) -> Self {
+        let (width, depth) = (width.max(1), depth.max(1));
+        Self {
+            width,
+            depth,
+            counters: vec![0; width * depth],
+            count: 0,
+        }
+    }
+
+    /// Size the sketch for an over-estimation of at most `error` (a fraction of the total count)
+    /// with the given probability of exceeding it.
+    pub fn with_error(error: f64, probability: f64) -> Self {
+        let width = (2.0 / error).ceil() as usize;
+        let depth = (probability.log10() / 0.5f64.log10()).ceil() as usize;
+        Self::new(width, depth)
+    }
+
+    /// Increase the count of an item, returns its new estimated count.
+    pub fn increment(&mut self, item: &str, by: u64) -> u64 {
+        let mut estimate = u64::MAX;
+        for row in 0..self.depth {
+            let i = self.index(row, item);
+            self.counters[i] = self.counters[i].saturating_add(by);
+            estimate = estimate.min(self.counters[i]);
+        }
+        self.count = self.count.saturating_add(by);
+        estimate
+    }
+
+    /// Estimated count of an item.
+    pub fn query(&self, item: &str) -> u64 {
+        (0..self.depth)
+            .map(|row| self.counters[self.index(row, item)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Total of all the increments.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    fn index(&self, row: usize, item: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        row.hash(&mut hasher);
+        item.hash(&mut hasher);
+        row * self.width + (hasher.finish() % self.width as u64) as usize
+    }
+}
+
+impl TopK {
+    pub const DEFAULT_WIDTH: usize = 8;
+    pub const DEFAULT_DEPTH: usize = 7;
+    pub const DEFAULT_DECAY: f64 = 0.9;
+
+    pub fn new(k: usize, width: usize, depth: usize, decay: f64) -> Self {
+        Self {
+            k: k.max(1),
+            decay,
+            sketch: CountMinSketch::new(width, depth),
+            top: Vec::new(),
+        }
+    }
+
+    /// Count an occurrence of an item, returns the item expelled from the top list, if any.
+    pub fn add(&mut self, item: &str) -> Option<String> {
+        let count = self.sketch.increment(item, 1);
+
+        let mut expelled = None;
+        if let Some(entry) = self.top.iter_mut().find(|(i, _)| i == item) {
+            entry.1 = count;
+        } else if self.top.len() < self.k {
+            self.top.push((item.to_string(), count));
+        } else if self.top.last().map(|(_, c)| *c < count).unwrap_or(false) {
+            let (old, _) = self.top.pop().expect("the top list is full");
+            self.top.push((item.to_string(), count));
+            expelled = Some(old);
+        }
+        // the list is tiny, keep it simply sorted
+        self.top.sort_by_key(|(_, count)| Reverse(*count));
+        expelled
+    }
+
+    /// Whether an item is currently in the top list.
+    pub fn contains(&self, item: &str) -> bool {
+        self.top.iter().any(|(i, _)| i == item)
+    }
+
+    /// The heavy hitters with their estimated count, highest first.
+    pub fn list(&self) -> &[(String, u64)] {
+        &self.top
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn width(&self) -> usize {
+        self.sketch.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.sketch.depth
+    }
+
+    pub fn decay(&self) -> f64 {
+        self.decay
+    }
+}
+
+impl MemSize for CountMinSketch {
+    fn mem_size(&self) -> usize {
+        size_of::<Self>() + self.counters.capacity() * size_of::<u64>()
+    }
+}
+
+impl MemSize for TopK {
+    fn mem_size(&self) -> usize {
+        // the sketch is stored inline, only add its counters
+        let counters = self.sketch.counters.capacity() * size_of::<u64>();
+        let top = self.top.capacity() * size_of::<(String, u64)>()
+            + self.top.iter().map(|(i, _)| i.capacity()).sum::<usize>();
+        size_of::<Self>() + counters + top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_min_sketch() {
+        let mut sketch = CountMinSketch::with_error(0.001, 0.01);
+        assert_eq!((sketch.width(), sketch.depth()), (2000, 7));
+
+        assert_eq!(sketch.increment("a", 5), 5);
+        assert_eq!(sketch.increment("a", 2), 7);
+        for i in 0..1000 {
+            sketch.increment(&format!("item{}", i), 1);
+        }
+        assert!(sketch.query("a") >= 7);
+        assert!(sketch.query("a") <= 7 + 2);
+        assert_eq!(sketch.count(), 1007);
+    }
+
+    #[test]
+    fn test_topk() {
+        let mut topk = TopK::new(2, 100, 5, 0.9);
+        assert_eq!(topk.add("a"), None);
+        assert_eq!(topk.add("b"), None);
+        // a tie does not evict
+        assert_eq!(topk.add("c"), None);
+        assert_eq!(topk.add("c"), Some("b".to_string()));
+        assert_eq!(topk.add("a"), None);
+
+        assert!(topk.contains("a"));
+        assert!(!topk.contains("b"));
+        assert_eq!(topk.list(), &[("c".to_string(), 2), ("a".to_string(), 2)]);
+    }
+}
@@ -0,0 +1,130 @@
+use super::{now_ms, Backend};
+use std::sync::RwLock;
+
+/// Sliding expiration windows by key prefix.
+#[derive(Debug, Default)]
+pub(super) struct SlidingTtl {
+    // (prefix, window in milliseconds), sorted by decreasing prefix length
+    rules: RwLock<Vec<(String, i64)>>,
+}
+
+impl SlidingTtl {
+    // the window of the longest prefix of a key, if any
+    fn window(&self, key: &str) -> Option<i64> {
+        let rules = self.rules.read().unwrap();
+        rules
+            .iter()
+            .find(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .map(|(_, window)| *window)
+    }
+}
+
+impl Backend {
+    /// Push back the expiration of keys starting with `prefix` to `window_ms` from now whenever
+    /// they are accessed, reads included.
+    ///
+    /// Only keys that already have a time to live are extended, and never shortened. The longest
+    /// matching prefix wins, setting the window of an existing prefix replaces it.
+    pub fn set_sliding_ttl(&self, prefix: impl Into<String>, window_ms: i64) {
+        let prefix = prefix.into();
+        let mut rules = self.sliding.rules.write().unwrap();
+        rules.retain(|(p, _)| *p != prefix);
+        rules.push((prefix, window_ms));
+        rules.sort_by_key(|(p, _)| std::cmp::Reverse(p.len()));
+    }
+
+    /// Stop sliding the expiration of the keys of a prefix, returns false if it had no window.
+    pub fn remove_sliding_ttl(&self, prefix: &str) -> bool {
+        let mut rules = self.sliding.rules.write().unwrap();
+        let len = rules.len();
+        rules.retain(|(p, _)| p != prefix);
+        rules.len() != len
+    }
+
+    /// Replace every sliding expiration window, as CONFIG SET sliding-ttl does.
+    pub fn set_sliding_ttls(&self, windows: Vec<(String, i64)>) {
+        let mut rules = self.sliding.rules.write().unwrap();
+        rules.clear();
+        for (prefix, window_ms) in windows {
+            rules.retain(|(p, _)| *p != prefix);
+            rules.push((prefix, window_ms));
+        }
+        rules.sort_by_key(|(p, _)| std::cmp::Reverse(p.len()));
+    }
+
+    /// The sliding expiration windows by prefix, longest prefix first.
+    pub fn sliding_ttls(&self) -> Vec<(String, i64)> {
+        self.sliding.rules.read().unwrap().clone()
+    }
+
+    // called on access to a key that has not expired
+    pub(super) fn slide_expiration(&self, key: &str) {
+        let Some(window) = self.sliding.window(key) else {
+            return;
+        };
+        if let Some(mut at) = self.expire.get_mut(key) {
+            *at = (*at).max(now_ms().saturating_add(window));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_ttl() {
+        let backend = Backend::new();
+        backend.set_sliding_ttl("session:", 60_000);
+        backend.set_sliding_ttl("session:admin:", 120_000);
+
+        for key in ["session:1", "session:admin:1", "other", "session:2"] {
+            backend.set(key.to_string(), "v");
+        }
+        let soon = now_ms() + 1_000;
+        for key in ["session:1", "session:admin:1", "other"] {
+            backend.expire_at(key, soon);
+        }
+
+        let now = now_ms();
+        backend.get("session:1").unwrap();
+        backend.get("session:admin:1").unwrap();
+        backend.get("other").unwrap();
+        backend.get("session:2").unwrap();
+        let at = backend.expire_time("session:1").unwrap();
+        assert!((now + 60_000..now + 60_100).contains(&at));
+        let at = backend.expire_time("session:admin:1").unwrap();
+        assert!((now + 120_000..now + 120_100).contains(&at));
+        assert_eq!(backend.expire_time("other"), Some(soon));
+        // keys without a time to live are not given one
+        assert_eq!(backend.expire_time("session:2"), None);
+
+        assert!(backend.remove_sliding_ttl("session:admin:"));
+        assert!(!backend.remove_sliding_ttl("session:admin:"));
+        assert_eq!(
+            backend.sliding_ttls(),
+            vec![("session:".to_string(), 60_000)]
+        );
+    }
+
+    #[test]
+    fn test_introspection_does_not_slide() {
+        let backend = Backend::new();
+        backend.set_sliding_ttl("session:", 60_000);
+        backend.set_access_sample_rate(1);
+        backend.set("session:1".to_string(), "v");
+        let soon = now_ms() + 1_000;
+        backend.expire_at("session:1", soon);
+
+        assert!(backend.exists("session:1"));
+        assert!(backend.memory_usage("session:1").is_some());
+        assert!(backend.key_type("session:1").is_some());
+        assert_eq!(backend.expire_time("session:1"), Some(soon));
+        assert!(backend.hotkeys(10).is_empty());
+
+        // a miss is not an access either
+        backend.get("session:missing").unwrap();
+        assert!(backend.hotkeys(10).is_empty());
+        assert!(backend.access_times.get("session:missing").is_none());
+    }
+}
@@ -0,0 +1,199 @@
+use super::{now_ms, Backend};
+use crate::MemSize;
+use std::vec;
+
+/// Type of the value held by a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    String,
+    Hash,
+    Set,
+    Bloom,
+    Cuckoo,
+    CountMin,
+    TopK,
+    TimeSeries,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Point in time summary of a key, as yielded by [`Backend::snapshot_iter`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySnapshot {
+    pub key: String,
+    pub kind: KeyType,
+    // length of a string, number of fields of a hash, number of members of a set, number of
+    // items added to a bloom or cuckoo filter, total count of a count-min sketch, number of
+    // heavy hitters of a top-k, number of samples of a time series, number of top level
+    // elements of a JSON document
+    pub len: usize,
+    // estimated memory usage of the value, in bytes
+    pub size: usize,
+    // absolute expiration time, in unix milliseconds
+    pub expire_at: Option<i64>,
+}
+
+/// Iterator over the keys of a backend, see [`Backend::snapshot_iter`].
+#[derive(Debug)]
+pub struct SnapshotIter {
+    backend: Backend,
+    shard: usize,
+    chunk: vec::IntoIter<KeySnapshot>,
+}
+
+impl KeyType {
+    pub const ALL: &'static [KeyType] = &[
+        KeyType::String,
+        KeyType::Hash,
+        KeyType::Set,
+        KeyType::Bloom,
+        KeyType::Cuckoo,
+        KeyType::CountMin,
+        KeyType::TopK,
+        KeyType::TimeSeries,
+        #[cfg(feature = "json")]
+        KeyType::Json,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyType::String => "string",
+            KeyType::Hash => "hash",
+            KeyType::Set => "set",
+            KeyType::Bloom => "MBbloom--",
+            KeyType::Cuckoo => "MBbloomCF",
+            KeyType::CountMin => "CMSk-TYPE",
+            KeyType::TopK => "TopK-TYPE",
+            KeyType::TimeSeries => "TSDB-TYPE",
+            #[cfg(feature = "json")]
+            KeyType::Json => "ReJSON-RL",
+        }
+    }
+
+    /// The type named as by TYPE, case-insensitive.
+    pub fn from_name(name: &str) -> Option<KeyType> {
+        KeyType::ALL
+            .iter()
+            .find(|k| k.as_str().eq_ignore_ascii_case(name))
+            .copied()
+    }
+}
+
+impl Backend {
+    /// Iterate over a summary of every live key without blocking writers for long.
+    ///
+    /// Keys are collected one shard at a time, only holding the read lock of that shard while
+    /// copying the keys with their type and length. The memory usage of each value is estimated
+    /// afterwards, one key at a time, so the result is not a consistent snapshot of the whole
+    /// keyspace.
+    pub fn snapshot_iter(&self) -> SnapshotIter {
+        SnapshotIter {
+            backend: self.clone(),
+            shard: 0,
+            chunk: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl SnapshotIter {
+    // collect the next shard, returns false once every shard is exhausted
+    fn next_chunk(&mut self) -> bool {
+        let backend = &self.backend;
+        let Some(shard) = backend.keyspace.shards().get(self.shard) else {
+            return false;
+        };
+        self.shard += 1;
+        let entries: Vec<_> = shard
+            .read()
+            .iter()
+            .map(|(key, value)| {
+                let value = value.get();
+                (key.clone(), value.kind(), value.len())
+            })
+            .collect();
+
+        // estimate the sizes and look up the expiration times after releasing the shard lock,
+        // walking a large value then only holds the lock for that key; keys deleted meanwhile
+        // are skipped
+        let now = now_ms();
+        let chunk: Vec<_> = entries
+            .into_iter()
+            .filter_map(|(key, kind, len)| {
+                let size = backend.keyspace.get(&key)?.mem_size();
+                let expire_at = backend.expire.get(&key).map(|at| *at);
+                match expire_at {
+                    Some(at) if at <= now => None,
+                    _ => Some(KeySnapshot {
+                        key,
+                        kind,
+                        len,
+                        size,
+                        expire_at,
+                    }),
+                }
+            })
+            .collect();
+        self.chunk = chunk.into_iter();
+        true
+    }
+}
+
+impl Iterator for SnapshotIter {
+    type Item = KeySnapshot;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.chunk.next() {
+                return Some(item);
+            }
+            if !self.next_chunk() {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_iter() {
+        let backend = Backend::new();
+        for i in 0..100 {
+            backend.set(format!("key{:03}", i), "value");
+        }
+        backend
+            .hset("hash".to_string(), "f1".to_string(), 1.into())
+            .unwrap();
+        backend
+            .hset("hash".to_string(), "f2".to_string(), 2.into())
+            .unwrap();
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        backend.expire_at("set", i64::MAX);
+        backend.set("expired".to_string(), "value");
+        backend.expire_at("expired", 1);
+
+        let mut snapshot: Vec<_> = backend.snapshot_iter().collect();
+        assert_eq!(snapshot.len(), 102);
+        snapshot.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let hash = &snapshot[0];
+        assert_eq!((hash.key.as_str(), hash.kind), ("hash", KeyType::Hash));
+        assert_eq!((hash.len, hash.expire_at), (2, None));
+
+        let string = &snapshot[1];
+        assert_eq!(
+            (string.key.as_str(), string.kind),
+            ("key000", KeyType::String)
+        );
+        assert_eq!(string.len, 5);
+        assert!(string.size > 0);
+
+        let set = &snapshot[101];
+        assert_eq!((set.key.as_str(), set.kind), ("set", KeyType::Set));
+        assert_eq!((set.len, set.expire_at), (1, Some(i64::MAX)));
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// latencies are bucketed by powers of two microseconds, the last bucket holds everything above
+const LATENCY_BUCKETS: usize = 32;
+
+/// Latency statistics of the server serving a backend, reported by INFO.
+#[derive(Debug)]
+pub struct ServerStats {
+    // time from the arrival of a request to the start of its execution
+    queue_delay: LatencyHistogram,
+    // how late the latest event loop probe woke up, in microseconds
+    event_loop_lag: AtomicU64,
+    // the latency-tracking parameter, off by default means tracking is on
+    tracking_disabled: AtomicBool,
+    // outcome of the warmup file executed at startup, if any
+    warmup: Mutex<Option<WarmupStats>>,
+    // creation of the backend, in unix seconds
+    started: i64,
+}
+
+/// Outcome of executing a warmup file at startup.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WarmupStats {
+    pub commands: usize,
+    pub errors: usize,
+    pub elapsed: Duration,
+}
+
+/// Histogram of latencies with power of two microsecond buckets.
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS],
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, latency: Duration) {
+        let micros = latency.as_micros().max(1) as u64;
+        let bucket = (micros.ilog2() as usize).min(LATENCY_BUCKETS - 1);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Upper bound of the bucket holding the given percentile, None if nothing was recorded.
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((total as f64 * p / 100.0).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in counts.into_iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_micros((1 << (i + 1)) - 1));
+            }
+        }
+        None
+    }
+}
+
+impl Default for ServerStats {
+    fn default() -> Self {
+        Self {
+            queue_delay: LatencyHistogram::default(),
+            event_loop_lag: AtomicU64::default(),
+            tracking_disabled: AtomicBool::default(),
+            warmup: Mutex::default(),
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl ServerStats {
+    pub fn record_queue_delay(&self, delay: Duration) {
+        if self.tracking() {
+            self.queue_delay.record(delay);
+        }
+    }
+
+    pub fn queue_delay(&self) -> &LatencyHistogram {
+        &self.queue_delay
+    }
+
+    pub fn set_event_loop_lag(&self, lag: Duration) {
+        self.event_loop_lag
+            .store(lag.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn event_loop_lag(&self) -> Duration {
+        Duration::from_micros(self.event_loop_lag.load(Ordering::Relaxed))
+    }
+
+    pub fn set_warmup(&self, stats: WarmupStats) {
+        *self.warmup.lock().unwrap() = Some(stats);
+    }
+
+    pub fn warmup(&self) -> Option<WarmupStats> {
+        self.warmup.lock().unwrap().clone()
+    }
+
+    /// Unix time in seconds of the last successful save, reported by LASTSAVE.
+    ///
+    /// The dataset is never saved, so as in redis before its first save this is the time the
+    /// server started.
+    pub fn last_save(&self) -> i64 {
+        self.started
+    }
+
+    /// Whether request latencies are recorded, they are unless disabled by configuration.
+    pub fn tracking(&self) -> bool {
+        !self.tracking_disabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_tracking(&self, enabled: bool) {
+        self.tracking_disabled.store(!enabled, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_histogram_percentile() {
+        let histogram = LatencyHistogram::default();
+        assert_eq!(histogram.percentile(50.0), None);
+
+        for _ in 0..98 {
+            histogram.record(Duration::from_micros(10));
+        }
+        histogram.record(Duration::from_millis(5));
+        histogram.record(Duration::from_secs(1));
+
+        // 10us falls in the [8us, 16us) bucket
+        assert_eq!(histogram.percentile(50.0), Some(Duration::from_micros(15)));
+        assert_eq!(histogram.percentile(98.0), Some(Duration::from_micros(15)));
+        assert_eq!(
+            histogram.percentile(99.0),
+            Some(Duration::from_micros(8191))
+        );
+        assert_eq!(
+            histogram.percentile(100.0),
+            Some(Duration::from_micros((1 << 20) - 1))
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use super::{MemSize, WrongTypeError};
+use std::collections::VecDeque;
+use std::mem::size_of;
+use thiserror::Error;
+
+// samples per chunk, old samples are dropped a chunk at a time
+const CHUNK_SIZE: usize = 256;
+
+/// An append-only series of (timestamp, value) samples.
+#[derive(Debug, Clone, Default)]
+pub struct TimeSeries {
+    chunks: VecDeque<Vec<(i64, f64)>>,
+    // samples older than the latest one by more than this are dropped, 0 keeps everything
+    retention: i64,
+    // downsampling rules fed by this series
+    rules: Vec<Rule>,
+    // the series feeding this one through a rule
+    source: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Count,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    dest: String,
+    aggregation: Aggregation,
+    bucket: i64,
+    // start of the bucket being aggregated
+    current: Option<(i64, Accumulator)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Accumulator {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TimeSeriesError {
+    #[error("ERR TSDB: key already exists")]
+    KeyExists,
+    #[error("ERR TSDB: the key does not exist")]
+    KeyMissing,
+    #[error("ERR TSDB: timestamp must be higher than the maximum existing timestamp")]
+    OutOfOrder,
+    #[error("ERR TSDB: the source key and destination key should be different")]
+    SameKey,
+    #[error("ERR TSDB: the destination key already has a src rule")]
+    DestinationHasSource,
+    #[error("ERR TSDB: the destination key already has a dst rule")]
+    DestinationHasRules,
+    #[error("ERR TSDB: the source key already has a src rule")]
+    SourceHasSource,
+    #[error(transparent)]
+    WrongType(#[from] WrongTypeError),
+}
+
+impl TimeSeries {
+    pub fn new(retention: i64) -> Self {
+        Self {
+            retention,
+            ..Default::default()
+        }
+    }
+
+    pub fn last(&self) -> Option<(i64, f64)> {
+        self.chunks.back().and_then(|c| c.last()).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|c| c.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append a sample, returns the samples closed by the downsampling rules as
+    /// (destination, timestamp, value).
+    pub fn add(&mut self, ts: i64, value: f64) -> Result<Vec<(String, i64, f64)>, TimeSeriesError> {
+        if self.last().map(|(last, _)| ts <= last).unwrap_or(false) {
+            return Err(TimeSeriesError::OutOfOrder);
+        }
+
+        match self.chunks.back_mut() {
+            Some(chunk) if chunk.len() < CHUNK_SIZE => chunk.push((ts, value)),
+            _ => {
+                let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+                chunk.push((ts, value));
+                self.chunks.push_back(chunk);
+            }
+        }
+        self.trim(ts);
+
+        Ok(self
+            .rules
+            .iter_mut()
+            .filter_map(|rule| rule.add(ts, value))
+            .collect())
+    }
+
+    /// Samples within [from, to].
+    pub fn range(&self, from: i64, to: i64) -> impl Iterator<Item = (i64, f64)> + '_ {
+        self.chunks
+            .iter()
+            .filter(move |c| c.first().map(|s| s.0 <= to).unwrap_or(false))
+            .filter(move |c| c.last().map(|s| s.0 >= from).unwrap_or(false))
+            .flat_map(|c| c.iter().copied())
+            .filter(move |(ts, _)| *ts >= from && *ts <= to)
+    }
+
+    /// Samples within [from, to] aggregated in buckets of `bucket` milliseconds, each bucket is
+    /// reported at its start time.
+    pub fn range_aggregated(
+        &self,
+        from: i64,
+        to: i64,
+        aggregation: Aggregation,
+        bucket: i64,
+    ) -> Vec<(i64, f64)> {
+        let mut ret = Vec::new();
+        let mut current: Option<(i64, Accumulator)> = None;
+        for (ts, value) in self.range(from, to) {
+            let start = bucket_start(ts, bucket);
+            match current.as_mut() {
+                Some((s, acc)) if *s == start => acc.add(value),
+                _ => {
+                    if let Some((s, acc)) = current.take() {
+                        ret.push((s, acc.finish(aggregation)));
+                    }
+                    current = Some((start, Accumulator::new(value)));
+                }
+            }
+        }
+        if let Some((s, acc)) = current {
+            ret.push((s, acc.finish(aggregation)));
+        }
+        ret
+    }
+
+    // downsampling rules link two series, the backend checks and wires both ends
+    pub(super) fn check_rule_source(&self, src: &str, dest: &str) -> Result<(), TimeSeriesError> {
+        if src == dest {
+            return Err(TimeSeriesError::SameKey);
+        }
+        if self.source.is_some() {
+            return Err(TimeSeriesError::SourceHasSource);
+        }
+        Ok(())
+    }
+
+    pub(super) fn check_rule_destination(&self) -> Result<(), TimeSeriesError> {
+        if self.source.is_some() {
+            return Err(TimeSeriesError::DestinationHasSource);
+        }
+        if !self.rules.is_empty() {
+            return Err(TimeSeriesError::DestinationHasRules);
+        }
+        Ok(())
+    }
+
+    pub(super) fn add_rule(&mut self, dest: String, aggregation: Aggregation, bucket: i64) {
+        self.rules.push(Rule {
+            dest,
+            aggregation,
+            bucket,
+            current: None,
+        });
+    }
+
+    pub(super) fn set_source(&mut self, src: String) {
+        self.source = Some(src);
+    }
+
+    // a copy of the samples, neither fed by nor feeding other series
+    pub(super) fn detached(&self) -> TimeSeries {
+        TimeSeries {
+            chunks: self.chunks.clone(),
+            retention: self.retention,
+            ..Default::default()
+        }
+    }
+
+    // drop the samples out of the retention window ending at `latest`
+    fn trim(&mut self, latest: i64) {
+        if self.retention <= 0 {
+            return;
+        }
+        let oldest = latest - self.retention;
+        while self
+            .chunks
+            .front()
+            .and_then(|c| c.last())
+            .map(|s| s.0 < oldest)
+            .unwrap_or(false)
+        {
+            self.chunks.pop_front();
+        }
+        if let Some(chunk) = self.chunks.front_mut() {
+            let expired = chunk.partition_point(|s| s.0 < oldest);
+            chunk.drain(..expired);
+        }
+    }
+}
+
+impl Aggregation {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "avg" => Some(Aggregation::Avg),
+            "sum" => Some(Aggregation::Sum),
+            "min" => Some(Aggregation::Min),
+            "max" => Some(Aggregation::Max),
+            "count" => Some(Aggregation::Count),
+            _ => None,
+        }
+    }
+}
+
+impl Rule {
+    // aggregate a sample, returns the previous bucket once a sample falls past it
+    fn add(&mut self, ts: i64, value: f64) -> Option<(String, i64, f64)> {
+        let start = bucket_start(ts, self.bucket);
+        match self.current.as_mut() {
+            Some((s, acc)) if *s == start => {
+                acc.add(value);
+                None
+            }
+            _ => {
+                let closed = self.current.take();
+                self.current = Some((start, Accumulator::new(value)));
+                closed.map(|(s, acc)| (self.dest.clone(), s, acc.finish(self.aggregation)))
+            }
+        }
+    }
+}
+
+impl Accumulator {
+    fn new(value: f64) -> Self {
+        Self {
+            count: 1,
+            sum: value,
+            min: value,
+            max: value,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn finish(&self, aggregation: Aggregation) -> f64 {
+        match aggregation {
+            Aggregation::Avg => self.sum / self.count as f64,
+            Aggregation::Sum => self.sum,
+            Aggregation::Min => self.min,
+            Aggregation::Max => self.max,
+            Aggregation::Count => self.count as f64,
+        }
+    }
+}
+
+fn bucket_start(ts: i64, bucket: i64) -> i64 {
+    ts - ts.rem_euclid(bucket)
+}
+
+impl MemSize for TimeSeries {
+    fn mem_size(&self) -> usize {
+        let chunks = self.chunks.capacity() * size_of::<Vec<(i64, f64)>>()
+            + self
+                .chunks
+                .iter()
+                .map(|c| c.capacity() * size_of::<(i64, f64)>())
+                .sum::<usize>();
+        let rules = self.rules.capacity() * size_of::<Rule>()
+            + self.rules.iter().map(|r| r.dest.capacity()).sum::<usize>();
+        let source = self.source.as_ref().map(|s| s.capacity()).unwrap_or(0);
+        size_of::<Self>() + chunks + rules + source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_series_add_range() {
+        let mut series = TimeSeries::new(0);
+        for ts in 0..1000 {
+            series.add(ts, ts as f64).unwrap();
+        }
+        assert_eq!(series.add(999, 1.0), Err(TimeSeriesError::OutOfOrder));
+        assert_eq!(series.len(), 1000);
+        assert_eq!(series.last(), Some((999, 999.0)));
+
+        let range: Vec<_> = series.range(254, 258).collect();
+        assert_eq!(
+            range,
+            vec![
+                (254, 254.0),
+                (255, 255.0),
+                (256, 256.0),
+                (257, 257.0),
+                (258, 258.0)
+            ]
+        );
+
+        let buckets = series.range_aggregated(5, 34, Aggregation::Avg, 10);
+        assert_eq!(buckets, vec![(0, 7.0), (10, 14.5), (20, 24.5), (30, 32.0)]);
+        let buckets = series.range_aggregated(0, 19, Aggregation::Count, 10);
+        assert_eq!(buckets, vec![(0, 10.0), (10, 10.0)]);
+    }
+
+    #[test]
+    fn test_time_series_retention() {
+        let mut series = TimeSeries::new(100);
+        for ts in 0..1000 {
+            series.add(ts, 1.0).unwrap();
+        }
+        assert_eq!(series.len(), 101);
+        assert_eq!(series.range(0, i64::MAX).next(), Some((899, 1.0)));
+    }
+
+    #[test]
+    fn test_time_series_rules() {
+        let mut series = TimeSeries::new(0);
+        series.add_rule("max".to_string(), Aggregation::Max, 10);
+
+        let mut closed = Vec::new();
+        for ts in [1, 5, 9, 12, 25] {
+            closed.extend(series.add(ts, ts as f64).unwrap());
+        }
+        assert_eq!(
+            closed,
+            vec![("max".to_string(), 0, 9.0), ("max".to_string(), 10, 12.0)]
+        );
+    }
+}
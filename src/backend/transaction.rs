@@ -0,0 +1,59 @@
+use super::Backend;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Isolation of transactions from the commands run concurrently by other connections.
+///
+/// The lock is asynchronous, a connection waiting for it yields its worker thread instead of
+/// blocking it.
+#[derive(Debug, Default)]
+pub(super) struct ExecLock(RwLock<()>);
+
+impl Backend {
+    /// Run `f` with no other call to [`Backend::exclusive`] or [`Backend::shared`] running, to
+    /// apply the commands of a transaction at once.
+    pub async fn exclusive<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = self.exec_lock.0.write().await;
+        f()
+    }
+
+    /// Wait for `delay` then run `f`, holding off every call to [`Backend::exclusive`] or
+    /// [`Backend::shared`] meanwhile.
+    ///
+    /// This is how DEBUG SLEEP stalls the whole server, the wait yields the worker thread.
+    pub async fn exclusive_after<R>(&self, delay: Duration, f: impl FnOnce() -> R) -> R {
+        let _guard = self.exec_lock.0.write().await;
+        tokio::time::sleep(delay).await;
+        f()
+    }
+
+    /// Run `f` concurrently with other calls to [`Backend::shared`] but never while a call to
+    /// [`Backend::exclusive`] is running.
+    pub async fn shared<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = self.exec_lock.0.read().await;
+        f()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_exclusive_excludes_shared() {
+        let backend = Backend::new();
+        let other = backend.clone();
+        let handle = backend
+            .exclusive(|| {
+                let handle = tokio::spawn(async move { other.shared(|| other.get("key")).await });
+                // the shared call cannot see the first write without the second one
+                backend.set("key".to_string(), "1");
+                thread::sleep(Duration::from_millis(20));
+                backend.set("key".to_string(), "2");
+                handle
+            })
+            .await;
+        assert_eq!(handle.await.unwrap(), Ok(Some("2".into())));
+    }
+}
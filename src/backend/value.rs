@@ -0,0 +1,271 @@
+use super::{
+    Backend, BloomFilter, ChangeEvent, CountMinSketch, CuckooFilter, KeyType, TimeSeries, TopK,
+};
+use crate::{MemSize, RespFrame};
+use bytes::Bytes;
+use dashmap::{
+    mapref::{
+        entry::Entry,
+        one::{MappedRef, MappedRefMut},
+    },
+    DashMap, DashSet,
+};
+use thiserror::Error;
+
+/// The value held by a key, a key holds a single value of a single type.
+#[derive(Debug, Clone)]
+pub(super) enum Value {
+    String(Bytes),
+    Hash(DashMap<String, RespFrame>),
+    Set(DashSet<String>),
+    Bloom(BloomFilter),
+    Cuckoo(CuckooFilter),
+    CountMin(CountMinSketch),
+    TopK(TopK),
+    TimeSeries(TimeSeries),
+    #[cfg(feature = "json")]
+    Json(serde_json::Value),
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+pub struct WrongTypeError;
+
+/// A type of value stored as one of the variants of [`Value`].
+pub(super) trait Typed: Sized + MemSize {
+    const KIND: KeyType;
+
+    fn of(value: &Value) -> Option<&Self>;
+    fn of_mut(value: &mut Value) -> Option<&mut Self>;
+    fn into_value(self) -> Value;
+}
+
+macro_rules! typed {
+    ($variant:ident, $type:ty) => {
+        impl Typed for $type {
+            const KIND: KeyType = KeyType::$variant;
+
+            fn of(value: &Value) -> Option<&Self> {
+                match value {
+                    Value::$variant(v) => Some(v),
+                    _ => None,
+                }
+            }
+
+            fn of_mut(value: &mut Value) -> Option<&mut Self> {
+                match value {
+                    Value::$variant(v) => Some(v),
+                    _ => None,
+                }
+            }
+
+            fn into_value(self) -> Value {
+                Value::$variant(self)
+            }
+        }
+    };
+}
+
+typed!(String, Bytes);
+typed!(Hash, DashMap<String, RespFrame>);
+typed!(Set, DashSet<String>);
+typed!(Bloom, BloomFilter);
+typed!(Cuckoo, CuckooFilter);
+typed!(CountMin, CountMinSketch);
+typed!(TopK, TopK);
+typed!(TimeSeries, TimeSeries);
+#[cfg(feature = "json")]
+typed!(Json, serde_json::Value);
+
+impl Value {
+    pub(super) fn kind(&self) -> KeyType {
+        match self {
+            Value::String(_) => KeyType::String,
+            Value::Hash(_) => KeyType::Hash,
+            Value::Set(_) => KeyType::Set,
+            Value::Bloom(_) => KeyType::Bloom,
+            Value::Cuckoo(_) => KeyType::Cuckoo,
+            Value::CountMin(_) => KeyType::CountMin,
+            Value::TopK(_) => KeyType::TopK,
+            Value::TimeSeries(_) => KeyType::TimeSeries,
+            #[cfg(feature = "json")]
+            Value::Json(_) => KeyType::Json,
+        }
+    }
+
+    // the length of the value as reported by a KeySnapshot
+    pub(super) fn len(&self) -> usize {
+        match self {
+            Value::String(bytes) => bytes.len(),
+            Value::Hash(hash) => hash.len(),
+            Value::Set(set) => set.len(),
+            Value::Bloom(filter) => filter.len(),
+            Value::Cuckoo(filter) => filter.len(),
+            Value::CountMin(sketch) => sketch.count() as usize,
+            Value::TopK(topk) => topk.list().len(),
+            Value::TimeSeries(series) => series.len(),
+            #[cfg(feature = "json")]
+            Value::Json(serde_json::Value::Array(a)) => a.len(),
+            #[cfg(feature = "json")]
+            Value::Json(serde_json::Value::Object(o)) => o.len(),
+            #[cfg(feature = "json")]
+            Value::Json(_) => 1,
+        }
+    }
+}
+
+impl MemSize for Value {
+    fn mem_size(&self) -> usize {
+        match self {
+            Value::String(v) => v.mem_size(),
+            Value::Hash(v) => v.mem_size(),
+            Value::Set(v) => v.mem_size(),
+            Value::Bloom(v) => v.mem_size(),
+            Value::Cuckoo(v) => v.mem_size(),
+            Value::CountMin(v) => v.mem_size(),
+            Value::TopK(v) => v.mem_size(),
+            Value::TimeSeries(v) => v.mem_size(),
+            #[cfg(feature = "json")]
+            Value::Json(v) => v.mem_size(),
+        }
+    }
+}
+
+impl Backend {
+    /// Type of the value held by a key, None if the key does not exist.
+    pub fn key_type(&self, key: &str) -> Option<KeyType> {
+        self.expire_if_needed(key);
+        self.keyspace.get(key).map(|v| v.kind())
+    }
+
+    /// The string value of a key for inspection, without counting an access to the key.
+    pub fn peek_string(&self, key: &str) -> Option<Bytes> {
+        self.peek::<Bytes>(key)
+    }
+
+    /// The fields of a hash for inspection, without counting an access to the key.
+    pub fn peek_hash(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
+        self.peek::<DashMap<String, RespFrame>>(key)
+    }
+
+    /// The members of a set for inspection, without counting an access to the key.
+    pub fn peek_set(&self, key: &str) -> Option<DashSet<String>> {
+        self.peek::<DashSet<String>>(key)
+    }
+
+    // a copy of the value of a key of a type, None if the key holds another type
+    fn peek<T: Typed + Clone>(&self, key: &str) -> Option<T> {
+        self.expire_if_needed(key);
+        self.typed::<T>(key).ok().flatten().map(|v| v.clone())
+    }
+
+    // publish the creation of a whole value, as the changes building it one element at a time
+    pub(super) fn notify_value(&self, key: &str, value: &Value) {
+        match value {
+            Value::String(value) => self.notify(|| ChangeEvent::SetString {
+                key: key.to_string(),
+                value: value.clone(),
+            }),
+            Value::Hash(hash) => {
+                for field in hash.iter() {
+                    self.notify(|| ChangeEvent::HashFieldSet {
+                        key: key.to_string(),
+                        field: field.key().clone(),
+                        value: field.value().clone(),
+                    });
+                }
+            }
+            Value::Set(set) => {
+                for member in set.iter() {
+                    self.notify(|| ChangeEvent::SetMemberAdded {
+                        key: key.to_string(),
+                        member: member.key().clone(),
+                    });
+                }
+            }
+            // the other types are not part of the change stream
+            _ => {}
+        }
+    }
+
+    // the value of a key, None if the key is missing, fails if the key is of another type
+    pub(super) fn typed<T: Typed>(
+        &self,
+        key: &str,
+    ) -> Result<Option<MappedRef<'_, String, Value, T>>, WrongTypeError> {
+        match self.keyspace.get(key) {
+            Some(value) => value.try_map(T::of).map(Some).map_err(|_| WrongTypeError),
+            None => Ok(None),
+        }
+    }
+
+    pub(super) fn typed_mut<T: Typed>(
+        &self,
+        key: &str,
+    ) -> Result<Option<MappedRefMut<'_, String, Value, T>>, WrongTypeError> {
+        match self.keyspace.get_mut(key) {
+            Some(value) => value
+                .try_map(T::of_mut)
+                .map(Some)
+                .map_err(|_| WrongTypeError),
+            None => Ok(None),
+        }
+    }
+
+    // the value of a key, created if the key is missing, fails if the key is of another type
+    pub(super) fn typed_or_insert_with<T: Typed>(
+        &self,
+        key: String,
+        create: impl FnOnce() -> T,
+    ) -> Result<MappedRefMut<'_, String, Value, T>, WrongTypeError> {
+        let value = match self.keyspace.entry(key) {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => {
+                let value = create().into_value();
+                self.created(entry.key(), &value);
+                entry.insert(value)
+            }
+        };
+        value.try_map(T::of_mut).map_err(|_| WrongTypeError)
+    }
+
+    // change a value in place, accounting for its change of size
+    pub(super) fn update<T: Typed, R>(&self, value: &mut T, f: impl FnOnce(&mut T) -> R) -> R {
+        let before = value.mem_size();
+        let ret = f(value);
+        self.account_resize(T::KIND, before, value.mem_size());
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_key_holds_a_single_type() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        assert_eq!(backend.key_type("key"), Some(KeyType::String));
+        assert_eq!(
+            backend.hset("key".to_string(), "field".to_string(), 1.into()),
+            Err(WrongTypeError)
+        );
+        assert_eq!(
+            backend.sadd("key".to_string(), "member".to_string()),
+            Err(WrongTypeError)
+        );
+        assert_eq!(backend.hget("key", "field"), Err(WrongTypeError));
+        assert_eq!(backend.smembers("key").err(), Some(WrongTypeError));
+        assert_eq!(backend.get("key").unwrap(), Some("value".into()));
+
+        // a set replaces the value whatever its type
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        backend.set("set".to_string(), "1");
+        assert_eq!(backend.key_type("set"), Some(KeyType::String));
+        assert_eq!(backend.sismember("set", "member"), Err(WrongTypeError));
+        assert_eq!(backend.key_type("missing"), None);
+    }
+}
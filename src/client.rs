@@ -0,0 +1,273 @@
+use crate::{
+    cmd::{self, CommandError},
+    middleware::ConnectionContext,
+    BulkString, RespArray, RespFrame, RespNull, SimpleString,
+};
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// The live client connections of a server, for the CLIENT commands.
+#[derive(Debug, Default)]
+pub struct ClientRegistry {
+    // by connection id, so that clients are listed in order of connection
+    clients: Mutex<BTreeMap<u64, ClientState>>,
+}
+
+#[derive(Debug, Clone)]
+struct ClientState {
+    addr: SocketAddr,
+    connected: Instant,
+    last_interaction: Instant,
+    // name of the latest command, lower case
+    last_command: String,
+    // set by CLIENT SETNAME
+    name: Option<String>,
+}
+
+/// Registration of a connection, the client is removed from the registry when dropped.
+#[derive(Debug)]
+pub(crate) struct ClientGuard {
+    registry: Arc<ClientRegistry>,
+    id: u64,
+}
+
+impl ClientRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn register(self: &Arc<Self>, ctx: &ConnectionContext) -> ClientGuard {
+        let now = Instant::now();
+        self.clients.lock().unwrap().insert(
+            ctx.id,
+            ClientState {
+                addr: ctx.peer,
+                connected: now,
+                last_interaction: now,
+                last_command: "NULL".to_string(),
+                name: None,
+            },
+        );
+        ClientGuard {
+            registry: self.clone(),
+            id: ctx.id,
+        }
+    }
+
+    /// Record that a client sent a command.
+    pub(crate) fn record_command(&self, id: u64, args: &RespArray) {
+        let name = match args.0.as_ref().and_then(|a| a.first()) {
+            Some(RespFrame::BulkString(BulkString(Some(name)))) => {
+                String::from_utf8_lossy(name).to_ascii_lowercase()
+            }
+            _ => return,
+        };
+        if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+            client.last_interaction = Instant::now();
+            client.last_command = name;
+        }
+    }
+
+    fn set_name(&self, id: u64, name: Option<String>) {
+        if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+            client.name = name;
+        }
+    }
+
+    fn name(&self, id: u64) -> Option<String> {
+        self.clients.lock().unwrap().get(&id)?.name.clone()
+    }
+
+    /// Number of connected clients.
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // one line per client as in CLIENT LIST, only the clients of `ids` if given
+    fn list(&self, ids: Option<&[u64]>) -> String {
+        let now = Instant::now();
+        self.clients
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| ids.is_none_or(|ids| ids.contains(id)))
+            .map(|(id, client)| client.describe(*id, now) + "\n")
+            .collect()
+    }
+}
+
+impl ClientState {
+    fn describe(&self, id: u64, now: Instant) -> String {
+        format!(
+            "id={} addr={} name={} age={} idle={} db=0 cmd={}",
+            id,
+            self.addr,
+            self.name.as_deref().unwrap_or_default(),
+            now.duration_since(self.connected).as_secs(),
+            now.duration_since(self.last_interaction).as_secs(),
+            self.last_command
+        )
+    }
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        self.registry.clients.lock().unwrap().remove(&self.id);
+    }
+}
+
+pub(crate) fn is_client_command(args: &RespArray) -> bool {
+    matches!(
+        args.0.as_ref().and_then(|a| a.first()),
+        Some(RespFrame::BulkString(BulkString(Some(name)))) if name.eq_ignore_ascii_case(b"client")
+    )
+}
+
+/// CLIENT ID | CLIENT INFO | CLIENT LIST [ID client-id ...] | CLIENT SETNAME name | CLIENT GETNAME
+pub(crate) fn client_command(
+    registry: &ClientRegistry,
+    ctx: &ConnectionContext,
+    args: RespArray,
+) -> Result<RespFrame, CommandError> {
+    let args = cmd::extract_args(args, 1)?
+        .into_iter()
+        .map(|arg| match arg {
+            RespFrame::BulkString(BulkString(Some(arg))) => Ok(String::from_utf8(arg)?),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let syntax_error = || CommandError::InvalidArgument("syntax error".to_string());
+    let (subcommand, rest) = args.split_first().ok_or_else(|| {
+        CommandError::InvalidArgument("wrong number of arguments for 'client' command".to_string())
+    })?;
+
+    let ret = match (subcommand.to_ascii_lowercase().as_str(), rest) {
+        ("id", []) => RespFrame::Integer(ctx.id as i64),
+        ("info", []) => BulkString::new(registry.list(Some(&[ctx.id]))).into(),
+        ("list", []) => BulkString::new(registry.list(None)).into(),
+        ("list", [option, ids @ ..]) if option.eq_ignore_ascii_case("id") && !ids.is_empty() => {
+            let ids = ids
+                .iter()
+                .map(|id| id.parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| CommandError::InvalidArgument("Invalid client ID".to_string()))?;
+            BulkString::new(registry.list(Some(&ids))).into()
+        }
+        ("list", _) => return Err(syntax_error()),
+        ("setname", [name]) => {
+            // names are listed space separated, an empty name removes it
+            if name.chars().any(|c| !c.is_ascii_graphic()) {
+                return Err(CommandError::InvalidArgument(
+                    "Client names cannot contain spaces, newlines or special characters."
+                        .to_string(),
+                ));
+            }
+            registry.set_name(ctx.id, Some(name.clone()).filter(|name| !name.is_empty()));
+            SimpleString::new("OK").into()
+        }
+        ("getname", []) => match registry.name(ctx.id) {
+            Some(name) => BulkString::new(name).into(),
+            None => RespFrame::Null(RespNull),
+        },
+        (subcommand, _) => {
+            return Err(CommandError::InvalidArgument(format!(
+                "unknown subcommand or wrong number of arguments for 'client|{}'",
+                subcommand
+            )))
+        }
+    };
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    fn text(frame: RespFrame) -> String {
+        match frame {
+            RespFrame::BulkString(BulkString(Some(text))) => String::from_utf8(text).unwrap(),
+            frame => panic!("not a bulk string: {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_client_registry() -> Result<()> {
+        let registry = Arc::new(ClientRegistry::new());
+        let a = ConnectionContext::new("127.0.0.1:1000".parse()?);
+        let b = ConnectionContext::new("127.0.0.1:2000".parse()?);
+        let guard_a = registry.register(&a);
+        let _guard_b = registry.register(&b);
+        registry.record_command(a.id, &args(&["GET", "key"]));
+
+        assert!(is_client_command(&args(&["CLIENT", "list"])));
+        assert!(!is_client_command(&args(&["get", "key"])));
+
+        let ret = client_command(&registry, &a, args(&["client", "id"]))?;
+        assert_eq!(ret, RespFrame::Integer(a.id as i64));
+
+        let info = text(client_command(&registry, &a, args(&["client", "info"]))?);
+        assert_eq!(
+            info,
+            format!(
+                "id={} addr=127.0.0.1:1000 name= age=0 idle=0 db=0 cmd=get\n",
+                a.id
+            )
+        );
+
+        let list = text(client_command(&registry, &a, args(&["client", "list"]))?);
+        assert_eq!(list.lines().count(), 2);
+        let id = b.id.to_string();
+        let list = client_command(&registry, &a, args(&["client", "list", "id", &id]))?;
+        assert!(text(list).contains("addr=127.0.0.1:2000"));
+
+        // a closed connection is no longer listed
+        drop(guard_a);
+        assert_eq!(registry.len(), 1);
+
+        assert!(client_command(&registry, &a, args(&["client", "list", "id"])).is_err());
+        assert!(client_command(&registry, &a, args(&["client", "list", "id", "x"])).is_err());
+        assert!(client_command(&registry, &a, args(&["client", "kill"])).is_err());
+        assert!(client_command(&registry, &a, args(&["client"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_name() -> Result<()> {
+        let registry = Arc::new(ClientRegistry::new());
+        let ctx = ConnectionContext::new("127.0.0.1:1000".parse()?);
+        let _guard = registry.register(&ctx);
+
+        let ret = client_command(&registry, &ctx, args(&["client", "getname"]))?;
+        assert_eq!(ret, RespFrame::Null(RespNull));
+        let ret = client_command(&registry, &ctx, args(&["client", "setname", "pool-1"]))?;
+        assert_eq!(ret, SimpleString::new("OK").into());
+        let ret = client_command(&registry, &ctx, args(&["client", "getname"]))?;
+        assert_eq!(ret, BulkString::new("pool-1").into());
+        let list = text(client_command(&registry, &ctx, args(&["client", "list"]))?);
+        assert!(list.contains(" name=pool-1 "));
+
+        assert!(client_command(&registry, &ctx, args(&["client", "setname", "a b"])).is_err());
+        assert!(client_command(&registry, &ctx, args(&["client", "setname"])).is_err());
+        client_command(&registry, &ctx, args(&["client", "setname", ""]))?;
+        let ret = client_command(&registry, &ctx, args(&["client", "getname"]))?;
+        assert_eq!(ret, RespFrame::Null(RespNull));
+
+        Ok(())
+    }
+}
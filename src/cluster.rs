@@ -0,0 +1,162 @@
+use crate::{
+    cmd::{self, CommandError},
+    BulkString, RespArray, RespFrame,
+};
+use lazy_static::lazy_static;
+use rand::Rng;
+use std::net::SocketAddr;
+
+// number of hash slots keys are distributed over by cluster clients
+const CLUSTER_SLOTS: i64 = 16384;
+
+lazy_static! {
+    // 40 random hex characters, as redis names the nodes of a cluster
+    static ref NODE_ID: String = {
+        let mut rng = rand::thread_rng();
+        (0..40)
+            .map(|_| char::from_digit(rng.gen_range(0..16), 16).unwrap_or('0'))
+            .collect()
+    };
+}
+
+pub(crate) fn is_cluster_command(args: &RespArray) -> bool {
+    matches!(
+        args.0.as_ref().and_then(|a| a.first()),
+        Some(RespFrame::BulkString(BulkString(Some(name)))) if name.eq_ignore_ascii_case(b"cluster")
+    )
+}
+
+/// CLUSTER INFO | CLUSTER SLOTS | CLUSTER SHARDS
+///
+/// The server is a cluster of a single master owning every slot, reached at `local`, the address
+/// the client connected to.
+pub(crate) fn cluster_command(
+    local: SocketAddr,
+    args: RespArray,
+) -> Result<RespFrame, CommandError> {
+    let args = cmd::parse_strings(args)?;
+    let (subcommand, rest) = args.split_first().ok_or_else(|| {
+        CommandError::InvalidArgument("wrong number of arguments for 'cluster' command".to_string())
+    })?;
+    let ip = local.ip().to_string();
+    let port = local.port() as i64;
+
+    let ret = match (subcommand.to_ascii_lowercase().as_str(), rest) {
+        ("info", []) => {
+            let info = [
+                "cluster_state:ok".to_string(),
+                format!("cluster_slots_assigned:{}", CLUSTER_SLOTS),
+                format!("cluster_slots_ok:{}", CLUSTER_SLOTS),
+                "cluster_slots_pfail:0".to_string(),
+                "cluster_slots_fail:0".to_string(),
+                "cluster_known_nodes:1".to_string(),
+                "cluster_size:1".to_string(),
+                "cluster_current_epoch:0".to_string(),
+                "cluster_my_epoch:0".to_string(),
+            ];
+            BulkString::new(info.map(|line| line + "\r\n").concat()).into()
+        }
+        ("slots", []) => {
+            // the empty array is the map of additional endpoints of redis 7
+            let node = RespArray::new(vec![
+                BulkString::new(ip).into(),
+                port.into(),
+                BulkString::new(NODE_ID.as_str()).into(),
+                RespArray::new(vec![]).into(),
+            ]);
+            RespArray::new(vec![RespArray::new(vec![
+                0.into(),
+                (CLUSTER_SLOTS - 1).into(),
+                node.into(),
+            ])
+            .into()])
+            .into()
+        }
+        ("shards", []) => {
+            let node = RespArray::new(vec![
+                BulkString::new("id").into(),
+                BulkString::new(NODE_ID.as_str()).into(),
+                BulkString::new("port").into(),
+                port.into(),
+                BulkString::new("ip").into(),
+                BulkString::new(ip.clone()).into(),
+                BulkString::new("endpoint").into(),
+                BulkString::new(ip).into(),
+                BulkString::new("role").into(),
+                BulkString::new("master").into(),
+                BulkString::new("replication-offset").into(),
+                0.into(),
+                BulkString::new("health").into(),
+                BulkString::new("online").into(),
+            ]);
+            let shard = RespArray::new(vec![
+                BulkString::new("slots").into(),
+                RespArray::new(vec![0.into(), (CLUSTER_SLOTS - 1).into()]).into(),
+                BulkString::new("nodes").into(),
+                RespArray::new(vec![node.into()]).into(),
+            ]);
+            RespArray::new(vec![shard.into()]).into()
+        }
+        (subcommand, _) => {
+            return Err(CommandError::InvalidArgument(format!(
+                "unknown subcommand or wrong number of arguments for 'cluster|{}'",
+                subcommand
+            )))
+        }
+    };
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    #[test]
+    fn test_cluster_command() -> Result<()> {
+        let local: SocketAddr = "127.0.0.1:6379".parse()?;
+        assert!(is_cluster_command(&args(&["CLUSTER", "info"])));
+        assert!(!is_cluster_command(&args(&["client", "info"])));
+
+        let RespFrame::BulkString(BulkString(Some(info))) =
+            cluster_command(local, args(&["cluster", "INFO"]))?
+        else {
+            panic!("expected a bulk string");
+        };
+        let info = String::from_utf8(info)?;
+        assert!(info.starts_with("cluster_state:ok\r\ncluster_slots_assigned:16384\r\n"));
+
+        let ret = cluster_command(local, args(&["cluster", "slots"]))?;
+        let expected = RespArray::new(vec![RespArray::new(vec![
+            0.into(),
+            16383.into(),
+            RespArray::new(vec![
+                BulkString::new("127.0.0.1").into(),
+                6379.into(),
+                BulkString::new(NODE_ID.as_str()).into(),
+                RespArray::new(vec![]).into(),
+            ])
+            .into(),
+        ])
+        .into()]);
+        assert_eq!(ret, expected.into());
+        assert_eq!(NODE_ID.len(), 40);
+
+        let RespFrame::Array(RespArray(Some(shards))) =
+            cluster_command(local, args(&["cluster", "shards"]))?
+        else {
+            panic!("expected an array");
+        };
+        assert_eq!(shards.len(), 1);
+
+        assert!(cluster_command(local, args(&["cluster", "nodes"])).is_err());
+        assert!(cluster_command(local, args(&["cluster", "slots", "x"])).is_err());
+        assert!(cluster_command(local, args(&["cluster"])).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,412 @@
+use super::{
+    extract_args, parse_number, validate_command, validate_dynamic_command, CommandError,
+    CommandExecutor,
+};
+use crate::{
+    Backend, BitUnit, BitwiseOp, BulkString, RespArray, RespFrame, SimpleError, MAX_BIT_OFFSET,
+};
+
+const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+
+/// SETBIT key offset value
+#[derive(Debug)]
+pub struct SetBit {
+    key: String,
+    offset: u64,
+    bit: bool,
+}
+
+/// GETBIT key offset
+#[derive(Debug)]
+pub struct GetBit {
+    key: String,
+    offset: u64,
+}
+
+/// BITCOUNT key [start end [BYTE | BIT]]
+#[derive(Debug)]
+pub struct BitCount {
+    key: String,
+    range: Option<(i64, i64, BitUnit)>,
+}
+
+/// BITOP AND | OR | XOR | NOT destkey key [key ...]
+#[derive(Debug)]
+pub struct BitOp {
+    op: BitwiseOp,
+    dest: String,
+    keys: Vec<String>,
+}
+
+/// BITPOS key bit [start [end [BYTE | BIT]]]
+#[derive(Debug)]
+pub struct BitPos {
+    key: String,
+    bit: bool,
+    range: Option<(i64, Option<i64>, BitUnit)>,
+}
+
+impl CommandExecutor for SetBit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.setbit(self.key, self.offset, self.bit) {
+            Some(old) => RespFrame::Integer(old as i64),
+            None => SimpleError::new(WRONGTYPE).into(),
+        }
+    }
+}
+
+impl CommandExecutor for GetBit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.getbit(&self.key, self.offset) {
+            Some(bit) => RespFrame::Integer(bit as i64),
+            None => SimpleError::new(WRONGTYPE).into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitcount(&self.key, self.range) {
+            Some(count) => RespFrame::Integer(count as i64),
+            None => SimpleError::new(WRONGTYPE).into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitOp {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitop(self.op, self.dest, &self.keys) {
+            Some(len) => RespFrame::Integer(len as i64),
+            None => SimpleError::new(WRONGTYPE).into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitPos {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitpos(&self.key, self.bit, self.range) {
+            Some(pos) => RespFrame::Integer(pos),
+            None => SimpleError::new(WRONGTYPE).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SetBit {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "setbit", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(offset)))),
+                Some(RespFrame::BulkString(BulkString(Some(bit)))),
+            ) => Ok(SetBit {
+                key: String::from_utf8(key)?,
+                offset: parse_offset(offset)?,
+                bit: parse_bit(&bit)?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, offset or value".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for GetBit {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "getbit", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(offset)))),
+            ) => Ok(GetBit {
+                key: String::from_utf8(key)?,
+                offset: parse_offset(offset)?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or offset".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BitCount {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "bitcount", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => String::from_utf8(key)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        let range = match (args.next(), args.next(), args.next(), args.next()) {
+            (None, None, None, None) => None,
+            (
+                Some(RespFrame::BulkString(BulkString(Some(start)))),
+                Some(RespFrame::BulkString(BulkString(Some(end)))),
+                unit,
+                None,
+            ) => {
+                let unit = match unit {
+                    None => BitUnit::Byte,
+                    Some(RespFrame::BulkString(BulkString(Some(unit)))) => parse_unit(&unit)?,
+                    Some(_) => return Err(syntax_error()),
+                };
+                Some((parse_index(start)?, parse_index(end)?, unit))
+            }
+            _ => return Err(syntax_error()),
+        };
+
+        Ok(BitCount { key, range })
+    }
+}
+
+impl TryFrom<RespArray> for BitOp {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "bitop", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let (op, dest) = match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(op)))),
+                Some(RespFrame::BulkString(BulkString(Some(dest)))),
+            ) => (parse_op(&op)?, String::from_utf8(dest)?),
+            _ => return Err(syntax_error()),
+        };
+
+        let keys = args
+            .map(|arg| match arg {
+                RespFrame::BulkString(BulkString(Some(key))) => Ok(String::from_utf8(key)?),
+                _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+
+        if op == BitwiseOp::Not && keys.len() != 1 {
+            return Err(CommandError::InvalidArgument(
+                "BITOP NOT must be called with a single source key.".to_string(),
+            ));
+        }
+
+        Ok(BitOp { op, dest, keys })
+    }
+}
+
+impl TryFrom<RespArray> for BitPos {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "bitpos", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let (key, bit) = match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(bit)))),
+            ) => (String::from_utf8(key)?, parse_bit(&bit)?),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key or bit".to_string(),
+                ))
+            }
+        };
+
+        let range = match (args.next(), args.next(), args.next(), args.next()) {
+            (None, None, None, None) => None,
+            (Some(RespFrame::BulkString(BulkString(Some(start)))), end, unit, None) => {
+                let end = match end {
+                    None => None,
+                    Some(RespFrame::BulkString(BulkString(Some(end)))) => Some(parse_index(end)?),
+                    Some(_) => return Err(syntax_error()),
+                };
+                let unit = match unit {
+                    None => BitUnit::Byte,
+                    Some(RespFrame::BulkString(BulkString(Some(unit)))) => parse_unit(&unit)?,
+                    Some(_) => return Err(syntax_error()),
+                };
+                Some((parse_index(start)?, end, unit))
+            }
+            _ => return Err(syntax_error()),
+        };
+
+        Ok(BitPos { key, bit, range })
+    }
+}
+
+pub(super) fn parse_offset(arg: Vec<u8>) -> Result<u64, CommandError> {
+    parse_number(arg, |o| *o <= MAX_BIT_OFFSET).ok_or_else(|| {
+        CommandError::InvalidArgument("bit offset is not an integer or out of range".to_string())
+    })
+}
+
+pub(super) fn parse_index(arg: Vec<u8>) -> Result<i64, CommandError> {
+    parse_number(arg, |_| true).ok_or_else(|| {
+        CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+    })
+}
+
+pub(super) fn parse_unit(arg: &[u8]) -> Result<BitUnit, CommandError> {
+    if arg.eq_ignore_ascii_case(b"byte") {
+        Ok(BitUnit::Byte)
+    } else if arg.eq_ignore_ascii_case(b"bit") {
+        Ok(BitUnit::Bit)
+    } else {
+        Err(syntax_error())
+    }
+}
+
+fn parse_bit(arg: &[u8]) -> Result<bool, CommandError> {
+    match arg {
+        b"0" => Ok(false),
+        b"1" => Ok(true),
+        _ => Err(CommandError::InvalidArgument(
+            "The bit argument must be 1 or 0.".to_string(),
+        )),
+    }
+}
+
+fn parse_op(arg: &[u8]) -> Result<BitwiseOp, CommandError> {
+    match arg.to_ascii_lowercase().as_slice() {
+        b"and" => Ok(BitwiseOp::And),
+        b"or" => Ok(BitwiseOp::Or),
+        b"xor" => Ok(BitwiseOp::Xor),
+        b"not" => Ok(BitwiseOp::Not),
+        _ => Err(syntax_error()),
+    }
+}
+
+fn syntax_error() -> CommandError {
+    CommandError::InvalidArgument("syntax error".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn parse_args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    #[test]
+    fn test_setbit_getbit_try_from() -> Result<()> {
+        let result = SetBit::try_from(parse_args(&["setbit", "key", "7", "1"]))?;
+        assert_eq!(result.key, "key");
+        assert_eq!(result.offset, 7);
+        assert!(result.bit);
+        assert!(SetBit::try_from(parse_args(&["setbit", "key", "7", "2"])).is_err());
+        assert!(SetBit::try_from(parse_args(&["setbit", "key", "-1", "1"])).is_err());
+        assert!(SetBit::try_from(parse_args(&["setbit", "key", "4294967296", "1"])).is_err());
+
+        let result = GetBit::try_from(parse_args(&["getbit", "key", "4294967295"]))?;
+        assert_eq!(result.offset, MAX_BIT_OFFSET);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitcount_try_from() -> Result<()> {
+        let result = BitCount::try_from(parse_args(&["bitcount", "key"]))?;
+        assert_eq!(result.range, None);
+        let result = BitCount::try_from(parse_args(&["bitcount", "key", "1", "-1"]))?;
+        assert_eq!(result.range, Some((1, -1, BitUnit::Byte)));
+        let result = BitCount::try_from(parse_args(&["bitcount", "key", "0", "5", "BIT"]))?;
+        assert_eq!(result.range, Some((0, 5, BitUnit::Bit)));
+        assert!(BitCount::try_from(parse_args(&["bitcount", "key", "0"])).is_err());
+        assert!(BitCount::try_from(parse_args(&["bitcount", "key", "0", "1", "word"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitop_try_from() -> Result<()> {
+        let result = BitOp::try_from(parse_args(&["bitop", "xor", "dest", "a", "b"]))?;
+        assert_eq!(result.op, BitwiseOp::Xor);
+        assert_eq!(result.dest, "dest");
+        assert_eq!(result.keys, vec!["a", "b"]);
+        assert!(BitOp::try_from(parse_args(&["bitop", "NOT", "dest", "a"])).is_ok());
+        assert!(BitOp::try_from(parse_args(&["bitop", "not", "dest", "a", "b"])).is_err());
+        assert!(BitOp::try_from(parse_args(&["bitop", "nand", "dest", "a"])).is_err());
+        assert!(BitOp::try_from(parse_args(&["bitop", "and", "dest"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitpos_try_from() -> Result<()> {
+        let result = BitPos::try_from(parse_args(&["bitpos", "key", "0"]))?;
+        assert!(!result.bit);
+        assert_eq!(result.range, None);
+        let result = BitPos::try_from(parse_args(&["bitpos", "key", "1", "2"]))?;
+        assert_eq!(result.range, Some((2, None, BitUnit::Byte)));
+        let result = BitPos::try_from(parse_args(&["bitpos", "key", "1", "2", "-1", "bit"]))?;
+        assert_eq!(result.range, Some((2, Some(-1), BitUnit::Bit)));
+        assert!(BitPos::try_from(parse_args(&["bitpos", "key", "2"])).is_err());
+        assert!(BitPos::try_from(parse_args(&["bitpos", "key", "1", "0", "1", "word"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitmap_commands() {
+        let backend = Backend::new();
+        let setbit = |offset, bit| SetBit {
+            key: "key".to_string(),
+            offset,
+            bit,
+        };
+        assert_eq!(setbit(1, true).execute(&backend), 0.into());
+        assert_eq!(setbit(1, true).execute(&backend), 1.into());
+        assert_eq!(setbit(20, true).execute(&backend), 0.into());
+
+        let getbit = GetBit {
+            key: "key".to_string(),
+            offset: 20,
+        };
+        assert_eq!(getbit.execute(&backend), 1.into());
+
+        let bitcount = BitCount {
+            key: "key".to_string(),
+            range: None,
+        };
+        assert_eq!(bitcount.execute(&backend), 2.into());
+
+        let bitop = BitOp {
+            op: BitwiseOp::Not,
+            dest: "dest".to_string(),
+            keys: vec!["key".to_string()],
+        };
+        assert_eq!(bitop.execute(&backend), 3.into());
+        let bitpos = BitPos {
+            key: "dest".to_string(),
+            bit: false,
+            range: None,
+        };
+        assert_eq!(bitpos.execute(&backend), 1.into());
+
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        let getbit = GetBit {
+            key: "set".to_string(),
+            offset: 0,
+        };
+        assert_eq!(getbit.execute(&backend), SimpleError::new(WRONGTYPE).into());
+    }
+}
@@ -0,0 +1,540 @@
+use super::{
+    extract_args, parse_number, validate_command, validate_dynamic_command, CommandError,
+    CommandExecutor, RESP_OK,
+};
+use crate::{Backend, BloomFilter, BulkString, CuckooFilter, RespArray, RespFrame, SimpleError};
+use std::str::FromStr;
+
+/// BF.RESERVE key error_rate capacity [EXPANSION expansion] [NONSCALING]
+#[derive(Debug)]
+pub struct BfReserve {
+    key: String,
+    error_rate: f64,
+    capacity: usize,
+    // None for a non scaling filter
+    expansion: Option<u32>,
+}
+
+#[derive(Debug)]
+pub struct BfAdd {
+    key: String,
+    item: String,
+}
+
+#[derive(Debug)]
+pub struct BfExists {
+    key: String,
+    item: String,
+}
+
+/// CF.RESERVE key capacity [BUCKETSIZE bucketsize] [MAXITERATIONS maxiterations]
+/// [EXPANSION expansion]
+#[derive(Debug)]
+pub struct CfReserve {
+    key: String,
+    capacity: usize,
+    bucket_size: usize,
+    max_iterations: usize,
+    // 0 for a non scaling filter
+    expansion: u32,
+}
+
+#[derive(Debug)]
+pub struct CfAdd {
+    key: String,
+    item: String,
+}
+
+#[derive(Debug)]
+pub struct CfExists {
+    key: String,
+    item: String,
+}
+
+#[derive(Debug)]
+pub struct CfDel {
+    key: String,
+    item: String,
+}
+
+impl CommandExecutor for BfReserve {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let filter = BloomFilter::new(self.error_rate, self.capacity, self.expansion);
+        if backend.bf_reserve(self.key, filter) {
+            RESP_OK.clone()
+        } else {
+            SimpleError::new("ERR item exists").into()
+        }
+    }
+}
+
+impl CommandExecutor for BfAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bf_add(self.key, &self.item) {
+            Ok(Some(added)) => RespFrame::Integer(added as i64),
+            Ok(None) => SimpleError::new("ERR non scaling filter is full").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for BfExists {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bf_exists(&self.key, &self.item) {
+            Ok(exists) => RespFrame::Integer(exists as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for CfReserve {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let filter = CuckooFilter::new(
+            self.capacity,
+            self.bucket_size,
+            self.max_iterations,
+            self.expansion,
+        );
+        if backend.cf_reserve(self.key, filter) {
+            RESP_OK.clone()
+        } else {
+            SimpleError::new("ERR item exists").into()
+        }
+    }
+}
+
+impl CommandExecutor for CfAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.cf_add(self.key, &self.item) {
+            Ok(true) => RespFrame::Integer(1),
+            Ok(false) => SimpleError::new("ERR Filter is full").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for CfExists {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.cf_exists(&self.key, &self.item) {
+            Ok(exists) => RespFrame::Integer(exists as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for CfDel {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.cf_del(&self.key, &self.item) {
+            Ok(Some(deleted)) => RespFrame::Integer(deleted as i64),
+            Ok(None) => SimpleError::new("ERR Not found").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BfReserve {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "bf.reserve", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, error_rate, capacity) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(error_rate)))),
+                Some(RespFrame::BulkString(BulkString(Some(capacity)))),
+            ) => (String::from_utf8(key)?, error_rate, capacity),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        let error_rate =
+            parse_number(error_rate, |r: &f64| *r > 0.0 && *r < 1.0).ok_or_else(|| {
+                CommandError::InvalidArgument("(0 < error rate range < 1)".to_string())
+            })?;
+        let capacity = parse_number(capacity, |c: &usize| *c > 0).ok_or_else(|| {
+            CommandError::InvalidArgument("(capacity should be larger than 0)".to_string())
+        })?;
+
+        let mut expansion = Some(BloomFilter::DEFAULT_EXPANSION);
+        let mut nonscaling = false;
+        while let Some(arg) = args.next() {
+            match arg {
+                RespFrame::BulkString(BulkString(Some(option)))
+                    if option.eq_ignore_ascii_case(b"nonscaling") =>
+                {
+                    nonscaling = true;
+                }
+                RespFrame::BulkString(BulkString(Some(option)))
+                    if option.eq_ignore_ascii_case(b"expansion") =>
+                {
+                    let n = parse_option_value(args.next(), |n: &u32| *n > 0).ok_or_else(|| {
+                        CommandError::InvalidArgument(
+                            "(expansion should be greater or equal to 1)".to_string(),
+                        )
+                    })?;
+                    expansion = Some(n);
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
+        }
+        if nonscaling {
+            expansion = None;
+        }
+
+        Ok(BfReserve {
+            key,
+            error_rate,
+            capacity,
+            expansion,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for CfReserve {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "cf.reserve", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, capacity) = match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(capacity)))),
+            ) => (String::from_utf8(key)?, capacity),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let capacity = parse_number(capacity, |c: &usize| *c > 0).ok_or_else(|| {
+            CommandError::InvalidArgument("(capacity should be larger than 0)".to_string())
+        })?;
+
+        let mut cmd = CfReserve {
+            key,
+            capacity,
+            bucket_size: CuckooFilter::DEFAULT_BUCKET_SIZE,
+            max_iterations: CuckooFilter::DEFAULT_MAX_ITERATIONS,
+            expansion: CuckooFilter::DEFAULT_EXPANSION,
+        };
+        while let Some(arg) = args.next() {
+            let option = match arg {
+                RespFrame::BulkString(BulkString(Some(option))) => option.to_ascii_lowercase(),
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            };
+            match option.as_slice() {
+                b"bucketsize" => {
+                    cmd.bucket_size =
+                        parse_option_value(args.next(), |n: &usize| (1..=255).contains(n))
+                            .ok_or_else(|| {
+                                CommandError::InvalidArgument(
+                                    "Bucket size must be between 1 and 255".to_string(),
+                                )
+                            })?;
+                }
+                b"maxiterations" => {
+                    cmd.max_iterations = parse_option_value(args.next(), |n: &usize| *n > 0)
+                        .ok_or_else(|| {
+                            CommandError::InvalidArgument(
+                                "MAXITERATIONS must be positive".to_string(),
+                            )
+                        })?;
+                }
+                b"expansion" => {
+                    cmd.expansion = parse_option_value(args.next(), |n: &u32| *n <= 32768)
+                        .ok_or_else(|| {
+                            CommandError::InvalidArgument(
+                                "EXPANSION must be in range [0, 32768]".to_string(),
+                            )
+                        })?;
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
+        }
+
+        Ok(cmd)
+    }
+}
+
+impl TryFrom<RespArray> for BfAdd {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "bf.add", 2)?;
+        let (key, item) = extract_key_item(value)?;
+        Ok(BfAdd { key, item })
+    }
+}
+
+impl TryFrom<RespArray> for BfExists {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "bf.exists", 2)?;
+        let (key, item) = extract_key_item(value)?;
+        Ok(BfExists { key, item })
+    }
+}
+
+impl TryFrom<RespArray> for CfAdd {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "cf.add", 2)?;
+        let (key, item) = extract_key_item(value)?;
+        Ok(CfAdd { key, item })
+    }
+}
+
+impl TryFrom<RespArray> for CfExists {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "cf.exists", 2)?;
+        let (key, item) = extract_key_item(value)?;
+        Ok(CfExists { key, item })
+    }
+}
+
+impl TryFrom<RespArray> for CfDel {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "cf.del", 2)?;
+        let (key, item) = extract_key_item(value)?;
+        Ok(CfDel { key, item })
+    }
+}
+
+// parse the value following an option name, None if it is missing or invalid
+fn parse_option_value<T: FromStr>(arg: Option<RespFrame>, valid: impl Fn(&T) -> bool) -> Option<T> {
+    match arg {
+        Some(RespFrame::BulkString(BulkString(Some(arg)))) => parse_number(arg, valid),
+        _ => None,
+    }
+}
+
+fn extract_key_item(value: RespArray) -> Result<(String, String), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+
+    match (args.next(), args.next()) {
+        (
+            Some(RespFrame::BulkString(BulkString(Some(key)))),
+            Some(RespFrame::BulkString(BulkString(Some(item)))),
+        ) => Ok((String::from_utf8(key)?, String::from_utf8(item)?)),
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid key or item".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn parse_args(args: &[&str]) -> RespArray {
+        RespArray::new(
+            args.iter()
+                .map(|a| BulkString::new(a.as_bytes()).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+    }
+
+    #[test]
+    fn test_bf_reserve_try_from() -> Result<()> {
+        let result = BfReserve::try_from(parse_args(&["bf.reserve", "key", "0.01", "1000"]))?;
+        assert_eq!(result.key, "key");
+        assert_eq!(result.error_rate, 0.01);
+        assert_eq!(result.capacity, 1000);
+        assert_eq!(result.expansion, Some(BloomFilter::DEFAULT_EXPANSION));
+
+        let result = BfReserve::try_from(parse_args(&[
+            "BF.RESERVE",
+            "key",
+            "0.001",
+            "10",
+            "EXPANSION",
+            "4",
+        ]))?;
+        assert_eq!(result.expansion, Some(4));
+
+        let result = BfReserve::try_from(parse_args(&[
+            "bf.reserve",
+            "key",
+            "0.1",
+            "10",
+            "nonscaling",
+        ]))?;
+        assert_eq!(result.expansion, None);
+
+        assert!(BfReserve::try_from(parse_args(&["bf.reserve", "key", "1", "10"])).is_err());
+        assert!(BfReserve::try_from(parse_args(&["bf.reserve", "key", "0.1", "0"])).is_err());
+        assert!(
+            BfReserve::try_from(parse_args(&["bf.reserve", "key", "0.1", "10", "expansion"]))
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bf_add_exists_try_from() -> Result<()> {
+        let result = BfAdd::try_from(parse_args(&["bf.add", "key", "item"]))?;
+        assert_eq!((result.key.as_str(), result.item.as_str()), ("key", "item"));
+
+        let result = BfExists::try_from(parse_args(&["BF.EXISTS", "key", "item"]))?;
+        assert_eq!((result.key.as_str(), result.item.as_str()), ("key", "item"));
+
+        assert!(BfAdd::try_from(parse_args(&["bf.add", "key"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bf_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = BfReserve {
+            key: "bf".to_string(),
+            error_rate: 0.01,
+            capacity: 2,
+            expansion: None,
+        };
+        assert_eq!(cmd.execute(&backend), super::RESP_OK.clone());
+        let cmd = BfReserve {
+            key: "bf".to_string(),
+            error_rate: 0.01,
+            capacity: 2,
+            expansion: None,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR item exists").into()
+        );
+
+        for (item, expected) in [("a", 1), ("a", 0), ("b", 1)] {
+            let cmd = BfAdd {
+                key: "bf".to_string(),
+                item: item.to_string(),
+            };
+            assert_eq!(cmd.execute(&backend), RespFrame::Integer(expected));
+        }
+        let cmd = BfAdd {
+            key: "bf".to_string(),
+            item: "c".to_string(),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR non scaling filter is full").into()
+        );
+
+        for (key, item, expected) in [("bf", "a", 1), ("bf", "c", 0), ("missing", "a", 0)] {
+            let cmd = BfExists {
+                key: key.to_string(),
+                item: item.to_string(),
+            };
+            assert_eq!(cmd.execute(&backend), RespFrame::Integer(expected));
+        }
+
+        // adding to a missing key creates a default filter
+        let cmd = BfAdd {
+            key: "auto".to_string(),
+            item: "a".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(backend.exists("auto"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cf_reserve_try_from() -> Result<()> {
+        let result = CfReserve::try_from(parse_args(&["cf.reserve", "key", "1000"]))?;
+        assert_eq!(result.key, "key");
+        assert_eq!(result.capacity, 1000);
+        assert_eq!(result.bucket_size, CuckooFilter::DEFAULT_BUCKET_SIZE);
+        assert_eq!(result.max_iterations, CuckooFilter::DEFAULT_MAX_ITERATIONS);
+        assert_eq!(result.expansion, CuckooFilter::DEFAULT_EXPANSION);
+
+        let result = CfReserve::try_from(parse_args(&[
+            "CF.RESERVE",
+            "key",
+            "1000",
+            "BUCKETSIZE",
+            "4",
+            "MAXITERATIONS",
+            "50",
+            "EXPANSION",
+            "0",
+        ]))?;
+        assert_eq!(
+            (result.bucket_size, result.max_iterations, result.expansion),
+            (4, 50, 0)
+        );
+
+        assert!(CfReserve::try_from(parse_args(&["cf.reserve", "key", "0"])).is_err());
+        assert!(
+            CfReserve::try_from(parse_args(&["cf.reserve", "key", "10", "bucketsize", "0"]))
+                .is_err()
+        );
+        assert!(CfReserve::try_from(parse_args(&["cf.reserve", "key", "10", "foo"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cf_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = CfReserve {
+            key: "cf".to_string(),
+            capacity: 2,
+            bucket_size: 1,
+            max_iterations: 1,
+            expansion: 0,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        let cmd = CfAdd {
+            key: "cf".to_string(),
+            item: "a".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        // the filter only has 2 slots
+        let ret: Vec<RespFrame> = ["b", "c", "d"]
+            .iter()
+            .map(|item| {
+                CfAdd {
+                    key: "cf".to_string(),
+                    item: item.to_string(),
+                }
+                .execute(&backend)
+            })
+            .collect();
+        assert!(ret.contains(&SimpleError::new("ERR Filter is full").into()));
+
+        let cmd = CfExists {
+            key: "cf".to_string(),
+            item: "a".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        for expected in [1, 0] {
+            let cmd = CfDel {
+                key: "cf".to_string(),
+                item: "a".to_string(),
+            };
+            assert_eq!(cmd.execute(&backend), RespFrame::Integer(expected));
+        }
+        let cmd = CfDel {
+            key: "missing".to_string(),
+            item: "a".to_string(),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR Not found").into()
+        );
+
+        Ok(())
+    }
+}
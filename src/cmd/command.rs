@@ -0,0 +1,402 @@
+use super::{extract_args, validate_dynamic_command, CommandError, CommandExecutor};
+use super::{lowercase_name, MAX_COMMAND_LEN};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SimpleString};
+use phf::phf_ordered_map;
+
+/// Static description of a built-in command, as served by COMMAND.
+#[derive(Debug, PartialEq)]
+pub struct CommandSpec {
+    // number of arguments including the name, negative for at least that many
+    pub arity: i64,
+    pub flags: &'static [&'static str],
+    // position of the first and last key and the step between keys, 0 when there are none;
+    // a negative last key counts from the end of the arguments
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+    pub group: &'static str,
+    pub summary: &'static str,
+}
+
+const fn spec(
+    arity: i64,
+    flags: &'static [&'static str],
+    (first_key, last_key, step): (i64, i64, i64),
+    group: &'static str,
+    summary: &'static str,
+) -> CommandSpec {
+    CommandSpec {
+        arity,
+        flags,
+        first_key,
+        last_key,
+        step,
+        group,
+        summary,
+    }
+}
+
+const READ: &[&str] = &["readonly", "fast"];
+const READ_SLOW: &[&str] = &["readonly"];
+const WRITE: &[&str] = &["write", "denyoom"];
+const WRITE_FAST: &[&str] = &["write", "denyoom", "fast"];
+const DELETE: &[&str] = &["write", "fast"];
+const ADMIN: &[&str] = &["admin", "noscript", "loading", "stale"];
+const NO_KEYS: (i64, i64, i64) = (0, 0, 0);
+const ONE_KEY: (i64, i64, i64) = (1, 1, 1);
+const ALL_KEYS: (i64, i64, i64) = (1, -1, 1);
+
+/// Every built-in command keyed by name, in the order they are listed by COMMAND.
+///
+/// The commands served by the connection itself, such as MULTI, are listed along with the
+/// others. Commands registered by modules are not, they declare no arity nor keys.
+#[rustfmt::skip]
+static COMMAND_TABLE: phf::OrderedMap<&'static str, CommandSpec> = phf_ordered_map! {
+    "get" => spec(2, READ, ONE_KEY, "string", "Returns the string value of a key."),
+    "set" => spec(-3, WRITE, ONE_KEY, "string", "Sets the string value of a key, ignoring its type."),
+    "getex" => spec(-2, &["write", "fast"], ONE_KEY, "string", "Returns the string value of a key after setting its expiration time."),
+    "hget" => spec(3, READ, ONE_KEY, "hash", "Returns the value of a field in a hash."),
+    "hset" => spec(4, WRITE_FAST, ONE_KEY, "hash", "Sets the value of a field in a hash."),
+    "hsetnx" => spec(4, WRITE_FAST, ONE_KEY, "hash", "Sets the value of a field in a hash only when the field doesn't exist."),
+    "hgetall" => spec(2, READ_SLOW, ONE_KEY, "hash", "Returns all fields and values in a hash."),
+    "hmget" => spec(-3, READ, ONE_KEY, "hash", "Returns the values of all fields in a hash."),
+    "hexists" => spec(3, READ, ONE_KEY, "hash", "Determines whether a field exists in a hash."),
+    "hlen" => spec(2, READ, ONE_KEY, "hash", "Returns the number of fields in a hash."),
+    "hstrlen" => spec(3, READ, ONE_KEY, "hash", "Returns the length of the value of a field."),
+    "echo" => spec(2, &["fast", "loading", "stale"], NO_KEYS, "connection", "Returns the given string."),
+    "sadd" => spec(-3, WRITE_FAST, ONE_KEY, "set", "Adds one or more members to a set."),
+    "sismember" => spec(3, READ, ONE_KEY, "set", "Determines whether a member belongs to a set."),
+    "smismember" => spec(-3, READ, ONE_KEY, "set", "Determines whether multiple members belong to a set."),
+    "scard" => spec(2, READ, ONE_KEY, "set", "Returns the number of members in a set."),
+    "smembers" => spec(2, READ_SLOW, ONE_KEY, "set", "Returns all members of a set."),
+    "sunionstore" => spec(-3, WRITE, ALL_KEYS, "set", "Stores the union of multiple sets in a key."),
+    "sinterstore" => spec(-3, WRITE, ALL_KEYS, "set", "Stores the intersect of multiple sets in a key."),
+    "sdiffstore" => spec(-3, WRITE, ALL_KEYS, "set", "Stores the difference of multiple sets in a key."),
+    "sintercard" => spec(-3, &["readonly", "movablekeys"], NO_KEYS, "set", "Returns the number of members of the intersect of multiple sets."),
+    "setbit" => spec(4, WRITE, ONE_KEY, "bitmap", "Sets or clears the bit at offset of the string value."),
+    "getbit" => spec(3, READ, ONE_KEY, "bitmap", "Returns a bit value by offset."),
+    "bitcount" => spec(-2, READ_SLOW, ONE_KEY, "bitmap", "Counts the number of set bits in a string."),
+    "bitop" => spec(-4, WRITE, (2, -1, 1), "bitmap", "Performs bitwise operations on multiple strings, and stores the result."),
+    "bitpos" => spec(-3, READ_SLOW, ONE_KEY, "bitmap", "Finds the first set or clear bit in a string."),
+    "pfadd" => spec(-2, WRITE_FAST, ONE_KEY, "hyperloglog", "Adds elements to a HyperLogLog key."),
+    "pfcount" => spec(-2, READ_SLOW, ALL_KEYS, "hyperloglog", "Returns the approximated cardinality of the sets observed by HyperLogLog keys."),
+    "pfmerge" => spec(-2, WRITE, ALL_KEYS, "hyperloglog", "Merges one or more HyperLogLog values into a single key."),
+    "bf.reserve" => spec(-4, WRITE, ONE_KEY, "bf", "Creates a new Bloom Filter."),
+    "bf.add" => spec(3, WRITE_FAST, ONE_KEY, "bf", "Adds an item to a Bloom Filter."),
+    "bf.exists" => spec(3, READ, ONE_KEY, "bf", "Checks whether an item exists in a Bloom Filter."),
+    "cf.reserve" => spec(-3, WRITE, ONE_KEY, "cf", "Creates a new Cuckoo Filter."),
+    "cf.add" => spec(3, WRITE_FAST, ONE_KEY, "cf", "Adds an item to a Cuckoo Filter."),
+    "cf.exists" => spec(3, READ, ONE_KEY, "cf", "Checks whether an item exists in a Cuckoo Filter."),
+    "cf.del" => spec(3, DELETE, ONE_KEY, "cf", "Deletes an item from a Cuckoo Filter."),
+    "cms.initbydim" => spec(4, WRITE, ONE_KEY, "cms", "Initializes a Count-Min Sketch to dimensions specified by user."),
+    "cms.initbyprob" => spec(4, WRITE, ONE_KEY, "cms", "Initializes a Count-Min Sketch to accommodate requested tolerances."),
+    "cms.incrby" => spec(-4, WRITE_FAST, ONE_KEY, "cms", "Increases the count of one or more items by increment."),
+    "cms.query" => spec(-3, READ, ONE_KEY, "cms", "Returns the count for one or more items in a sketch."),
+    "cms.info" => spec(2, READ, ONE_KEY, "cms", "Returns information about a sketch."),
+    "topk.reserve" => spec(-3, WRITE, ONE_KEY, "topk", "Initializes a TopK with specified parameters."),
+    "topk.add" => spec(-3, WRITE_FAST, ONE_KEY, "topk", "Increases the count of one or more items by increment."),
+    "topk.query" => spec(-3, READ, ONE_KEY, "topk", "Checks whether one or more items are in a sketch."),
+    "topk.list" => spec(-2, READ, ONE_KEY, "topk", "Returns the full list of items in the TopK list."),
+    "topk.info" => spec(2, READ, ONE_KEY, "topk", "Returns information about a sketch."),
+    "ts.create" => spec(-2, WRITE, ONE_KEY, "timeseries", "Creates a new time series."),
+    "ts.add" => spec(-4, WRITE_FAST, ONE_KEY, "timeseries", "Appends a sample to a time series."),
+    "ts.get" => spec(2, READ, ONE_KEY, "timeseries", "Gets the sample with the highest timestamp from a given time series."),
+    "ts.range" => spec(-4, READ_SLOW, ONE_KEY, "timeseries", "Queries a range in forward direction."),
+    "ts.createrule" => spec(6, WRITE, (1, 2, 1), "timeseries", "Creates a compaction rule."),
+    "keys" => spec(2, &["readonly"], NO_KEYS, "generic", "Returns all key names that match a pattern."),
+    "scan" => spec(-2, &["readonly"], NO_KEYS, "generic", "Iterates over the key names in the database."),
+    "copy" => spec(-3, WRITE, (1, 2, 1), "generic", "Copies the value of a key to a new key."),
+    "move" => spec(3, DELETE, ONE_KEY, "generic", "Moves a key to another database."),
+    "expire" => spec(-3, DELETE, ONE_KEY, "generic", "Sets the expiration time of a key in seconds."),
+    "pexpire" => spec(-3, DELETE, ONE_KEY, "generic", "Sets the expiration time of a key in milliseconds."),
+    "expireat" => spec(-3, DELETE, ONE_KEY, "generic", "Sets the expiration time of a key to a Unix timestamp."),
+    "pexpireat" => spec(-3, DELETE, ONE_KEY, "generic", "Sets the expiration time of a key to a Unix milliseconds timestamp."),
+    "ttl" => spec(2, READ, ONE_KEY, "generic", "Returns the expiration time in seconds of a key."),
+    "pttl" => spec(2, READ, ONE_KEY, "generic", "Returns the expiration time in milliseconds of a key."),
+    "expiretime" => spec(2, READ, ONE_KEY, "generic", "Returns the expiration time of a key as a Unix timestamp."),
+    "pexpiretime" => spec(2, READ, ONE_KEY, "generic", "Returns the expiration time of a key as a Unix milliseconds timestamp."),
+    "unlink" => spec(-2, DELETE, ALL_KEYS, "generic", "Asynchronously deletes one or more keys."),
+    "touch" => spec(-2, READ, ALL_KEYS, "generic", "Returns the number of existing keys out of those specified after updating the time they were last accessed."),
+    "object" => spec(3, READ_SLOW, (2, 2, 1), "generic", "Returns information about the internals of a key."),
+    "dump" => spec(2, READ_SLOW, ONE_KEY, "generic", "Returns a serialized representation of the value stored at a key."),
+    "restore" => spec(-4, WRITE, ONE_KEY, "generic", "Creates a key from the serialized representation of a value."),
+    "role" => spec(1, &["noscript", "loading", "stale", "fast"], NO_KEYS, "server", "Returns the replication role."),
+    "lastsave" => spec(1, &["loading", "stale", "fast"], NO_KEYS, "server", "Returns the Unix timestamp of the last successful save to disk."),
+    "info" => spec(-1, &["loading", "stale"], NO_KEYS, "server", "Returns information and statistics about the server."),
+    "memory" => spec(-2, READ_SLOW, (2, 2, 1), "server", "A container for memory diagnostics commands."),
+    "bigkeys" => spec(2, ADMIN, NO_KEYS, "server", "Starts or reports a background scan for the largest keys."),
+    "config" => spec(-2, ADMIN, NO_KEYS, "server", "Gets, sets or persists configuration parameters."),
+    "debug" => spec(-2, ADMIN, NO_KEYS, "server", "A container for debugging commands."),
+    "command" => spec(-1, &["loading", "stale"], NO_KEYS, "server", "Returns detailed information about all commands."),
+    "shutdown" => spec(-1, ADMIN, NO_KEYS, "server", "Shuts down the server."),
+    "spublish" => spec(3, &["pubsub", "loading", "stale", "fast"], ONE_KEY, "pubsub", "Posts a message to a shard channel."),
+    "multi" => spec(1, &["noscript", "loading", "stale", "fast", "allow_busy"], NO_KEYS, "transactions", "Starts a transaction."),
+    "exec" => spec(1, &["noscript", "loading", "stale", "skip_slowlog"], NO_KEYS, "transactions", "Executes all commands in a transaction."),
+    "discard" => spec(1, &["noscript", "loading", "stale", "fast", "allow_busy"], NO_KEYS, "transactions", "Discards a transaction."),
+    "module" => spec(-2, ADMIN, NO_KEYS, "server", "A container for module commands."),
+    "client" => spec(-2, &["noscript", "loading", "stale"], NO_KEYS, "connection", "A container for client connection commands."),
+    "ssubscribe" => spec(-2, &["pubsub", "noscript", "loading", "stale"], ALL_KEYS, "pubsub", "Listens for messages published to shard channels."),
+    "sunsubscribe" => spec(-1, &["pubsub", "noscript", "loading", "stale"], ALL_KEYS, "pubsub", "Stops listening to messages posted to shard channels."),
+    "cluster" => spec(-2, &["loading", "stale"], NO_KEYS, "cluster", "A container for Redis Cluster commands."),
+};
+
+// phf_ordered_map! can not conditionally compile entries, feature gated commands have their own
+#[cfg(feature = "json")]
+#[rustfmt::skip]
+static JSON_COMMAND_TABLE: phf::OrderedMap<&'static str, CommandSpec> = phf_ordered_map! {
+    "json.set" => spec(-4, WRITE, ONE_KEY, "json", "Sets or updates the JSON value at a path."),
+    "json.get" => spec(-2, READ_SLOW, ONE_KEY, "json", "Gets the value at one or more paths in JSON serialized form."),
+    "json.del" => spec(-2, DELETE, ONE_KEY, "json", "Deletes a value."),
+    "json.arrappend" => spec(-4, WRITE, ONE_KEY, "json", "Appends one or more JSON values into the array at path after the last element in it."),
+};
+
+#[cfg(feature = "wasm")]
+#[rustfmt::skip]
+static FUNCTION_COMMAND_TABLE: phf::OrderedMap<&'static str, CommandSpec> = phf_ordered_map! {
+    "function" => spec(-2, &["noscript"], NO_KEYS, "scripting", "A container for function commands."),
+    "fcall" => spec(-3, &["noscript", "stale", "movablekeys"], NO_KEYS, "scripting", "Invokes a function."),
+};
+
+/// COMMAND | COMMAND COUNT | COMMAND INFO [name ...] | COMMAND DOCS [name ...]
+#[derive(Debug, PartialEq)]
+pub enum CommandQuery {
+    List,
+    Count,
+    Info(Vec<String>),
+    Docs(Vec<String>),
+}
+
+/// Every built-in command and its description, in the order they are listed by COMMAND.
+pub fn command_table() -> impl Iterator<Item = (&'static str, &'static CommandSpec)> {
+    let table = COMMAND_TABLE.entries();
+    #[cfg(feature = "json")]
+    let table = table.chain(JSON_COMMAND_TABLE.entries());
+    #[cfg(feature = "wasm")]
+    let table = table.chain(FUNCTION_COMMAND_TABLE.entries());
+    table.map(|(name, spec)| (*name, spec))
+}
+
+/// The name and description of a built-in command, case-insensitive and without allocating.
+pub fn command_spec(name: &[u8]) -> Option<(&'static str, &'static CommandSpec)> {
+    let mut buf = [0u8; MAX_COMMAND_LEN];
+    let name = std::str::from_utf8(lowercase_name(name, &mut buf)?).ok()?;
+    #[cfg(feature = "json")]
+    if let Some((name, spec)) = JSON_COMMAND_TABLE.get_entry(name) {
+        return Some((*name, spec));
+    }
+    #[cfg(feature = "wasm")]
+    if let Some((name, spec)) = FUNCTION_COMMAND_TABLE.get_entry(name) {
+        return Some((*name, spec));
+    }
+    COMMAND_TABLE
+        .get_entry(name)
+        .map(|(name, spec)| (*name, spec))
+}
+
+impl CommandExecutor for CommandQuery {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self {
+            CommandQuery::Count => RespFrame::Integer(command_table().count() as i64),
+            CommandQuery::List => all_info_frame(),
+            CommandQuery::Info(names) if names.is_empty() => all_info_frame(),
+            CommandQuery::Info(names) => RespArray::new(
+                names
+                    .iter()
+                    .map(|name| {
+                        command_spec(name.as_bytes())
+                            .map(info_frame)
+                            .unwrap_or(RespFrame::Null(RespNull))
+                    })
+                    .collect(),
+            )
+            .into(),
+            CommandQuery::Docs(names) => {
+                let specs: Vec<_> = if names.is_empty() {
+                    command_table().collect()
+                } else {
+                    // unknown commands are left out
+                    names
+                        .iter()
+                        .filter_map(|name| command_spec(name.as_bytes()))
+                        .collect()
+                };
+                RespArray::new(
+                    specs
+                        .into_iter()
+                        .flat_map(|(name, spec)| [BulkString::new(name).into(), docs_frame(spec)])
+                        .collect(),
+                )
+                .into()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandQuery {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "command", 0)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let sub = match args.next() {
+            None => return Ok(CommandQuery::List),
+            Some(RespFrame::BulkString(BulkString(Some(sub)))) => sub.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+        let names = args
+            .map(|arg| match arg {
+                RespFrame::BulkString(BulkString(Some(name))) => Ok(String::from_utf8(name)?),
+                _ => Err(CommandError::InvalidArgument(
+                    "Invalid command name".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+
+        match sub.as_slice() {
+            b"count" if names.is_empty() => Ok(CommandQuery::Count),
+            b"count" => Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'command|count' command".to_string(),
+            )),
+            b"info" => Ok(CommandQuery::Info(names)),
+            b"docs" => Ok(CommandQuery::Docs(names)),
+            _ => Err(CommandError::InvalidArgument(
+                "unknown subcommand for 'command'".to_string(),
+            )),
+        }
+    }
+}
+
+fn all_info_frame() -> RespFrame {
+    RespArray::new(command_table().map(info_frame).collect()).into()
+}
+
+// name, arity, flags, first key, last key, step, and the empty ACL categories, tips, key
+// specifications and subcommands of the redis 7 reply
+fn info_frame((name, spec): (&str, &CommandSpec)) -> RespFrame {
+    let flags = spec
+        .flags
+        .iter()
+        .map(|flag| SimpleString::new(*flag).into())
+        .collect();
+    RespArray::new(vec![
+        BulkString::new(name).into(),
+        spec.arity.into(),
+        RespArray::new(flags).into(),
+        spec.first_key.into(),
+        spec.last_key.into(),
+        spec.step.into(),
+        RespArray::new(vec![]).into(),
+        RespArray::new(vec![]).into(),
+        RespArray::new(vec![]).into(),
+        RespArray::new(vec![]).into(),
+    ])
+    .into()
+}
+
+fn docs_frame(spec: &CommandSpec) -> RespFrame {
+    RespArray::new(vec![
+        BulkString::new("summary").into(),
+        BulkString::new(spec.summary).into(),
+        BulkString::new("group").into(),
+        BulkString::new(spec.group).into(),
+    ])
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::is_builtin;
+    use anyhow::Result;
+
+    // commands served by the connection rather than parsed into a command
+    const CONNECTION_COMMANDS: &[&str] = &[
+        "multi",
+        "exec",
+        "discard",
+        "module",
+        "client",
+        "ssubscribe",
+        "sunsubscribe",
+        "cluster",
+    ];
+
+    fn parse_args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    #[test]
+    fn test_command_table_matches_builtins() {
+        for (name, _) in command_table() {
+            let builtin = is_builtin(name.as_bytes()) || CONNECTION_COMMANDS.contains(&name);
+            assert!(builtin, "{} is not built in", name);
+            assert_eq!(name, name.to_ascii_lowercase());
+        }
+        let builtins = crate::cmd::COMMANDS.keys();
+        #[cfg(feature = "json")]
+        let builtins = builtins.chain(crate::cmd::JSON_COMMANDS.keys());
+        #[cfg(feature = "wasm")]
+        let builtins = builtins.chain(crate::cmd::FUNCTION_COMMANDS.keys());
+        for name in builtins {
+            let described = command_spec(name).is_some();
+            assert!(
+                described,
+                "{} is not described",
+                String::from_utf8_lossy(name)
+            );
+        }
+    }
+
+    #[test]
+    fn test_command_try_from() -> Result<()> {
+        assert_eq!(
+            CommandQuery::try_from(parse_args(&["command"]))?,
+            CommandQuery::List
+        );
+        assert_eq!(
+            CommandQuery::try_from(parse_args(&["command", "COUNT"]))?,
+            CommandQuery::Count
+        );
+        assert_eq!(
+            CommandQuery::try_from(parse_args(&["command", "info", "get", "set"]))?,
+            CommandQuery::Info(vec!["get".to_string(), "set".to_string()])
+        );
+        assert!(CommandQuery::try_from(parse_args(&["command", "count", "get"])).is_err());
+        assert!(CommandQuery::try_from(parse_args(&["command", "getkeys", "get"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_execute() {
+        let backend = Backend::new();
+        assert_eq!(
+            CommandQuery::Count.execute(&backend),
+            (command_table().count() as i64).into()
+        );
+
+        let info = CommandQuery::Info(vec!["GET".to_string(), "nope".to_string()]);
+        let RespFrame::Array(RespArray(Some(info))) = info.execute(&backend) else {
+            panic!("COMMAND INFO did not return an array");
+        };
+        assert_eq!(info[1], RespFrame::Null(RespNull));
+        let RespFrame::Array(RespArray(Some(get))) = &info[0] else {
+            panic!("COMMAND INFO did not describe GET");
+        };
+        assert_eq!(get[0], BulkString::new("get").into());
+        assert_eq!(get[1], 2.into());
+        assert_eq!(get[3..6], [1.into(), 1.into(), 1.into()]);
+
+        let docs = CommandQuery::Docs(vec!["ttl".to_string(), "nope".to_string()]);
+        let RespFrame::Array(RespArray(Some(docs))) = docs.execute(&backend) else {
+            panic!("COMMAND DOCS did not return an array");
+        };
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0], BulkString::new("ttl").into());
+    }
+}
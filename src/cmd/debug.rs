@@ -0,0 +1,537 @@
+use super::{
+    extract_args, parse_number, validate_command, validate_dynamic_command, CommandError,
+    CommandExecutor, RESP_OK,
+};
+#[cfg(feature = "json")]
+use crate::{now_ms, KeyType};
+use crate::{Backend, BulkString, RespArray, RespFrame, SimpleError, SimpleString};
+use enum_dispatch::enum_dispatch;
+#[cfg(feature = "json")]
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+// number of keys dumped by DEBUG JMAP when no LIMIT is given
+#[cfg(feature = "json")]
+const DEFAULT_JMAP_LIMIT: usize = 100;
+// number of keys of each list reported by DEBUG HOTKEYS when no COUNT is given
+const DEFAULT_HOTKEYS_COUNT: usize = 10;
+
+#[enum_dispatch(CommandExecutor)]
+#[derive(Debug)]
+pub enum DebugCommand {
+    #[cfg(feature = "json")]
+    Jmap(DebugJmap),
+    Hotkeys(DebugHotkeys),
+    Object(DebugObject),
+    Sleep(DebugSleep),
+}
+
+/// DEBUG JMAP pattern [LIMIT count], dump matching keys as a JSON array.
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub struct DebugJmap {
+    pattern: String,
+    limit: usize,
+}
+
+/// DEBUG HOTKEYS [COUNT count] | DEBUG HOTKEYS SAMPLE rate
+///
+/// Report the most accessed and the largest keys, or set the access sample rate. Accesses are
+/// not tracked until a sample rate is set, a rate of 1 counts them exactly.
+#[derive(Debug)]
+pub enum DebugHotkeys {
+    Report(usize),
+    Sample(u64),
+}
+
+/// DEBUG OBJECT key, describe the internal representation of the value of a key.
+#[derive(Debug)]
+pub struct DebugObject {
+    key: String,
+}
+
+/// DEBUG SLEEP seconds, block the server for a while, seconds may be fractional.
+///
+/// The wait is done by whoever runs the command, as reported by [`Command::delay`]. The server
+/// waits holding the transaction lock exclusively, so the commands of every other connection
+/// wait too, but without tying up a worker thread.
+///
+/// [`Command::delay`]: super::Command::delay
+#[derive(Debug)]
+pub struct DebugSleep {
+    pub(super) duration: Duration,
+}
+
+#[cfg(feature = "json")]
+impl CommandExecutor for DebugJmap {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut entries = Vec::new();
+        for key in backend.keys(&self.pattern).into_iter().take(self.limit) {
+            let ttl = backend
+                .expire_time(&key)
+                .map(|at| (at - now_ms()).max(0))
+                .unwrap_or(-1);
+            let size = backend.memory_usage(&key).unwrap_or(0);
+
+            // only strings, hashes and sets are dumped
+            let Some(kind) = backend.key_type(&key) else {
+                continue;
+            };
+            let value = match kind {
+                KeyType::String => backend
+                    .peek_string(&key)
+                    .map(|v| json!(String::from_utf8_lossy(&v))),
+                KeyType::Hash => backend.peek_hash(&key).map(|hash| {
+                    let fields: Map<String, Value> = hash
+                        .into_iter()
+                        .map(|(k, v)| (k, frame_to_json(&v)))
+                        .collect();
+                    Value::Object(fields)
+                }),
+                KeyType::Set => backend.peek_set(&key).map(|set| {
+                    let mut members: Vec<String> = set.into_iter().collect();
+                    members.sort();
+                    json!(members)
+                }),
+                _ => None,
+            };
+            let Some(value) = value else {
+                continue;
+            };
+
+            entries.push(json!({
+                "key": key,
+                "type": kind.as_str(),
+                "ttl": ttl,
+                "size": size,
+                "value": value,
+            }));
+        }
+
+        BulkString::new(Value::Array(entries).to_string()).into()
+    }
+}
+
+impl CommandExecutor for DebugHotkeys {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let count = match self {
+            DebugHotkeys::Sample(rate) => {
+                backend.set_access_sample_rate(rate);
+                return RESP_OK.clone();
+            }
+            DebugHotkeys::Report(count) => count,
+        };
+
+        let hot = backend
+            .hotkeys(count)
+            .into_iter()
+            .map(|(key, accesses)| {
+                RespArray::new(vec![BulkString::new(key).into(), (accesses as i64).into()]).into()
+            })
+            .collect();
+        let big = backend
+            .bigkeys(count)
+            .into_iter()
+            .map(|s| {
+                RespArray::new(vec![
+                    BulkString::new(s.key).into(),
+                    BulkString::new(s.kind.as_str()).into(),
+                    (s.size as i64).into(),
+                ])
+                .into()
+            })
+            .collect();
+        RespArray::new(vec![
+            BulkString::new("hotkeys").into(),
+            RespArray::new(hot).into(),
+            BulkString::new("bigkeys").into(),
+            RespArray::new(big).into(),
+        ])
+        .into()
+    }
+}
+
+impl CommandExecutor for DebugObject {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let (Some(encoding), Some(refcount), Some(idle)) = (
+            backend.object_encoding(&self.key),
+            backend.object_refcount(&self.key),
+            backend.object_idletime(&self.key),
+        ) else {
+            return SimpleError::new("ERR no such key").into();
+        };
+        let size = backend.memory_usage(&self.key).unwrap_or(0);
+        SimpleString::new(format!(
+            "refcount:{} encoding:{} serializedlength:{} lru_seconds_idle:{}",
+            refcount, encoding, size, idle
+        ))
+        .into()
+    }
+}
+
+impl CommandExecutor for DebugSleep {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for DebugCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "debug", 1)?;
+
+        let subcommand = match value.0.as_ref().and_then(|v| v.get(1)) {
+            Some(RespFrame::BulkString(BulkString(Some(sub)))) => sub.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+
+        match subcommand.as_slice() {
+            #[cfg(feature = "json")]
+            b"jmap" => Ok(DebugJmap::try_from(value)?.into()),
+            b"hotkeys" => Ok(DebugHotkeys::try_from(value)?.into()),
+            b"object" => Ok(DebugObject::try_from(value)?.into()),
+            b"sleep" => Ok(DebugSleep::try_from(value)?.into()),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "unknown subcommand '{}' for 'debug'",
+                String::from_utf8_lossy(&subcommand)
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<RespArray> for DebugJmap {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "debug", 2)?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+
+        let pattern = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(pattern)))) => String::from_utf8(pattern)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid pattern".to_string())),
+        };
+
+        let limit = match (args.next(), args.next(), args.next()) {
+            (None, None, None) => DEFAULT_JMAP_LIMIT,
+            (
+                Some(RespFrame::BulkString(BulkString(Some(option)))),
+                Some(RespFrame::BulkString(BulkString(Some(limit)))),
+                None,
+            ) if option.eq_ignore_ascii_case(b"limit") => {
+                String::from_utf8(limit)?.parse().map_err(|_| {
+                    CommandError::InvalidArgument(
+                        "value is not an integer or out of range".to_string(),
+                    )
+                })?
+            }
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+
+        Ok(DebugJmap { pattern, limit })
+    }
+}
+
+impl TryFrom<RespArray> for DebugHotkeys {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "debug", 1)?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+
+        match (args.next(), args.next(), args.next()) {
+            (None, None, None) => Ok(DebugHotkeys::Report(DEFAULT_HOTKEYS_COUNT)),
+            (
+                Some(RespFrame::BulkString(BulkString(Some(option)))),
+                Some(RespFrame::BulkString(BulkString(Some(n)))),
+                None,
+            ) => {
+                let invalid = || {
+                    CommandError::InvalidArgument(
+                        "value is not an integer or out of range".to_string(),
+                    )
+                };
+                if option.eq_ignore_ascii_case(b"count") {
+                    Ok(DebugHotkeys::Report(
+                        parse_number(n, |_| true).ok_or_else(invalid)?,
+                    ))
+                } else if option.eq_ignore_ascii_case(b"sample") {
+                    Ok(DebugHotkeys::Sample(
+                        parse_number(n, |_| true).ok_or_else(invalid)?,
+                    ))
+                } else {
+                    Err(CommandError::InvalidArgument("syntax error".to_string()))
+                }
+            }
+            _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for DebugObject {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "debug", 2)?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => Ok(DebugObject {
+                key: String::from_utf8(key)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for DebugSleep {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "debug", 2)?;
+
+        let mut args = extract_args(value, 2)?.into_iter();
+        let seconds = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(seconds)))) => {
+                parse_number(seconds, |s: &f64| s.is_finite() && *s >= 0.0)
+            }
+            _ => None,
+        };
+        let seconds = seconds.ok_or_else(|| {
+            CommandError::InvalidArgument("value is not a valid float".to_string())
+        })?;
+        let duration = Duration::try_from_secs_f64(seconds)
+            .map_err(|_| CommandError::InvalidArgument("sleep time is out of range".to_string()))?;
+        Ok(DebugSleep { duration })
+    }
+}
+
+// render a stored value as JSON, binary strings are converted lossily
+#[cfg(feature = "json")]
+fn frame_to_json(frame: &RespFrame) -> Value {
+    match frame {
+        RespFrame::SimpleString(s) => json!(s.0),
+        RespFrame::Error(e) => json!({ "error": e.0 }),
+        RespFrame::Integer(i) => json!(i),
+        RespFrame::BulkString(BulkString(Some(s))) => json!(String::from_utf8_lossy(s)),
+        RespFrame::BulkString(BulkString(None)) | RespFrame::Null(_) => Value::Null,
+        RespFrame::Array(RespArray(Some(v))) => v.iter().map(frame_to_json).collect(),
+        RespFrame::Array(RespArray(None)) => Value::Null,
+        RespFrame::Boolean(b) => json!(b),
+        RespFrame::Double(d) => json!(d),
+        RespFrame::Map(m) => Value::Object(
+            m.iter()
+                .map(|(k, v)| (k.clone(), frame_to_json(v)))
+                .collect(),
+        ),
+        RespFrame::Set(s) => s.iter().map(frame_to_json).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_debug_jmap_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("debug".as_bytes())),
+            RespFrame::BulkString(BulkString::new("JMAP".as_bytes())),
+            RespFrame::BulkString(BulkString::new("user:*".as_bytes())),
+            RespFrame::BulkString(BulkString::new("limit".as_bytes())),
+            RespFrame::BulkString(BulkString::new("10".as_bytes())),
+        ]);
+
+        let DebugCommand::Jmap(result) = DebugCommand::try_from(input)? else {
+            panic!("expected DEBUG JMAP");
+        };
+        assert_eq!(result.pattern, "user:*".to_string());
+        assert_eq!(result.limit, 10);
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("debug".as_bytes())),
+            RespFrame::BulkString(BulkString::new("unknown".as_bytes())),
+        ]);
+        assert!(DebugCommand::try_from(input).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_debug_jmap_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("user:1".to_string(), "alice");
+        backend
+            .hset("user:2".to_string(), "age".to_string(), 42.into())
+            .unwrap();
+        backend.sadd("user:3".to_string(), "b".to_string()).unwrap();
+        backend.sadd("user:3".to_string(), "a".to_string()).unwrap();
+        backend.set("other".to_string(), "x");
+        backend.set_access_sample_rate(1);
+
+        let cmd = DebugJmap {
+            pattern: "user:*".to_string(),
+            limit: 10,
+        };
+        let dump = match cmd.execute(&backend) {
+            RespFrame::BulkString(BulkString(Some(dump))) => {
+                serde_json::from_slice::<Value>(&dump)?
+            }
+            frame => panic!("unexpected response {:?}", frame),
+        };
+
+        let entries = dump.as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0]["key"], "user:1");
+        assert_eq!(entries[0]["type"], "string");
+        assert_eq!(entries[0]["value"], "alice");
+        assert_eq!(entries[0]["ttl"], -1);
+        assert!(entries[0]["size"].as_u64().unwrap() > 0);
+        assert_eq!(entries[1]["type"], "hash");
+        assert_eq!(entries[1]["value"], json!({ "age": 42 }));
+        assert_eq!(entries[2]["type"], "set");
+        assert_eq!(entries[2]["value"], json!(["a", "b"]));
+        // dumping the keys does not count as accessing them
+        assert!(backend.hotkeys(10).is_empty());
+
+        let cmd = DebugJmap {
+            pattern: "*".to_string(),
+            limit: 1,
+        };
+        let dump = match cmd.execute(&backend) {
+            RespFrame::BulkString(BulkString(Some(dump))) => {
+                serde_json::from_slice::<Value>(&dump)?
+            }
+            frame => panic!("unexpected response {:?}", frame),
+        };
+        assert_eq!(dump.as_array().unwrap().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_hotkeys_try_from() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+
+        let result = DebugCommand::try_from(args(&["debug", "hotkeys"]))?;
+        assert!(matches!(
+            result,
+            DebugCommand::Hotkeys(DebugHotkeys::Report(DEFAULT_HOTKEYS_COUNT))
+        ));
+        let result = DebugCommand::try_from(args(&["debug", "HOTKEYS", "count", "3"]))?;
+        assert!(matches!(
+            result,
+            DebugCommand::Hotkeys(DebugHotkeys::Report(3))
+        ));
+        let result = DebugCommand::try_from(args(&["debug", "hotkeys", "SAMPLE", "1"]))?;
+        assert!(matches!(
+            result,
+            DebugCommand::Hotkeys(DebugHotkeys::Sample(1))
+        ));
+        assert!(DebugCommand::try_from(args(&["debug", "hotkeys", "count", "x"])).is_err());
+        assert!(DebugCommand::try_from(args(&["debug", "hotkeys", "limit", "3"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_hotkeys_command() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), "1");
+        assert_eq!(DebugHotkeys::Sample(1).execute(&backend), RESP_OK.clone());
+        backend.get("a").unwrap();
+
+        let expected = RespArray::new(vec![
+            BulkString::new("hotkeys").into(),
+            RespArray::new(vec![RespArray::new(vec![
+                BulkString::new("a").into(),
+                1.into(),
+            ])
+            .into()])
+            .into(),
+            BulkString::new("bigkeys").into(),
+            RespArray::new(vec![RespArray::new(vec![
+                BulkString::new("a").into(),
+                BulkString::new("string").into(),
+                (backend.bigkeys(1)[0].size as i64).into(),
+            ])
+            .into()])
+            .into(),
+        ]);
+        assert_eq!(DebugHotkeys::Report(10).execute(&backend), expected.into());
+    }
+
+    #[test]
+    fn test_debug_object_and_sleep_try_from() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+
+        let DebugCommand::Object(result) = DebugCommand::try_from(args(&["debug", "object", "k"]))?
+        else {
+            panic!("expected DEBUG OBJECT");
+        };
+        assert_eq!(result.key, "k");
+        assert!(DebugCommand::try_from(args(&["debug", "object"])).is_err());
+
+        let DebugCommand::Sleep(result) = DebugCommand::try_from(args(&["debug", "SLEEP", "0.5"]))?
+        else {
+            panic!("expected DEBUG SLEEP");
+        };
+        assert_eq!(result.duration, Duration::from_millis(500));
+        assert!(DebugCommand::try_from(args(&["debug", "sleep", "-1"])).is_err());
+        assert!(DebugCommand::try_from(args(&["debug", "sleep", "inf"])).is_err());
+        assert!(DebugCommand::try_from(args(&["debug", "sleep", "1e30"])).is_err());
+        assert!(DebugCommand::try_from(args(&["debug", "sleep"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_object_command() {
+        let backend = Backend::new();
+        backend.set("n".to_string(), "42");
+
+        let ret = DebugObject {
+            key: "n".to_string(),
+        }
+        .execute(&backend);
+        let expected = format!(
+            "refcount:1 encoding:int serializedlength:{} lru_seconds_idle:0",
+            backend.memory_usage("n").unwrap()
+        );
+        assert_eq!(ret, SimpleString::new(expected).into());
+
+        let ret = DebugObject {
+            key: "missing".to_string(),
+        }
+        .execute(&backend);
+        assert_eq!(ret, SimpleError::new("ERR no such key").into());
+    }
+
+    #[test]
+    fn test_debug_sleep_command() -> Result<()> {
+        let backend = Backend::new();
+        let args = ["debug", "sleep", "0.02"]
+            .iter()
+            .map(|a| BulkString::new(*a).into())
+            .collect();
+        let cmd = crate::cmd::Command::try_from(RespArray::new(args))?;
+        assert_eq!(cmd.delay(), Some(Duration::from_millis(20)));
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use super::{
+    extract_args, parse_time, validate_command, validate_dynamic_command, CommandError,
+    CommandExecutor, Expiry, RESP_OK,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError};
+
+/// DUMP key
+#[derive(Debug)]
+pub struct Dump {
+    key: String,
+}
+
+/// RESTORE key ttl serialized-value [REPLACE] [ABSTTL]
+#[derive(Debug)]
+pub struct Restore {
+    key: String,
+    // PX with a time to live, PXAT with ABSTTL, None for a ttl of 0
+    expiry: Option<Expiry>,
+    payload: Vec<u8>,
+    replace: bool,
+}
+
+impl CommandExecutor for Dump {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.dump(&self.key) {
+            Ok(Some(payload)) => BulkString::new(payload).into(),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for Restore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let expire_at = self.expiry.and_then(|expiry| expiry.deadline(0));
+        match backend.restore(self.key, expire_at, &self.payload, self.replace) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Dump {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "dump", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => Ok(Dump {
+                key: String::from_utf8(key)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Restore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "restore", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => String::from_utf8(key)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let ttl = parse_time(args.next(), "restore", 1)?;
+        if ttl < 0 {
+            return Err(CommandError::InvalidArgument(
+                "Invalid TTL value, must be >= 0".to_string(),
+            ));
+        }
+        let mut restore = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(payload)))) => Restore {
+                key,
+                expiry: (ttl > 0).then_some(Expiry::Px(ttl)),
+                payload,
+                replace: false,
+            },
+            _ => return Err(CommandError::InvalidArgument("Invalid payload".to_string())),
+        };
+
+        for option in args {
+            match option {
+                RespFrame::BulkString(BulkString(Some(option)))
+                    if option.eq_ignore_ascii_case(b"replace") =>
+                {
+                    restore.replace = true
+                }
+                RespFrame::BulkString(BulkString(Some(option)))
+                    if option.eq_ignore_ascii_case(b"absttl") =>
+                {
+                    restore.expiry = (ttl > 0).then_some(Expiry::PxAt(ttl))
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
+        }
+
+        Ok(restore)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn parse_args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    #[test]
+    fn test_restore_try_from() -> Result<()> {
+        let result = Restore::try_from(parse_args(&["restore", "key", "0", "payload"]))?;
+        assert_eq!(result.key, "key");
+        assert_eq!(result.payload, b"payload");
+        assert_eq!(result.expiry, None);
+        assert!(!result.replace);
+
+        let result = Restore::try_from(parse_args(&["restore", "key", "100", "payload"]))?;
+        assert_eq!(result.expiry, Some(Expiry::Px(100)));
+
+        let args = ["restore", "key", "100", "payload", "REPLACE", "absttl"];
+        let result = Restore::try_from(parse_args(&args))?;
+        assert_eq!(result.expiry, Some(Expiry::PxAt(100)));
+        assert!(result.replace);
+
+        assert!(Restore::try_from(parse_args(&["restore", "key", "-1", "payload"])).is_err());
+        let args = ["restore", "key", "0", "payload", "idletime"];
+        assert!(Restore::try_from(parse_args(&args)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_restore_commands() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+
+        let dump = Dump {
+            key: "key".to_string(),
+        };
+        let RespFrame::BulkString(BulkString(Some(payload))) = dump.execute(&backend) else {
+            panic!("DUMP did not return a payload");
+        };
+        let restore = |key: &str, replace| Restore {
+            key: key.to_string(),
+            expiry: Some(Expiry::Px(60_000)),
+            payload: payload.clone(),
+            replace,
+        };
+        assert_eq!(restore("copy", false).execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.get("copy").unwrap(), Some("value".into()));
+        assert!(backend.expire_time("copy").is_some());
+        assert_eq!(
+            restore("copy", false).execute(&backend),
+            SimpleError::new("BUSYKEY Target key name already exists.").into()
+        );
+
+        let dump = Dump {
+            key: "missing".to_string(),
+        };
+        assert_eq!(dump.execute(&backend), RespFrame::Null(RespNull));
+    }
+}
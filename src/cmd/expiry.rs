@@ -0,0 +1,709 @@
+use super::{
+    extract_args, parse_number, validate_command, validate_dynamic_command, CommandError,
+    CommandExecutor,
+};
+use crate::{now_ms, Backend, BulkString, ExpireCondition, RespArray, RespFrame};
+use rand::Rng;
+
+/// Expiry option of a command, shared by every command accepting a time to live.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expiry {
+    /// EX seconds
+    Ex(i64),
+    /// PX milliseconds
+    Px(i64),
+    /// EXAT unix-time-seconds
+    ExAt(i64),
+    /// PXAT unix-time-milliseconds
+    PxAt(i64),
+    /// PERSIST, remove the time to live
+    Persist,
+    /// KEEPTTL, retain the time to live
+    KeepTtl,
+}
+
+/// The expiry options a command accepts besides EX/PX/EXAT/PXAT.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExpiryOptions {
+    pub persist: bool,
+    pub keepttl: bool,
+}
+
+impl Expiry {
+    /// Absolute expiration time in unix milliseconds, None for PERSIST and KEEPTTL.
+    ///
+    /// A relative time to live (EX/PX) is randomly extended by up to `jitter` percent, so that
+    /// keys written together with the same TTL do not all expire at once. A time to live that
+    /// is not in the future, which only EXPIRE and PEXPIRE accept, is never extended.
+    pub fn deadline(&self, jitter: u8) -> Option<i64> {
+        match *self {
+            Expiry::Ex(secs) => Some(relative_deadline(secs * 1000, jitter)),
+            Expiry::Px(ms) => Some(relative_deadline(ms, jitter)),
+            Expiry::ExAt(secs) => Some(secs * 1000),
+            Expiry::PxAt(ms) => Some(ms),
+            Expiry::Persist | Expiry::KeepTtl => None,
+        }
+    }
+}
+
+/// Parse the expiry options out of the remaining arguments of a command.
+///
+/// At most one expiry option may be given, any unknown argument is a syntax error.
+pub fn parse_expiry(
+    args: impl IntoIterator<Item = RespFrame>,
+    name: &str,
+    options: ExpiryOptions,
+) -> Result<Option<Expiry>, CommandError> {
+    let mut args = args.into_iter();
+    let mut expiry = None;
+
+    while let Some(arg) = args.next() {
+        let option = match arg {
+            RespFrame::BulkString(BulkString(Some(option))) => option.to_ascii_uppercase(),
+            _ => return Err(syntax_error()),
+        };
+
+        let parsed = match option.as_slice() {
+            b"EX" => Expiry::Ex(parse_positive_time(args.next(), name, 1000)?),
+            b"PX" => Expiry::Px(parse_positive_time(args.next(), name, 1)?),
+            b"EXAT" => Expiry::ExAt(parse_positive_time(args.next(), name, 1000)?),
+            b"PXAT" => Expiry::PxAt(parse_positive_time(args.next(), name, 1)?),
+            b"PERSIST" if options.persist => Expiry::Persist,
+            b"KEEPTTL" if options.keepttl => Expiry::KeepTtl,
+            _ => return Err(syntax_error()),
+        };
+
+        if expiry.replace(parsed).is_some() {
+            return Err(syntax_error());
+        }
+    }
+
+    Ok(expiry)
+}
+
+/// Parse the expiry options and an optional `JITTER percent` out of the remaining arguments.
+///
+/// JITTER overrides the server-wide TTL jitter, it requires a relative EX or PX expiry.
+pub fn parse_expiry_with_jitter(
+    args: impl IntoIterator<Item = RespFrame>,
+    name: &str,
+    options: ExpiryOptions,
+) -> Result<(Option<Expiry>, Option<u8>), CommandError> {
+    let mut rest = Vec::new();
+    let mut jitter = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg {
+            RespFrame::BulkString(BulkString(Some(option)))
+                if option.eq_ignore_ascii_case(b"jitter") =>
+            {
+                if jitter.replace(parse_jitter(args.next())?).is_some() {
+                    return Err(syntax_error());
+                }
+            }
+            arg => rest.push(arg),
+        }
+    }
+
+    let expiry = parse_expiry(rest, name, options)?;
+    if jitter.is_some() && !matches!(expiry, Some(Expiry::Ex(_) | Expiry::Px(_))) {
+        return Err(syntax_error());
+    }
+    Ok((expiry, jitter))
+}
+
+/// EXPIRE key seconds [NX | XX | GT | LT] [JITTER percent]
+///
+/// JITTER overrides the server-wide TTL jitter for this command.
+#[derive(Debug)]
+pub struct Expire {
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+    jitter: Option<u8>,
+}
+
+/// PEXPIRE key milliseconds [NX | XX | GT | LT] [JITTER percent]
+#[derive(Debug)]
+pub struct PExpire {
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+    jitter: Option<u8>,
+}
+
+/// EXPIREAT key unix-time-seconds [NX | XX | GT | LT]
+#[derive(Debug)]
+pub struct ExpireAt {
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+}
+
+/// PEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT]
+#[derive(Debug)]
+pub struct PExpireAt {
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+}
+
+/// TTL key, the remaining time to live in seconds
+#[derive(Debug)]
+pub struct Ttl {
+    key: String,
+}
+
+/// PTTL key, the remaining time to live in milliseconds
+#[derive(Debug)]
+pub struct PTtl {
+    key: String,
+}
+
+/// EXPIRETIME key, the absolute expiration time in unix seconds
+#[derive(Debug)]
+pub struct ExpireTime {
+    key: String,
+}
+
+/// PEXPIRETIME key, the absolute expiration time in unix milliseconds
+#[derive(Debug)]
+pub struct PExpireTime {
+    key: String,
+}
+
+impl CommandExecutor for Expire {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expire_reply(backend, self.key, self.expiry, self.condition, self.jitter)
+    }
+}
+
+impl CommandExecutor for PExpire {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expire_reply(backend, self.key, self.expiry, self.condition, self.jitter)
+    }
+}
+
+impl CommandExecutor for ExpireAt {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expire_reply(backend, self.key, self.expiry, self.condition, None)
+    }
+}
+
+impl CommandExecutor for PExpireAt {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expire_reply(backend, self.key, self.expiry, self.condition, None)
+    }
+}
+
+impl TryFrom<RespArray> for Expire {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, seconds, condition, jitter) = parse_expire_args(value, "expire", 1000)?;
+        Ok(Expire {
+            key,
+            expiry: Expiry::Ex(seconds),
+            condition,
+            jitter,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PExpire {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, milliseconds, condition, jitter) = parse_expire_args(value, "pexpire", 1)?;
+        Ok(PExpire {
+            key,
+            expiry: Expiry::Px(milliseconds),
+            condition,
+            jitter,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ExpireAt {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // an absolute time is never jittered
+        let (key, seconds, condition, None) = parse_expire_args(value, "expireat", 1000)? else {
+            return Err(syntax_error());
+        };
+        Ok(ExpireAt {
+            key,
+            expiry: Expiry::ExAt(seconds),
+            condition,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PExpireAt {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, milliseconds, condition, None) = parse_expire_args(value, "pexpireat", 1)? else {
+            return Err(syntax_error());
+        };
+        Ok(PExpireAt {
+            key,
+            expiry: Expiry::PxAt(milliseconds),
+            condition,
+        })
+    }
+}
+
+impl CommandExecutor for Ttl {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        // rounded to the nearest second, as redis does
+        expiration_reply(backend, &self.key, |at| {
+            ((at - now_ms()).max(0) + 500) / 1000
+        })
+    }
+}
+
+impl CommandExecutor for PTtl {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expiration_reply(backend, &self.key, |at| (at - now_ms()).max(0))
+    }
+}
+
+impl CommandExecutor for ExpireTime {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expiration_reply(backend, &self.key, |at| at / 1000)
+    }
+}
+
+impl CommandExecutor for PExpireTime {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expiration_reply(backend, &self.key, |at| at)
+    }
+}
+
+impl TryFrom<RespArray> for Ttl {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(Ttl {
+            key: parse_key(value, "ttl")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PTtl {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(PTtl {
+            key: parse_key(value, "pttl")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ExpireTime {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ExpireTime {
+            key: parse_key(value, "expiretime")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PExpireTime {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(PExpireTime {
+            key: parse_key(value, "pexpiretime")?,
+        })
+    }
+}
+
+fn parse_key(value: RespArray, name: &str) -> Result<String, CommandError> {
+    validate_command(&value, name, 1)?;
+
+    match extract_args(value, 1)?.into_iter().next() {
+        Some(RespFrame::BulkString(BulkString(Some(key)))) => Ok(String::from_utf8(key)?),
+        _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    }
+}
+
+// the expiration of a key converted by `f`, -1 if the key has none and -2 if it does not exist
+fn expiration_reply(backend: &Backend, key: &str, f: impl FnOnce(i64) -> i64) -> RespFrame {
+    let reply = match backend.expire_time(key) {
+        Some(at) => f(at),
+        None if backend.exists(key) => -1,
+        None => -2,
+    };
+    RespFrame::Integer(reply)
+}
+
+// parse `key time [NX | XX | GT | LT] [JITTER percent]`, `unit` is the size of the time in
+// milliseconds; unlike the expiry options the time may be zero or negative, which deletes the key
+fn parse_expire_args(
+    value: RespArray,
+    name: &str,
+    unit: i64,
+) -> Result<(String, i64, ExpireCondition, Option<u8>), CommandError> {
+    validate_dynamic_command(&value, name, 2)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(RespFrame::BulkString(BulkString(Some(key)))) => String::from_utf8(key)?,
+        _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    };
+    let time = parse_time(args.next(), name, unit)?;
+
+    let mut condition = ExpireCondition::default();
+    let mut jitter = None;
+    while let Some(option) = args.next() {
+        let flag = match option {
+            RespFrame::BulkString(BulkString(Some(option))) => option.to_ascii_uppercase(),
+            _ => return Err(syntax_error()),
+        };
+        match flag.as_slice() {
+            b"NX" => condition.nx = true,
+            b"XX" => condition.xx = true,
+            b"GT" => condition.gt = true,
+            b"LT" => condition.lt = true,
+            b"JITTER" => {
+                if jitter.replace(parse_jitter(args.next())?).is_some() {
+                    return Err(syntax_error());
+                }
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "Unsupported option {}",
+                    String::from_utf8_lossy(&flag)
+                )))
+            }
+        }
+    }
+    if condition.nx && (condition.xx || condition.gt || condition.lt) {
+        return Err(CommandError::InvalidArgument(
+            "NX and XX, GT or LT options at the same time are not compatible".to_string(),
+        ));
+    }
+    if condition.gt && condition.lt {
+        return Err(CommandError::InvalidArgument(
+            "GT and LT options at the same time are not compatible".to_string(),
+        ));
+    }
+
+    Ok((key, time, condition, jitter))
+}
+
+// the percentage following JITTER
+fn parse_jitter(arg: Option<RespFrame>) -> Result<u8, CommandError> {
+    match arg {
+        Some(RespFrame::BulkString(BulkString(Some(percent)))) => {
+            parse_number(percent, |p: &u8| *p <= 100).ok_or_else(|| {
+                CommandError::InvalidArgument(
+                    "jitter must be a percentage between 0 and 100".to_string(),
+                )
+            })
+        }
+        _ => Err(syntax_error()),
+    }
+}
+
+// absolute time of a relative time to live, only a time to live in the future is jittered
+fn relative_deadline(ms: i64, jitter: u8) -> i64 {
+    if ms > 0 {
+        now_ms().saturating_add(with_jitter(ms, jitter))
+    } else {
+        now_ms().saturating_add(ms)
+    }
+}
+
+// set the expiration of a key, replying whether it was set; `jitter` overrides the server-wide
+// TTL jitter
+fn expire_reply(
+    backend: &Backend,
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+    jitter: Option<u8>,
+) -> RespFrame {
+    let jitter = jitter.unwrap_or_else(|| backend.ttl_jitter());
+    // the time was checked not to overflow in milliseconds when parsing
+    let at = expiry.deadline(jitter).unwrap_or_default();
+    RespFrame::Integer(backend.expire_with(&key, at, condition) as i64)
+}
+
+// extend a time to live by a random amount of up to `percent` percent of it
+fn with_jitter(ttl: i64, percent: u8) -> i64 {
+    if percent == 0 {
+        return ttl;
+    }
+    let max = ttl.saturating_mul(percent as i64) / 100;
+    ttl.saturating_add(rand::thread_rng().gen_range(0..=max))
+}
+
+/// Parse a time value of any sign, `unit` is its size in milliseconds used for overflow
+/// checking.
+pub fn parse_time(arg: Option<RespFrame>, name: &str, unit: i64) -> Result<i64, CommandError> {
+    let value = match arg {
+        Some(RespFrame::BulkString(BulkString(Some(value)))) => value,
+        _ => return Err(syntax_error()),
+    };
+
+    let value: i64 = parse_number(value, |_| true).ok_or_else(|| {
+        CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+    })?;
+
+    if value.checked_mul(unit).is_none() {
+        return Err(invalid_expire_time(name));
+    }
+
+    Ok(value)
+}
+
+// parse a positive time value, as the expiry options take
+fn parse_positive_time(arg: Option<RespFrame>, name: &str, unit: i64) -> Result<i64, CommandError> {
+    match parse_time(arg, name, unit)? {
+        value if value > 0 => Ok(value),
+        _ => Err(invalid_expire_time(name)),
+    }
+}
+
+fn invalid_expire_time(name: &str) -> CommandError {
+    CommandError::InvalidArgument(format!("invalid expire time in '{}' command", name))
+}
+
+fn syntax_error() -> CommandError {
+    CommandError::InvalidArgument("syntax error".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn args(v: &[&str]) -> Vec<RespFrame> {
+        v.iter()
+            .map(|s| BulkString::new(s.as_bytes()).into())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_expiry() -> Result<()> {
+        let options = ExpiryOptions::default();
+        assert_eq!(parse_expiry(args(&[]), "set", options)?, None);
+        assert_eq!(
+            parse_expiry(args(&["ex", "10"]), "set", options)?,
+            Some(Expiry::Ex(10))
+        );
+        assert_eq!(
+            parse_expiry(args(&["PX", "100"]), "set", options)?,
+            Some(Expiry::Px(100))
+        );
+        assert_eq!(
+            parse_expiry(args(&["exat", "1700000000"]), "set", options)?,
+            Some(Expiry::ExAt(1700000000))
+        );
+        assert_eq!(
+            parse_expiry(args(&["pxat", "1700000000000"]), "set", options)?,
+            Some(Expiry::PxAt(1700000000000))
+        );
+
+        let options = ExpiryOptions {
+            persist: true,
+            keepttl: false,
+        };
+        assert_eq!(
+            parse_expiry(args(&["persist"]), "getex", options)?,
+            Some(Expiry::Persist)
+        );
+        assert!(parse_expiry(args(&["keepttl"]), "getex", options).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_expiry_with_jitter() -> Result<()> {
+        let options = ExpiryOptions::default();
+        assert_eq!(
+            parse_expiry_with_jitter(args(&["jitter", "10", "ex", "10"]), "set", options)?,
+            (Some(Expiry::Ex(10)), Some(10))
+        );
+        assert_eq!(
+            parse_expiry_with_jitter(args(&["px", "100"]), "set", options)?,
+            (Some(Expiry::Px(100)), None)
+        );
+        assert!(parse_expiry_with_jitter(args(&["jitter", "10"]), "set", options).is_err());
+        assert!(
+            parse_expiry_with_jitter(args(&["exat", "1", "jitter", "10"]), "set", options).is_err()
+        );
+        assert!(
+            parse_expiry_with_jitter(args(&["ex", "1", "jitter", "101"]), "set", options).is_err()
+        );
+        assert!(parse_expiry_with_jitter(args(&["ex", "1", "jitter"]), "set", options).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadline_jitter() {
+        let now = now_ms();
+        let at = Expiry::Px(10_000).deadline(0).unwrap();
+        assert!((now + 10_000..now + 10_100).contains(&at));
+        for _ in 0..100 {
+            let at = Expiry::Px(10_000).deadline(10).unwrap();
+            assert!((now + 10_000..now + 11_100).contains(&at));
+        }
+        assert_eq!(Expiry::PxAt(5).deadline(10), Some(5));
+    }
+
+    #[test]
+    fn test_expire_try_from() -> Result<()> {
+        let parse = |v: &[&str]| RespArray::new(args(v));
+        let result = Expire::try_from(parse(&["expire", "key", "10"]))?;
+        assert_eq!(result.key, "key");
+        assert_eq!(result.expiry, Expiry::Ex(10));
+        assert_eq!(result.condition, ExpireCondition::default());
+        assert_eq!(result.jitter, None);
+
+        let result = PExpire::try_from(parse(&["pexpire", "key", "100", "jitter", "20", "nx"]))?;
+        assert_eq!(result.jitter, Some(20));
+        assert!(result.condition.nx);
+        assert!(Expire::try_from(parse(&["expire", "key", "10", "jitter", "101"])).is_err());
+        assert!(Expire::try_from(parse(&["expire", "key", "10", "jitter"])).is_err());
+        assert!(ExpireAt::try_from(parse(&["expireat", "key", "10", "jitter", "5"])).is_err());
+
+        let result = PExpireAt::try_from(parse(&["pexpireat", "key", "-5", "xx", "GT"]))?;
+        assert_eq!(result.expiry, Expiry::PxAt(-5));
+        assert!(result.condition.xx && result.condition.gt);
+
+        assert!(Expire::try_from(parse(&["expire", "key", "10", "nx", "xx"])).is_err());
+        assert!(Expire::try_from(parse(&["expire", "key", "10", "gt", "lt"])).is_err());
+        assert!(Expire::try_from(parse(&["expire", "key", "10", "keepttl"])).is_err());
+        assert!(Expire::try_from(parse(&["expire", "key", "ten"])).is_err());
+        assert!(Expire::try_from(parse(&["expire", "key", "9223372036854775807"])).is_err());
+        assert!(ExpireAt::try_from(parse(&["expireat", "key"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_commands() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        let nx = ExpireCondition {
+            nx: true,
+            ..Default::default()
+        };
+
+        let cmd = Expire {
+            key: "key".to_string(),
+            expiry: Expiry::Ex(100),
+            condition: nx,
+            jitter: None,
+        };
+        assert_eq!(cmd.execute(&backend), 1.into());
+        let at = backend.expire_time("key").unwrap();
+        assert!((now_ms() + 99_000..=now_ms() + 100_000).contains(&at));
+
+        let cmd = PExpire {
+            key: "key".to_string(),
+            expiry: Expiry::Px(1000),
+            condition: nx,
+            jitter: None,
+        };
+        assert_eq!(cmd.execute(&backend), 0.into());
+
+        let cmd = ExpireAt {
+            key: "key".to_string(),
+            expiry: Expiry::ExAt(4_000_000_000),
+            condition: ExpireCondition::default(),
+        };
+        assert_eq!(cmd.execute(&backend), 1.into());
+        assert_eq!(backend.expire_time("key"), Some(4_000_000_000_000));
+
+        let cmd = PExpireAt {
+            key: "key".to_string(),
+            expiry: Expiry::PxAt(1),
+            condition: ExpireCondition::default(),
+        };
+        assert_eq!(cmd.execute(&backend), 1.into());
+        assert!(!backend.exists("key"));
+
+        // JITTER 0 turns off the server-wide jitter for one command
+        backend.set("key".to_string(), "value");
+        backend.set_ttl_jitter(100);
+        let cmd = Expire {
+            key: "key".to_string(),
+            expiry: Expiry::Ex(100),
+            condition: ExpireCondition::default(),
+            jitter: Some(0),
+        };
+        assert_eq!(cmd.execute(&backend), 1.into());
+        let at = backend.expire_time("key").unwrap();
+        assert!((now_ms() + 99_000..=now_ms() + 100_000).contains(&at));
+    }
+
+    #[test]
+    fn test_ttl_commands() -> Result<()> {
+        let parse = |v: &[&str]| RespArray::new(args(v));
+        let backend = Backend::new();
+        backend.set("volatile".to_string(), "value");
+        backend.expire_at("volatile", now_ms() + 10_400);
+        backend.set("persistent".to_string(), "value");
+
+        assert_eq!(
+            Ttl::try_from(parse(&["ttl", "volatile"]))?.execute(&backend),
+            10.into()
+        );
+        let RespFrame::Integer(pttl) =
+            PTtl::try_from(parse(&["pttl", "volatile"]))?.execute(&backend)
+        else {
+            panic!("PTTL did not return an integer");
+        };
+        assert!((10_000..=10_400).contains(&pttl));
+
+        backend.expire_at("volatile", 4_000_000_000_500);
+        assert_eq!(
+            ExpireTime::try_from(parse(&["expiretime", "volatile"]))?.execute(&backend),
+            4_000_000_000.into()
+        );
+        assert_eq!(
+            PExpireTime::try_from(parse(&["pexpiretime", "volatile"]))?.execute(&backend),
+            4_000_000_000_500.into()
+        );
+
+        for name in ["ttl", "pttl", "expiretime", "pexpiretime"] {
+            let persistent = lookup(name, "persistent")?.execute(&backend);
+            assert_eq!(persistent, (-1).into());
+            let missing = lookup(name, "missing")?.execute(&backend);
+            assert_eq!(missing, (-2).into());
+        }
+        assert!(Ttl::try_from(parse(&["ttl", "a", "b"])).is_err());
+
+        Ok(())
+    }
+
+    fn lookup(name: &str, key: &str) -> Result<crate::cmd::Command> {
+        Ok(crate::cmd::Command::try_from(RespArray::new(args(&[
+            name, key,
+        ])))?)
+    }
+
+    #[test]
+    fn test_parse_expiry_invalid() {
+        let options = ExpiryOptions {
+            persist: true,
+            keepttl: true,
+        };
+        assert!(parse_expiry(args(&["ex"]), "set", options).is_err());
+        assert!(parse_expiry(args(&["ex", "0"]), "set", options).is_err());
+        assert!(parse_expiry(args(&["px", "-1"]), "set", options).is_err());
+        assert!(parse_expiry(args(&["ex", "abc"]), "set", options).is_err());
+        assert!(parse_expiry(args(&["ex", "9223372036854775807"]), "set", options).is_err());
+        assert!(parse_expiry(args(&["ex", "10", "px", "10"]), "set", options).is_err());
+        assert!(parse_expiry(args(&["persist", "keepttl"]), "set", options).is_err());
+        assert!(parse_expiry(args(&["nx"]), "set", options).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use super::{
+    extract_args, parse, validate_dynamic_command, CommandError, CommandExecutor, CommandParser,
+    RESP_OK,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError};
+use phf::phf_map;
+
+pub(super) static FUNCTION_COMMANDS: phf::Map<&'static [u8], CommandParser> = phf_map! {
+    b"function" => parse::<FunctionCommand>,
+    b"fcall" => parse::<FCall>,
+};
+
+/// FUNCTION LOAD [REPLACE] code | FUNCTION DELETE library | FUNCTION FLUSH [ASYNC | SYNC] |
+/// FUNCTION LIST
+#[derive(Debug, PartialEq)]
+pub enum FunctionCommand {
+    Load { replace: bool, code: Vec<u8> },
+    Delete(String),
+    Flush,
+    List,
+}
+
+/// FCALL function numkeys [key ...] [arg ...]
+#[derive(Debug, PartialEq)]
+pub struct FCall {
+    function: String,
+    keys: Vec<String>,
+    args: Vec<Vec<u8>>,
+}
+
+impl CommandExecutor for FunctionCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            FunctionCommand::Load { replace, code } => {
+                match backend.function_load(&code, replace) {
+                    Ok(library) => BulkString::new(library).into(),
+                    Err(e) => SimpleError::new(e.to_string()).into(),
+                }
+            }
+            FunctionCommand::Delete(library) => match backend.function_delete(&library) {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => SimpleError::new(e.to_string()).into(),
+            },
+            FunctionCommand::Flush => {
+                backend.function_flush();
+                RESP_OK.clone()
+            }
+            FunctionCommand::List => {
+                let libraries = backend
+                    .function_list()
+                    .into_iter()
+                    .map(|(library, functions)| {
+                        let functions = functions
+                            .into_iter()
+                            .map(|function| {
+                                RespArray::new(vec![
+                                    BulkString::new("name").into(),
+                                    BulkString::new(function).into(),
+                                    BulkString::new("description").into(),
+                                    RespFrame::Null(RespNull),
+                                    BulkString::new("flags").into(),
+                                    RespArray::new(vec![]).into(),
+                                ])
+                                .into()
+                            })
+                            .collect();
+                        RespArray::new(vec![
+                            BulkString::new("library_name").into(),
+                            BulkString::new(library).into(),
+                            BulkString::new("engine").into(),
+                            BulkString::new("WASM").into(),
+                            BulkString::new("functions").into(),
+                            RespArray::new(functions).into(),
+                        ])
+                        .into()
+                    })
+                    .collect();
+                RespArray::new(libraries).into()
+            }
+        }
+    }
+}
+
+impl CommandExecutor for FCall {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.fcall(&self.function, self.keys, self.args) {
+            Ok(frame) => frame,
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for FunctionCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "function", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().map(|arg| match arg {
+            RespFrame::BulkString(BulkString(Some(arg))) => Ok(arg),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        });
+        let subcommand = args.next().transpose()?.unwrap_or_default();
+        let args = args.collect::<Result<Vec<_>, _>>()?;
+        let syntax_error = || CommandError::InvalidArgument("syntax error".to_string());
+
+        match (subcommand.to_ascii_lowercase().as_slice(), args.as_slice()) {
+            (b"load", [code]) => Ok(FunctionCommand::Load {
+                replace: false,
+                code: code.clone(),
+            }),
+            (b"load", [option, code]) if option.eq_ignore_ascii_case(b"replace") => {
+                Ok(FunctionCommand::Load {
+                    replace: true,
+                    code: code.clone(),
+                })
+            }
+            (b"load", _) => Err(syntax_error()),
+            (b"delete", [library]) => {
+                Ok(FunctionCommand::Delete(String::from_utf8(library.clone())?))
+            }
+            // libraries are small, freeing them asynchronously is not worth it
+            (b"flush", []) => Ok(FunctionCommand::Flush),
+            (b"flush", [mode])
+                if mode.eq_ignore_ascii_case(b"async") || mode.eq_ignore_ascii_case(b"sync") =>
+            {
+                Ok(FunctionCommand::Flush)
+            }
+            (b"list", []) => Ok(FunctionCommand::List),
+            (subcommand, _) => Err(CommandError::InvalidArgument(format!(
+                "unknown subcommand or wrong number of arguments for 'function|{}'",
+                String::from_utf8_lossy(subcommand)
+            ))),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for FCall {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "fcall", 2)?;
+
+        let mut args = extract_args(value, 1)?
+            .into_iter()
+            .map(|arg| match arg {
+                RespFrame::BulkString(BulkString(Some(arg))) => Ok(arg),
+                _ => Err(CommandError::InvalidArgument(
+                    "Invalid argument".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let function = String::from_utf8(args.next().unwrap_or_default())?;
+        let numkeys: i64 = String::from_utf8(args.next().unwrap_or_default())?
+            .parse()
+            .map_err(|_| {
+                CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+            })?;
+        let numkeys = usize::try_from(numkeys).map_err(|_| {
+            CommandError::InvalidArgument("Number of keys can't be negative".to_string())
+        })?;
+        if numkeys > args.len() {
+            return Err(CommandError::InvalidArgument(
+                "Number of keys can't be greater than number of args".to_string(),
+            ));
+        }
+        let keys = args
+            .by_ref()
+            .take(numkeys)
+            .map(String::from_utf8)
+            .collect::<Result<_, _>>()?;
+
+        Ok(FCall {
+            function,
+            keys,
+            args: args.collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    #[test]
+    fn test_function_try_from() -> Result<()> {
+        assert_eq!(
+            FunctionCommand::try_from(args(&["function", "LOAD", "REPLACE", "code"]))?,
+            FunctionCommand::Load {
+                replace: true,
+                code: b"code".to_vec()
+            }
+        );
+        assert_eq!(
+            FunctionCommand::try_from(args(&["function", "delete", "lib"]))?,
+            FunctionCommand::Delete("lib".to_string())
+        );
+        assert_eq!(
+            FunctionCommand::try_from(args(&["function", "flush", "async"]))?,
+            FunctionCommand::Flush
+        );
+        assert!(FunctionCommand::try_from(args(&["function", "load"])).is_err());
+        assert!(FunctionCommand::try_from(args(&["function", "dump"])).is_err());
+
+        assert_eq!(
+            FCall::try_from(args(&["fcall", "f", "1", "key", "arg"]))?,
+            FCall {
+                function: "f".to_string(),
+                keys: vec!["key".to_string()],
+                args: vec![b"arg".to_vec()],
+            }
+        );
+        assert!(FCall::try_from(args(&["fcall", "f", "2", "key"])).is_err());
+        assert!(FCall::try_from(args(&["fcall", "f", "-1"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_commands() -> Result<()> {
+        let backend = Backend::new();
+        let mut code = b"#!wasm name=lib\n".to_vec();
+        code.extend(wat::parse_str(
+            r#"(module (func (export "answer") (result i64) (i64.const 42)))"#,
+        )?);
+
+        let load = FunctionCommand::Load {
+            replace: false,
+            code,
+        };
+        assert_eq!(load.execute(&backend), BulkString::new("lib").into());
+        let call = FCall::try_from(args(&["fcall", "answer", "0"]))?;
+        assert_eq!(call.execute(&backend), 42.into());
+
+        let RespFrame::Array(RespArray(Some(libraries))) = FunctionCommand::List.execute(&backend)
+        else {
+            panic!("expected an array");
+        };
+        assert_eq!(libraries.len(), 1);
+
+        let delete = FunctionCommand::Delete("lib".to_string());
+        assert_eq!(delete.execute(&backend), RESP_OK.clone());
+        let call = FCall::try_from(args(&["fcall", "answer", "0"]))?;
+        assert_eq!(
+            call.execute(&backend),
+            SimpleError::new("ERR Function not found").into()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,554 @@
+use super::{
+    extract_args, parse_number, parse_strings, validate_command, validate_dynamic_command,
+    CommandError, CommandExecutor,
+};
+use crate::{Backend, BulkString, KeyType, RespArray, RespFrame, RespNull, SimpleError};
+
+// keys examined per call when SCAN is not given a COUNT
+const DEFAULT_SCAN_COUNT: usize = 10;
+
+#[derive(Debug)]
+pub struct Keys {
+    pattern: String,
+}
+
+/// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+#[derive(Debug)]
+pub struct Scan {
+    cursor: u64,
+    pattern: Option<String>,
+    count: usize,
+    kind: Option<KeyType>,
+}
+
+impl CommandExecutor for Keys {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let keys = backend
+            .keys(&self.pattern)
+            .into_iter()
+            .map(|k| BulkString::new(k).into())
+            .collect();
+        RespArray::new(keys).into()
+    }
+}
+
+impl TryFrom<RespArray> for Keys {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "keys", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(pattern)))) => Ok(Keys {
+                pattern: String::from_utf8(pattern)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid pattern".to_string())),
+        }
+    }
+}
+
+/// COPY source destination [DB destination-db] [REPLACE]
+///
+/// There is a single logical database, so DB can only be 0.
+#[derive(Debug)]
+pub struct CopyCommand {
+    src: String,
+    dest: String,
+    db: Option<i64>,
+    replace: bool,
+}
+
+/// MOVE key db
+///
+/// There is a single logical database, so no key can be moved. Any index but 0 is out of range,
+/// and a key that exists cannot be moved to the database it is already in.
+#[derive(Debug)]
+pub struct Move {
+    key: String,
+    db: i64,
+}
+
+impl CommandExecutor for Scan {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let (cursor, keys) =
+            backend.scan(self.cursor, self.count, self.pattern.as_deref(), self.kind);
+        RespArray::new(vec![
+            BulkString::new(cursor.to_string()).into(),
+            RespArray::new(
+                keys.into_iter()
+                    .map(|k| BulkString::new(k).into())
+                    .collect(),
+            )
+            .into(),
+        ])
+        .into()
+    }
+}
+
+impl TryFrom<RespArray> for Scan {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "scan", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let cursor = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(cursor)))) => parse_number(cursor, |_| true)
+                .ok_or_else(|| CommandError::InvalidArgument("invalid cursor".to_string()))?,
+            _ => return Err(CommandError::InvalidArgument("invalid cursor".to_string())),
+        };
+
+        let mut scan = Scan {
+            cursor,
+            pattern: None,
+            count: DEFAULT_SCAN_COUNT,
+            kind: None,
+        };
+        let syntax_error = || CommandError::InvalidArgument("syntax error".to_string());
+        while let Some(option) = args.next() {
+            let (
+                RespFrame::BulkString(BulkString(Some(option))),
+                Some(RespFrame::BulkString(BulkString(Some(arg)))),
+            ) = (option, args.next())
+            else {
+                return Err(syntax_error());
+            };
+            match option.to_ascii_lowercase().as_slice() {
+                b"match" => scan.pattern = Some(String::from_utf8(arg)?),
+                b"count" => {
+                    scan.count = parse_number(arg, |c| *c > 0).ok_or_else(syntax_error)?;
+                }
+                b"type" => {
+                    let name = String::from_utf8(arg)?;
+                    scan.kind = Some(KeyType::from_name(&name).ok_or_else(|| {
+                        CommandError::InvalidArgument(format!("unknown type name '{}'", name))
+                    })?);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(scan)
+    }
+}
+
+impl CommandExecutor for CopyCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.db.is_some_and(|db| db != 0) {
+            return SimpleError::new("ERR DB index is out of range").into();
+        }
+        if self.src == self.dest {
+            return SimpleError::new("ERR source and destination objects are the same").into();
+        }
+        RespFrame::Integer(backend.copy(&self.src, self.dest, self.replace) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for CopyCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "copy", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let (src, dest) = match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(src)))),
+                Some(RespFrame::BulkString(BulkString(Some(dest)))),
+            ) => (String::from_utf8(src)?, String::from_utf8(dest)?),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        let mut copy = CopyCommand {
+            src,
+            dest,
+            db: None,
+            replace: false,
+        };
+        let syntax_error = || CommandError::InvalidArgument("syntax error".to_string());
+        while let Some(option) = args.next() {
+            let RespFrame::BulkString(BulkString(Some(option))) = option else {
+                return Err(syntax_error());
+            };
+            match option.to_ascii_lowercase().as_slice() {
+                b"replace" => copy.replace = true,
+                b"db" => match args.next() {
+                    Some(RespFrame::BulkString(BulkString(Some(db)))) => {
+                        copy.db = Some(parse_db(db)?);
+                    }
+                    _ => return Err(syntax_error()),
+                },
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(copy)
+    }
+}
+
+impl CommandExecutor for Move {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.db != 0 {
+            SimpleError::new("ERR DB index is out of range").into()
+        } else if backend.exists(&self.key) {
+            SimpleError::new("ERR source and destination objects are the same").into()
+        } else {
+            RespFrame::Integer(0)
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Move {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "move", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(db)))),
+            ) => Ok(Move {
+                key: String::from_utf8(key)?,
+                db: parse_db(db)?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or db".to_string(),
+            )),
+        }
+    }
+}
+
+/// UNLINK key [key ...]
+#[derive(Debug)]
+pub struct Unlink {
+    keys: Vec<String>,
+}
+
+/// TOUCH key [key ...]
+#[derive(Debug)]
+pub struct Touch {
+    keys: Vec<String>,
+}
+
+impl CommandExecutor for Unlink {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.unlink(&self.keys) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for Unlink {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "unlink", 1)?;
+
+        Ok(Unlink {
+            keys: parse_strings(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for Touch {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.touch(&self.keys) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for Touch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "touch", 1)?;
+
+        Ok(Touch {
+            keys: parse_strings(value)?,
+        })
+    }
+}
+
+/// OBJECT ENCODING key | OBJECT REFCOUNT key | OBJECT IDLETIME key
+#[derive(Debug, PartialEq)]
+pub enum Object {
+    Encoding(String),
+    RefCount(String),
+    IdleTime(String),
+}
+
+impl CommandExecutor for Object {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let reply = match self {
+            Object::Encoding(key) => backend
+                .object_encoding(&key)
+                .map(|encoding| BulkString::new(encoding).into()),
+            Object::RefCount(key) => backend.object_refcount(&key).map(RespFrame::Integer),
+            Object::IdleTime(key) => backend.object_idletime(&key).map(RespFrame::Integer),
+        };
+        reply.unwrap_or(RespFrame::Null(RespNull))
+    }
+}
+
+impl TryFrom<RespArray> for Object {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "object", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(sub)))),
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+            ) => {
+                let key = String::from_utf8(key)?;
+                match sub.to_ascii_lowercase().as_slice() {
+                    b"encoding" => Ok(Object::Encoding(key)),
+                    b"refcount" => Ok(Object::RefCount(key)),
+                    b"idletime" => Ok(Object::IdleTime(key)),
+                    _ => Err(CommandError::InvalidArgument(
+                        "unknown subcommand for 'object'".to_string(),
+                    )),
+                }
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid subcommand or key".to_string(),
+            )),
+        }
+    }
+}
+
+fn parse_db(arg: Vec<u8>) -> Result<i64, CommandError> {
+    parse_number(arg, |_| true).ok_or_else(|| {
+        CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_keys_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("keys".as_bytes())),
+            RespFrame::BulkString(BulkString::new("h*".as_bytes())),
+        ]);
+
+        let result = Keys::try_from(input)?;
+
+        assert_eq!(result.pattern, "h*".to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_try_from() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+        let result = Scan::try_from(args(&["scan", "0"]))?;
+        assert_eq!((result.cursor, result.count), (0, DEFAULT_SCAN_COUNT));
+        let result = Scan::try_from(args(&[
+            "scan", "42", "MATCH", "user:*", "count", "100", "TYPE", "hash",
+        ]))?;
+        assert_eq!(result.cursor, 42);
+        assert_eq!(result.pattern, Some("user:*".to_string()));
+        assert_eq!(result.count, 100);
+        assert_eq!(result.kind, Some(KeyType::Hash));
+
+        assert!(Scan::try_from(args(&["scan", "x"])).is_err());
+        assert!(Scan::try_from(args(&["scan", "0", "count", "0"])).is_err());
+        assert!(Scan::try_from(args(&["scan", "0", "type", "list"])).is_err());
+        assert!(Scan::try_from(args(&["scan", "0", "match"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_command() {
+        let backend = Backend::new();
+        backend.set("hello".to_string(), "world");
+        backend
+            .hset("hmap".to_string(), "f".to_string(), 1.into())
+            .unwrap();
+
+        let scan = Scan {
+            cursor: 0,
+            pattern: None,
+            count: 100,
+            kind: Some(KeyType::Hash),
+        };
+        let expected = RespArray::new(vec![
+            BulkString::new("0").into(),
+            RespArray::new(vec![BulkString::new("hmap").into()]).into(),
+        ]);
+        assert_eq!(scan.execute(&backend), expected.into());
+    }
+
+    #[test]
+    fn test_copy_try_from() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+        let result = CopyCommand::try_from(args(&["copy", "a", "b"]))?;
+        assert_eq!((result.src.as_str(), result.dest.as_str()), ("a", "b"));
+        assert_eq!((result.db, result.replace), (None, false));
+        let result = CopyCommand::try_from(args(&["copy", "a", "b", "REPLACE", "db", "0"]))?;
+        assert_eq!((result.db, result.replace), (Some(0), true));
+        assert!(CopyCommand::try_from(args(&["copy", "a", "b", "db"])).is_err());
+        assert!(CopyCommand::try_from(args(&["copy", "a", "b", "force"])).is_err());
+
+        assert_eq!(Move::try_from(args(&["move", "a", "1"]))?.db, 1);
+        assert!(Move::try_from(args(&["move", "a", "x"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_command() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), "value");
+        let move_to = |key: &str, db| Move {
+            key: key.to_string(),
+            db,
+        };
+        assert_eq!(
+            move_to("a", 1).execute(&backend),
+            SimpleError::new("ERR DB index is out of range").into()
+        );
+        assert_eq!(
+            move_to("a", 0).execute(&backend),
+            SimpleError::new("ERR source and destination objects are the same").into()
+        );
+        assert_eq!(move_to("missing", 0).execute(&backend), 0.into());
+        assert_eq!(backend.get("a").unwrap(), Some("value".into()));
+    }
+
+    #[test]
+    fn test_copy_command() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), "value");
+        let copy = |dest: &str, db| CopyCommand {
+            src: "a".to_string(),
+            dest: dest.to_string(),
+            db,
+            replace: false,
+        };
+        assert_eq!(copy("b", Some(0)).execute(&backend), 1.into());
+        assert_eq!(copy("b", None).execute(&backend), 0.into());
+        assert_eq!(
+            copy("c", Some(1)).execute(&backend),
+            SimpleError::new("ERR DB index is out of range").into()
+        );
+        assert_eq!(
+            copy("a", None).execute(&backend),
+            SimpleError::new("ERR source and destination objects are the same").into()
+        );
+    }
+
+    #[test]
+    fn test_unlink_touch_commands() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+        let backend = Backend::new();
+        backend.set("a".to_string(), "value");
+        backend.set("b".to_string(), "value");
+
+        let touch = Touch::try_from(args(&["touch", "a", "b", "c"]))?;
+        assert_eq!(touch.execute(&backend), 2.into());
+        let unlink = Unlink::try_from(args(&["unlink", "a", "c"]))?;
+        assert_eq!(unlink.execute(&backend), 1.into());
+        assert!(!backend.exists("a"));
+        assert!(Unlink::try_from(args(&["unlink"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_try_from() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+        assert_eq!(
+            Object::try_from(args(&["object", "ENCODING", "key"]))?,
+            Object::Encoding("key".to_string())
+        );
+        assert_eq!(
+            Object::try_from(args(&["object", "idletime", "key"]))?,
+            Object::IdleTime("key".to_string())
+        );
+        assert!(Object::try_from(args(&["object", "freq", "key"])).is_err());
+        assert!(Object::try_from(args(&["object", "encoding"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_command() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "42");
+        assert_eq!(
+            Object::Encoding("key".to_string()).execute(&backend),
+            BulkString::new("int").into()
+        );
+        assert_eq!(
+            Object::RefCount("key".to_string()).execute(&backend),
+            1.into()
+        );
+        assert_eq!(
+            Object::IdleTime("key".to_string()).execute(&backend),
+            0.into()
+        );
+        assert_eq!(
+            Object::Encoding("missing".to_string()).execute(&backend),
+            RespFrame::Null(RespNull)
+        );
+    }
+
+    #[test]
+    fn test_keys_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("hello".to_string(), "world");
+        backend
+            .hset("hmap".to_string(), "f".to_string(), 1.into())
+            .unwrap();
+        backend
+            .sadd("set".to_string(), "member".to_string())
+            .unwrap();
+        assert!(backend
+            .sadd("hello".to_string(), "member".to_string())
+            .is_err());
+
+        let keys = Keys {
+            pattern: "h*".to_string(),
+        };
+        let result = keys.execute(&backend);
+        let expected = RespArray::new(vec![
+            BulkString::new("hello").into(),
+            BulkString::new("hmap").into(),
+        ]);
+        assert_eq!(result, expected.into());
+
+        let keys = Keys {
+            pattern: "*".to_string(),
+        };
+        let result = keys.execute(&backend);
+        let expected = RespArray::new(vec![
+            BulkString::new("hello").into(),
+            BulkString::new("hmap").into(),
+            BulkString::new("set").into(),
+        ]);
+        assert_eq!(result, expected.into());
+
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use super::{
     extract_args, validate_command, validate_dynamic_command, CommandError, CommandExecutor,
     RESP_OK,
 };
-use crate::{Backend, BulkString, RespArray, RespFrame, RespNull};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError};
 
 #[derive(Debug)]
 pub struct HGet {
@@ -17,6 +17,13 @@ pub struct HSet {
     value: RespFrame,
 }
 
+#[derive(Debug)]
+pub struct HSetNx {
+    key: String,
+    field: String,
+    value: RespFrame,
+}
+
 #[derive(Debug)]
 pub struct HMGet {
     key: String,
@@ -28,11 +35,29 @@ pub struct HGetAll {
     key: String,
 }
 
+#[derive(Debug)]
+pub struct HExists {
+    key: String,
+    field: String,
+}
+
+#[derive(Debug)]
+pub struct HLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct HStrLen {
+    key: String,
+    field: String,
+}
+
 impl CommandExecutor for HGet {
     fn execute(self, backend: &Backend) -> RespFrame {
         match backend.hget(&self.key, &self.field) {
-            Some(value) => value,
-            None => RespFrame::Null(RespNull),
+            Ok(Some(value)) => value,
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => SimpleError::new(e.to_string()).into(),
         }
     }
 }
@@ -42,12 +67,13 @@ impl CommandExecutor for HMGet {
         let mut ret = Vec::with_capacity(self.fields.len());
         for field in &self.fields {
             match backend.hget(&self.key, field) {
-                Some(value) => {
+                Ok(Some(value)) => {
                     ret.push(value);
                 }
-                None => {
+                Ok(None) => {
                     ret.push(RespFrame::Null(RespNull));
                 }
+                Err(e) => return SimpleError::new(e.to_string()).into(),
             }
         }
         RespArray::new(ret).into()
@@ -56,24 +82,69 @@ impl CommandExecutor for HMGet {
 
 impl CommandExecutor for HGetAll {
     fn execute(self, backend: &Backend) -> RespFrame {
-        if let Some(map) = backend.hgetall(&self.key) {
-            // transform the map into a RespMap
-            let mut ret = Vec::with_capacity(map.len() * 2);
-            map.into_iter().for_each(|(k, v)| {
-                ret.push(BulkString::new(k).into());
-                ret.push(v)
-            });
-            RespArray::new(ret).into()
-        } else {
-            RespArray::new(Vec::new()).into()
+        match backend.hgetall(&self.key) {
+            Ok(Some(map)) => {
+                // transform the map into a RespMap
+                let mut ret = Vec::with_capacity(map.len() * 2);
+                map.into_iter().for_each(|(k, v)| {
+                    ret.push(BulkString::new(k).into());
+                    ret.push(v)
+                });
+                RespArray::new(ret).into()
+            }
+            Ok(None) => RespArray::new(Vec::new()).into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for HExists {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hexists(&self.key, &self.field) {
+            Ok(exists) => RespFrame::Integer(exists as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
         }
     }
 }
 
+impl CommandExecutor for HLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hlen(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for HStrLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let len = match backend.hget(&self.key, &self.field) {
+            Ok(Some(RespFrame::BulkString(BulkString(Some(value))))) => value.len(),
+            Ok(Some(RespFrame::SimpleString(value))) => value.len(),
+            Ok(Some(RespFrame::Integer(value))) => value.to_string().len(),
+            Ok(Some(RespFrame::Double(value))) => value.to_string().len(),
+            Ok(_) => 0,
+            Err(e) => return SimpleError::new(e.to_string()).into(),
+        };
+        RespFrame::Integer(len as i64)
+    }
+}
+
 impl CommandExecutor for HSet {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.hset(self.key, self.field, self.value.clone());
-        RESP_OK.clone()
+        match backend.hset(self.key, self.field, self.value) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for HSetNx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hsetnx(self.key, self.field, self.value) {
+            Ok(set) => RespFrame::Integer(set as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
     }
 }
 
@@ -83,20 +154,8 @@ impl TryFrom<RespArray> for HGet {
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
         validate_command(&value, "hget", 2)?;
 
-        let mut args = extract_args(value, 1)?.into_iter();
-
-        match (args.next(), args.next()) {
-            (
-                Some(RespFrame::BulkString(BulkString(Some(key)))),
-                Some(RespFrame::BulkString(BulkString(Some(field)))),
-            ) => Ok(HGet {
-                key: String::from_utf8(key)?,
-                field: String::from_utf8(field)?,
-            }),
-            _ => Err(CommandError::InvalidArgument(
-                "Invalid key or field".to_string(),
-            )),
-        }
+        let (key, field) = extract_key_field(value)?;
+        Ok(HGet { key, field })
     }
 }
 
@@ -143,6 +202,45 @@ impl TryFrom<RespArray> for HGetAll {
     }
 }
 
+impl TryFrom<RespArray> for HExists {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "hexists", 2)?;
+
+        let (key, field) = extract_key_field(value)?;
+        Ok(HExists { key, field })
+    }
+}
+
+impl TryFrom<RespArray> for HLen {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "hlen", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => Ok(HLen {
+                key: String::from_utf8(key)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for HStrLen {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "hstrlen", 2)?;
+
+        let (key, field) = extract_key_field(value)?;
+        Ok(HStrLen { key, field })
+    }
+}
+
 impl TryFrom<RespArray> for HSet {
     type Error = CommandError;
 
@@ -168,6 +266,45 @@ impl TryFrom<RespArray> for HSet {
     }
 }
 
+impl TryFrom<RespArray> for HSetNx {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "hsetnx", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(field)))),
+                Some(value),
+            ) => Ok(HSetNx {
+                key: String::from_utf8(key)?,
+                field: String::from_utf8(field)?,
+                value,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, field or value".to_string(),
+            )),
+        }
+    }
+}
+
+fn extract_key_field(value: RespArray) -> Result<(String, String), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+
+    match (args.next(), args.next()) {
+        (
+            Some(RespFrame::BulkString(BulkString(Some(key)))),
+            Some(RespFrame::BulkString(BulkString(Some(field)))),
+        ) => Ok((String::from_utf8(key)?, String::from_utf8(field)?)),
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid key or field".to_string(),
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,4 +448,159 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_hexists_hlen_hstrlen_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("hexists".as_bytes())),
+            RespFrame::BulkString(BulkString::new("map".as_bytes())),
+            RespFrame::BulkString(BulkString::new("hello".as_bytes())),
+        ]);
+        let result = HExists::try_from(input)?;
+        assert_eq!(result.key, "map".to_string());
+        assert_eq!(result.field, "hello".to_string());
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("hlen".as_bytes())),
+            RespFrame::BulkString(BulkString::new("map".as_bytes())),
+        ]);
+        let result = HLen::try_from(input)?;
+        assert_eq!(result.key, "map".to_string());
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("hstrlen".as_bytes())),
+            RespFrame::BulkString(BulkString::new("map".as_bytes())),
+            RespFrame::BulkString(BulkString::new("hello".as_bytes())),
+        ]);
+        let result = HStrLen::try_from(input)?;
+        assert_eq!(result.key, "map".to_string());
+        assert_eq!(result.field, "hello".to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hexists_hlen_hstrlen_command() -> Result<()> {
+        let backend = Backend::new();
+        backend
+            .hset(
+                "map".to_string(),
+                "hello".to_string(),
+                BulkString::new("world".as_bytes()).into(),
+            )
+            .unwrap();
+        backend
+            .hset("map".to_string(), "num".to_string(), 1234.into())
+            .unwrap();
+
+        let hexists = HExists {
+            key: "map".to_string(),
+            field: "hello".to_string(),
+        };
+        assert_eq!(hexists.execute(&backend), 1.into());
+        let hexists = HExists {
+            key: "map".to_string(),
+            field: "missing".to_string(),
+        };
+        assert_eq!(hexists.execute(&backend), 0.into());
+
+        let hlen = HLen {
+            key: "map".to_string(),
+        };
+        assert_eq!(hlen.execute(&backend), 2.into());
+        let hlen = HLen {
+            key: "missing".to_string(),
+        };
+        assert_eq!(hlen.execute(&backend), 0.into());
+
+        let hstrlen = HStrLen {
+            key: "map".to_string(),
+            field: "hello".to_string(),
+        };
+        assert_eq!(hstrlen.execute(&backend), 5.into());
+        let hstrlen = HStrLen {
+            key: "map".to_string(),
+            field: "num".to_string(),
+        };
+        assert_eq!(hstrlen.execute(&backend), 4.into());
+        let hstrlen = HStrLen {
+            key: "map".to_string(),
+            field: "missing".to_string(),
+        };
+        assert_eq!(hstrlen.execute(&backend), 0.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hsetnx_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("hsetnx".as_bytes())),
+            RespFrame::BulkString(BulkString::new("map".as_bytes())),
+            RespFrame::BulkString(BulkString::new("hello".as_bytes())),
+            RespFrame::BulkString(BulkString::new("world".as_bytes())),
+        ]);
+
+        let result = HSetNx::try_from(input)?;
+
+        assert_eq!(result.key, "map".to_string());
+        assert_eq!(result.field, "hello".to_string());
+        assert_eq!(
+            result.value,
+            RespFrame::BulkString(BulkString::new("world".as_bytes()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hsetnx_command() -> Result<()> {
+        let backend = Backend::new();
+
+        let hsetnx = HSetNx {
+            key: "map".to_string(),
+            field: "hello".to_string(),
+            value: RespFrame::BulkString(BulkString::new("world".as_bytes())),
+        };
+        assert_eq!(hsetnx.execute(&backend), 1.into());
+
+        let hsetnx = HSetNx {
+            key: "map".to_string(),
+            field: "hello".to_string(),
+            value: RespFrame::BulkString(BulkString::new("other".as_bytes())),
+        };
+        assert_eq!(hsetnx.execute(&backend), 0.into());
+        assert_eq!(
+            backend.hget("map", "hello").unwrap(),
+            Some(RespFrame::BulkString(BulkString::new("world".as_bytes())))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_commands_on_a_string_key() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        let wrongtype: RespFrame =
+            SimpleError::new("WRONGTYPE Operation against a key holding the wrong kind of value")
+                .into();
+
+        let hget = HGet {
+            key: "key".to_string(),
+            field: "field".to_string(),
+        };
+        assert_eq!(hget.execute(&backend), wrongtype);
+        let hlen = HLen {
+            key: "key".to_string(),
+        };
+        assert_eq!(hlen.execute(&backend), wrongtype);
+        let hexists = HExists {
+            key: "key".to_string(),
+            field: "field".to_string(),
+        };
+        assert_eq!(hexists.execute(&backend), wrongtype);
+
+        Ok(())
+    }
 }
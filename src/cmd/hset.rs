@@ -1,7 +1,7 @@
 use super::{
     extract_args, validate_command, validate_dynamic_command, CommandError, CommandExecutor,
 };
-use crate::{Backend, BulkString, RespArray, RespFrame};
+use crate::{Backend, BulkString, RespArray, RespFrame, SimpleError, WrongTypeError};
 
 #[derive(Debug)]
 pub struct SAdd {
@@ -15,12 +15,54 @@ pub struct SIsMember {
     member: String,
 }
 
+#[derive(Debug)]
+pub struct SMIsMember {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SInterCard {
+    keys: Vec<String>,
+    limit: usize,
+}
+
+#[derive(Debug)]
+pub struct SCard {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct SMembers {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct SUnionStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SInterStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SDiffStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
 impl CommandExecutor for SAdd {
     fn execute(self, backend: &Backend) -> RespFrame {
         let mut added: i64 = 0;
         for member in self.members {
-            let ret = backend.sadd(self.key.clone(), member);
-            added += ret as i64;
+            match backend.sadd(self.key.clone(), member) {
+                Ok(ret) => added += ret as i64,
+                Err(e) => return SimpleError::new(e.to_string()).into(),
+            }
         }
         added.into()
     }
@@ -28,10 +70,84 @@ impl CommandExecutor for SAdd {
 
 impl CommandExecutor for SIsMember {
     fn execute(self, backend: &Backend) -> RespFrame {
-        let ret = backend.sismember(&self.key, &self.member);
-        let ret = if ret { 1 } else { 0 };
-        // ret.into()
-        RespFrame::Integer(ret)
+        match backend.sismember(&self.key, &self.member) {
+            Ok(ret) => RespFrame::Integer(ret as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for SMIsMember {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let ret: Result<Vec<_>, WrongTypeError> = self
+            .members
+            .iter()
+            .map(|m| Ok(RespFrame::Integer(backend.sismember(&self.key, m)? as i64)))
+            .collect();
+        match ret {
+            Ok(ret) => RespArray::new(ret).into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for SInterCard {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.sintercard(&self.keys, self.limit) {
+            Ok(count) => RespFrame::Integer(count as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for SCard {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.scard(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for SMembers {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut members: Vec<String> = match backend.smembers(&self.key) {
+            Ok(set) => set.map(|s| s.into_iter().collect()).unwrap_or_default(),
+            Err(e) => return SimpleError::new(e.to_string()).into(),
+        };
+        members.sort();
+        let members = members
+            .into_iter()
+            .map(|m| BulkString::new(m).into())
+            .collect();
+        RespArray::new(members).into()
+    }
+}
+
+impl CommandExecutor for SUnionStore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.sunion(&self.keys) {
+            Ok(members) => RespFrame::Integer(backend.sstore(self.destination, members) as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for SInterStore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.sinter(&self.keys) {
+            Ok(members) => RespFrame::Integer(backend.sstore(self.destination, members) as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for SDiffStore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.sdiff(&self.keys) {
+            Ok(members) => RespFrame::Integer(backend.sstore(self.destination, members) as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
     }
 }
 
@@ -84,6 +200,161 @@ impl TryFrom<RespArray> for SIsMember {
     }
 }
 
+impl TryFrom<RespArray> for SMIsMember {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "smismember", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => String::from_utf8(key)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        let mut members = Vec::new();
+        loop {
+            match args.next() {
+                Some(RespFrame::BulkString(BulkString(Some(member)))) => {
+                    members.push(String::from_utf8(member)?)
+                }
+                None => return Ok(SMIsMember { key, members }),
+                _ => return Err(CommandError::InvalidArgument("Invalid member".to_string())),
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SInterCard {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "sintercard", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let numkeys = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(n)))) => {
+                String::from_utf8(n)?.parse::<i64>().map_err(|_| {
+                    CommandError::InvalidArgument("numkeys should be greater than 0".to_string())
+                })?
+            }
+            _ => return Err(CommandError::InvalidArgument("Invalid numkeys".to_string())),
+        };
+        if numkeys <= 0 {
+            return Err(CommandError::InvalidArgument(
+                "numkeys should be greater than 0".to_string(),
+            ));
+        }
+
+        let mut keys = Vec::with_capacity(numkeys as usize);
+        for _ in 0..numkeys {
+            match args.next() {
+                Some(RespFrame::BulkString(BulkString(Some(key)))) => {
+                    keys.push(String::from_utf8(key)?)
+                }
+                None => {
+                    return Err(CommandError::InvalidArgument(
+                        "Number of keys can't be greater than number of args".to_string(),
+                    ))
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+            }
+        }
+
+        let limit = match (args.next(), args.next(), args.next()) {
+            (None, None, None) => 0,
+            (
+                Some(RespFrame::BulkString(BulkString(Some(option)))),
+                Some(RespFrame::BulkString(BulkString(Some(limit)))),
+                None,
+            ) if option.eq_ignore_ascii_case(b"limit") => {
+                String::from_utf8(limit)?.parse::<usize>().map_err(|_| {
+                    CommandError::InvalidArgument("LIMIT can't be negative".to_string())
+                })?
+            }
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+
+        Ok(SInterCard { keys, limit })
+    }
+}
+
+impl TryFrom<RespArray> for SCard {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "scard", 1)?;
+        Ok(SCard {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SMembers {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "smembers", 1)?;
+        Ok(SMembers {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SUnionStore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "sunionstore", 2)?;
+        let (destination, keys) = extract_destination_keys(value)?;
+        Ok(SUnionStore { destination, keys })
+    }
+}
+
+impl TryFrom<RespArray> for SInterStore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "sinterstore", 2)?;
+        let (destination, keys) = extract_destination_keys(value)?;
+        Ok(SInterStore { destination, keys })
+    }
+}
+
+impl TryFrom<RespArray> for SDiffStore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "sdiffstore", 2)?;
+        let (destination, keys) = extract_destination_keys(value)?;
+        Ok(SDiffStore { destination, keys })
+    }
+}
+
+// extract `destination key [key ...]`
+fn extract_destination_keys(value: RespArray) -> Result<(String, Vec<String>), CommandError> {
+    let mut keys = Vec::new();
+    for arg in extract_args(value, 1)? {
+        match arg {
+            RespFrame::BulkString(BulkString(Some(key))) => keys.push(String::from_utf8(key)?),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+    let destination = keys.remove(0);
+    Ok((destination, keys))
+}
+
+fn extract_key(value: RespArray) -> Result<String, CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+
+    match args.next() {
+        Some(RespFrame::BulkString(BulkString(Some(key)))) => Ok(String::from_utf8(key)?),
+        _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,6 +391,99 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_try_from_smismember() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString(Some("smismember".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("key".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("member1".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("member2".as_bytes().to_vec()))),
+        ]);
+
+        let cmd = SMIsMember::try_from(input)?;
+        assert_eq!(cmd.key, "key");
+        assert_eq!(cmd.members, vec!["member1", "member2"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_smismember_execute() {
+        let backend = Backend::new();
+        backend
+            .sadd("key".to_string(), "member1".to_string())
+            .unwrap();
+
+        let cmd = SMIsMember {
+            key: "key".to_string(),
+            members: vec!["member1".to_string(), "member2".to_string()],
+        };
+        let expected = RespArray::new(vec![1.into(), 0.into()]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = SMIsMember {
+            key: "missing".to_string(),
+            members: vec!["member1".to_string()],
+        };
+        let expected = RespArray::new(vec![0.into()]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+    }
+
+    #[test]
+    fn test_try_from_sintercard() -> Result<()> {
+        let args = |v: &[&str]| {
+            RespArray::new(
+                v.iter()
+                    .map(|s| RespFrame::BulkString(BulkString(Some(s.as_bytes().to_vec()))))
+                    .collect(),
+            )
+        };
+
+        let cmd = SInterCard::try_from(args(&["sintercard", "2", "key1", "key2"]))?;
+        assert_eq!(cmd.keys, vec!["key1", "key2"]);
+        assert_eq!(cmd.limit, 0);
+
+        let cmd = SInterCard::try_from(args(&["sintercard", "1", "key1", "LIMIT", "5"]))?;
+        assert_eq!(cmd.keys, vec!["key1"]);
+        assert_eq!(cmd.limit, 5);
+
+        assert!(SInterCard::try_from(args(&["sintercard", "0", "key1"])).is_err());
+        assert!(SInterCard::try_from(args(&["sintercard", "3", "key1", "key2"])).is_err());
+        assert!(SInterCard::try_from(args(&["sintercard", "1", "key1", "limit", "-1"])).is_err());
+        assert!(SInterCard::try_from(args(&["sintercard", "1", "key1", "key2"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sintercard_execute() {
+        let backend = Backend::new();
+        for m in ["a", "b", "c", "d"] {
+            backend.sadd("key1".to_string(), m.to_string()).unwrap();
+        }
+        for m in ["b", "c", "d", "e"] {
+            backend.sadd("key2".to_string(), m.to_string()).unwrap();
+        }
+
+        let cmd = SInterCard {
+            keys: vec!["key1".to_string(), "key2".to_string()],
+            limit: 0,
+        };
+        assert_eq!(cmd.execute(&backend), 3.into());
+
+        let cmd = SInterCard {
+            keys: vec!["key1".to_string(), "key2".to_string()],
+            limit: 2,
+        };
+        assert_eq!(cmd.execute(&backend), 2.into());
+
+        let cmd = SInterCard {
+            keys: vec!["key1".to_string(), "missing".to_string()],
+            limit: 0,
+        };
+        assert_eq!(cmd.execute(&backend), 0.into());
+    }
+
     #[test]
     fn test_sadd_sismember_execute() {
         let backend = Backend::new();
@@ -153,4 +517,131 @@ mod tests {
         let ret = cmd.execute(&backend);
         assert_eq!(ret, 0.into());
     }
+
+    #[test]
+    fn test_try_from_scard_smembers() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString(Some("scard".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("key".as_bytes().to_vec()))),
+        ]);
+        let cmd = SCard::try_from(input)?;
+        assert_eq!(cmd.key, "key");
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString(Some("smembers".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("key".as_bytes().to_vec()))),
+        ]);
+        let cmd = SMembers::try_from(input)?;
+        assert_eq!(cmd.key, "key");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scard_smembers_execute() {
+        let backend = Backend::new();
+        let cmd = SAdd {
+            key: "key".to_string(),
+            members: vec!["member2".to_string(), "member1".to_string()],
+        };
+        cmd.execute(&backend);
+
+        let cmd = SCard {
+            key: "key".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), 2.into());
+
+        let cmd = SMembers {
+            key: "key".to_string(),
+        };
+        let expected = RespArray::new(vec![
+            BulkString::new("member1").into(),
+            BulkString::new("member2").into(),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = SCard {
+            key: "missing".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), 0.into());
+
+        let cmd = SMembers {
+            key: "missing".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespArray::new(vec![]).into());
+    }
+
+    #[test]
+    fn test_try_from_set_store() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString(Some("sunionstore".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("dest".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("key1".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("key2".as_bytes().to_vec()))),
+        ]);
+        let cmd = SUnionStore::try_from(input)?;
+        assert_eq!(cmd.destination, "dest");
+        assert_eq!(cmd.keys, vec!["key1", "key2"]);
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString(Some("sinterstore".as_bytes().to_vec()))),
+            RespFrame::BulkString(BulkString(Some("dest".as_bytes().to_vec()))),
+        ]);
+        assert!(SInterStore::try_from(input).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_store_execute() {
+        let backend = Backend::new();
+        for m in ["a", "b", "c"] {
+            backend.sadd("key1".to_string(), m.to_string()).unwrap();
+        }
+        for m in ["b", "c", "d"] {
+            backend.sadd("key2".to_string(), m.to_string()).unwrap();
+        }
+        let keys = vec!["key1".to_string(), "key2".to_string()];
+        let members = |key: &str| {
+            let mut v: Vec<String> = backend
+                .smembers(key)
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .collect();
+            v.sort();
+            v
+        };
+
+        // the destination is overwritten whatever its type
+        backend.set("dest".to_string(), "value");
+        let cmd = SUnionStore {
+            destination: "dest".to_string(),
+            keys: keys.clone(),
+        };
+        assert_eq!(cmd.execute(&backend), 4.into());
+        assert_eq!(members("dest"), vec!["a", "b", "c", "d"]);
+        assert_eq!(backend.get("dest"), Err(WrongTypeError));
+
+        let cmd = SInterStore {
+            destination: "dest".to_string(),
+            keys: keys.clone(),
+        };
+        assert_eq!(cmd.execute(&backend), 2.into());
+        assert_eq!(members("dest"), vec!["b", "c"]);
+
+        let cmd = SDiffStore {
+            destination: "dest".to_string(),
+            keys: keys.clone(),
+        };
+        assert_eq!(cmd.execute(&backend), 1.into());
+        assert_eq!(members("dest"), vec!["a"]);
+
+        let cmd = SInterStore {
+            destination: "dest".to_string(),
+            keys: vec!["key1".to_string(), "missing".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), 0.into());
+        assert!(backend.smembers("dest").unwrap().is_none());
+    }
 }
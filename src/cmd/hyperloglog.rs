@@ -0,0 +1,154 @@
+use super::{parse_strings, validate_dynamic_command, CommandError, CommandExecutor, RESP_OK};
+use crate::{Backend, RespArray, RespFrame, SimpleError};
+
+const WRONGTYPE: &str = "WRONGTYPE Key is not a valid HyperLogLog string value.";
+
+/// PFADD key [element [element ...]]
+#[derive(Debug)]
+pub struct PfAdd {
+    key: String,
+    elements: Vec<String>,
+}
+
+/// PFCOUNT key [key ...]
+#[derive(Debug)]
+pub struct PfCount {
+    keys: Vec<String>,
+}
+
+/// PFMERGE destkey [sourcekey [sourcekey ...]]
+#[derive(Debug)]
+pub struct PfMerge {
+    dest: String,
+    keys: Vec<String>,
+}
+
+impl CommandExecutor for PfAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfadd(self.key, &self.elements) {
+            Some(changed) => RespFrame::Integer(changed as i64),
+            None => SimpleError::new(WRONGTYPE).into(),
+        }
+    }
+}
+
+impl CommandExecutor for PfCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfcount(&self.keys) {
+            Some(count) => RespFrame::Integer(count as i64),
+            None => SimpleError::new(WRONGTYPE).into(),
+        }
+    }
+}
+
+impl CommandExecutor for PfMerge {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfmerge(self.dest, &self.keys) {
+            Some(()) => RESP_OK.clone(),
+            None => SimpleError::new(WRONGTYPE).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PfAdd {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "pfadd", 1)?;
+
+        let mut args = parse_strings(value)?.into_iter();
+        let key = args.next().unwrap_or_default();
+
+        Ok(PfAdd {
+            key,
+            elements: args.collect(),
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PfCount {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "pfcount", 1)?;
+
+        Ok(PfCount {
+            keys: parse_strings(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PfMerge {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "pfmerge", 1)?;
+
+        let mut args = parse_strings(value)?.into_iter();
+        let dest = args.next().unwrap_or_default();
+
+        Ok(PfMerge {
+            dest,
+            keys: args.collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+    use anyhow::Result;
+
+    fn parse_args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    #[test]
+    fn test_hyperloglog_try_from() -> Result<()> {
+        let result = PfAdd::try_from(parse_args(&["pfadd", "key", "a", "b"]))?;
+        assert_eq!(result.key, "key");
+        assert_eq!(result.elements, vec!["a", "b"]);
+        let result = PfAdd::try_from(parse_args(&["pfadd", "key"]))?;
+        assert!(result.elements.is_empty());
+        assert!(PfAdd::try_from(parse_args(&["pfadd"])).is_err());
+
+        let result = PfCount::try_from(parse_args(&["pfcount", "a", "b"]))?;
+        assert_eq!(result.keys, vec!["a", "b"]);
+        assert!(PfCount::try_from(parse_args(&["pfcount"])).is_err());
+
+        let result = PfMerge::try_from(parse_args(&["pfmerge", "dest", "a"]))?;
+        assert_eq!(result.dest, "dest");
+        assert_eq!(result.keys, vec!["a"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hyperloglog_commands() {
+        let backend = Backend::new();
+        let pfadd = |key: &str, elements: &[&str]| PfAdd {
+            key: key.to_string(),
+            elements: elements.iter().map(|e| e.to_string()).collect(),
+        };
+        assert_eq!(pfadd("a", &["x", "y"]).execute(&backend), 1.into());
+        assert_eq!(pfadd("a", &["x"]).execute(&backend), 0.into());
+        assert_eq!(pfadd("b", &["y", "z"]).execute(&backend), 1.into());
+
+        let pfmerge = PfMerge {
+            dest: "c".to_string(),
+            keys: vec!["a".to_string(), "b".to_string()],
+        };
+        assert_eq!(pfmerge.execute(&backend), RESP_OK.clone());
+        let pfcount = PfCount {
+            keys: vec!["c".to_string()],
+        };
+        assert_eq!(pfcount.execute(&backend), 3.into());
+
+        backend.set("s".to_string(), "v");
+        assert_eq!(
+            pfadd("s", &["x"]).execute(&backend),
+            SimpleError::new(WRONGTYPE).into()
+        );
+    }
+}
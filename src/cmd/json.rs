@@ -0,0 +1,389 @@
+use super::{
+    extract_args, parse, validate_dynamic_command, CommandError, CommandExecutor, CommandParser,
+    RESP_OK,
+};
+use crate::{
+    Backend, BulkString, JsonError, JsonPath, JsonSetMode, RespArray, RespFrame, RespNull,
+    SimpleError,
+};
+use phf::phf_map;
+use serde_json::{Map, Value};
+
+pub(super) static JSON_COMMANDS: phf::Map<&'static [u8], CommandParser> = phf_map! {
+    b"json.set" => parse::<JsonSet>,
+    b"json.get" => parse::<JsonGet>,
+    b"json.del" => parse::<JsonDel>,
+    b"json.arrappend" => parse::<JsonArrAppend>,
+};
+
+/// JSON.SET key path value [NX | XX]
+#[derive(Debug)]
+pub struct JsonSet {
+    key: String,
+    path: JsonPath,
+    value: Value,
+    mode: JsonSetMode,
+}
+
+/// JSON.GET key [path ...]
+#[derive(Debug)]
+pub struct JsonGet {
+    key: String,
+    paths: Vec<JsonPath>,
+}
+
+/// JSON.DEL key [path]
+#[derive(Debug)]
+pub struct JsonDel {
+    key: String,
+    path: JsonPath,
+}
+
+/// JSON.ARRAPPEND key path value [value ...]
+#[derive(Debug)]
+pub struct JsonArrAppend {
+    key: String,
+    path: JsonPath,
+    values: Vec<Value>,
+}
+
+impl CommandExecutor for JsonSet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.json_set(self.key, &self.path, self.value, self.mode) {
+            Ok(true) => RESP_OK.clone(),
+            Ok(false) => RespFrame::Null(RespNull),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for JsonGet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let values = match backend.json_get(&self.key, &self.paths) {
+            Ok(Some(values)) => values,
+            Ok(None) => return RespFrame::Null(RespNull),
+            Err(e) => return SimpleError::new(e.to_string()).into(),
+        };
+
+        // a legacy path replies with the value itself, a JSONPath with the list of matches
+        let mut replies = Vec::with_capacity(values.len());
+        for (path, value) in self.paths.iter().zip(values) {
+            let reply = match (path.is_legacy(), value) {
+                (true, Some(value)) => value,
+                (true, None) => {
+                    return SimpleError::new(JsonError::PathMissing(path.to_string()).to_string())
+                        .into()
+                }
+                (false, value) => Value::Array(value.into_iter().collect()),
+            };
+            replies.push((path.to_string(), reply));
+        }
+
+        let reply = if replies.len() == 1 {
+            replies.remove(0).1
+        } else {
+            Value::Object(replies.into_iter().collect::<Map<_, _>>())
+        };
+        BulkString::new(reply.to_string()).into()
+    }
+}
+
+impl CommandExecutor for JsonDel {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.json_del(&self.key, &self.path) {
+            Ok(deleted) => RespFrame::Integer(deleted as i64),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for JsonArrAppend {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let ret = backend.json_arrappend(&self.key, &self.path, self.values);
+        if self.path.is_legacy() {
+            return match ret {
+                Ok(len) => RespFrame::Integer(len as i64),
+                Err(e) => SimpleError::new(e.to_string()).into(),
+            };
+        }
+        // a JSONPath replies with one length per match, nil for a match that is not an array
+        match ret {
+            Ok(len) => RespArray::new(vec![RespFrame::Integer(len as i64)]).into(),
+            Err(JsonError::WrongType(_)) => RespArray::new(vec![RespFrame::Null(RespNull)]).into(),
+            Err(JsonError::PathMissing(_)) => RespArray::new(vec![]).into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for JsonSet {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "json.set", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, path, value) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(path)))),
+                Some(RespFrame::BulkString(BulkString(Some(value)))),
+            ) => (
+                String::from_utf8(key)?,
+                parse_path(path)?,
+                parse_value(&value)?,
+            ),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let mode = match (args.next(), args.next()) {
+            (None, None) => JsonSetMode::Always,
+            (Some(RespFrame::BulkString(BulkString(Some(mode)))), None)
+                if mode.eq_ignore_ascii_case(b"nx") =>
+            {
+                JsonSetMode::IfMissing
+            }
+            (Some(RespFrame::BulkString(BulkString(Some(mode)))), None)
+                if mode.eq_ignore_ascii_case(b"xx") =>
+            {
+                JsonSetMode::IfExists
+            }
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+
+        Ok(JsonSet {
+            key,
+            path,
+            value,
+            mode,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for JsonGet {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "json.get", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => String::from_utf8(key)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let mut paths = Vec::new();
+        for arg in args {
+            match arg {
+                RespFrame::BulkString(BulkString(Some(path))) => paths.push(parse_path(path)?),
+                _ => return Err(CommandError::InvalidArgument("Invalid path".to_string())),
+            }
+        }
+        if paths.is_empty() {
+            paths.push(JsonPath::parse(".").expect("the legacy root is a valid path"));
+        }
+
+        Ok(JsonGet { key, paths })
+    }
+}
+
+impl TryFrom<RespArray> for JsonDel {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "json.del", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (Some(RespFrame::BulkString(BulkString(Some(key)))), path, None) => {
+                let path = match path {
+                    None => JsonPath::root(),
+                    Some(RespFrame::BulkString(BulkString(Some(path)))) => parse_path(path)?,
+                    _ => return Err(CommandError::InvalidArgument("Invalid path".to_string())),
+                };
+                Ok(JsonDel {
+                    key: String::from_utf8(key)?,
+                    path,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "json.del command must have at most 2 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for JsonArrAppend {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "json.arrappend", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, path) = match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(path)))),
+            ) => (String::from_utf8(key)?, parse_path(path)?),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let mut values = Vec::new();
+        for arg in args {
+            match arg {
+                RespFrame::BulkString(BulkString(Some(value))) => values.push(parse_value(&value)?),
+                _ => return Err(CommandError::InvalidArgument("Invalid value".to_string())),
+            }
+        }
+
+        Ok(JsonArrAppend { key, path, values })
+    }
+}
+
+fn parse_path(path: Vec<u8>) -> Result<JsonPath, CommandError> {
+    JsonPath::parse(&String::from_utf8(path)?).map_err(CommandError::InvalidArgument)
+}
+
+fn parse_value(value: &[u8]) -> Result<Value, CommandError> {
+    serde_json::from_slice(value).map_err(|e| CommandError::InvalidArgument(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use serde_json::json;
+
+    fn parse_args(args: &[&str]) -> RespArray {
+        RespArray::new(
+            args.iter()
+                .map(|a| BulkString::new(a.as_bytes()).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+    }
+
+    fn execute(backend: &Backend, args: &[&str]) -> Result<RespFrame> {
+        let cmd = match args[0] {
+            "json.set" => JsonSet::try_from(parse_args(args))?.execute(backend),
+            "json.get" => JsonGet::try_from(parse_args(args))?.execute(backend),
+            "json.del" => JsonDel::try_from(parse_args(args))?.execute(backend),
+            "json.arrappend" => JsonArrAppend::try_from(parse_args(args))?.execute(backend),
+            _ => unreachable!(),
+        };
+        Ok(cmd)
+    }
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::new(s).into()
+    }
+
+    #[test]
+    fn test_json_try_from() -> Result<()> {
+        let result = JsonSet::try_from(parse_args(&["JSON.SET", "doc", "$", r#"{"a":1}"#, "NX"]))?;
+        assert_eq!(result.key, "doc");
+        assert!(result.path.is_root());
+        assert_eq!(result.value, json!({"a": 1}));
+        assert_eq!(result.mode, JsonSetMode::IfMissing);
+
+        assert!(JsonSet::try_from(parse_args(&["json.set", "doc", "$", "{invalid"])).is_err());
+        assert!(JsonSet::try_from(parse_args(&["json.set", "doc", "$", "1", "foo"])).is_err());
+        assert!(JsonSet::try_from(parse_args(&["json.set", "doc", "$.*", "1"])).is_err());
+
+        let result = JsonGet::try_from(parse_args(&["json.get", "doc"]))?;
+        assert_eq!(result.paths.len(), 1);
+        assert!(result.paths[0].is_legacy() && result.paths[0].is_root());
+
+        let result = JsonDel::try_from(parse_args(&["json.del", "doc"]))?;
+        assert!(result.path.is_root());
+        assert!(JsonDel::try_from(parse_args(&["json.del", "doc", "$", "$"])).is_err());
+
+        let result =
+            JsonArrAppend::try_from(parse_args(&["json.arrappend", "doc", "$.a", "1", "\"x\""]))?;
+        assert_eq!(result.values, vec![json!(1), json!("x")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        assert_eq!(
+            execute(&backend, &["json.set", "doc", "$.a", "1"])?,
+            SimpleError::new("ERR new objects must be created at the root").into()
+        );
+        assert_eq!(
+            execute(&backend, &["json.set", "doc", "$", r#"{"a":{"b":[1]}}"#])?,
+            RESP_OK.clone()
+        );
+        assert_eq!(
+            execute(&backend, &["json.set", "doc", "$.a.c", "\"x\"", "XX"])?,
+            RespFrame::Null(RespNull)
+        );
+        assert_eq!(
+            execute(&backend, &["json.set", "doc", "$.a.c", "\"x\"", "NX"])?,
+            RESP_OK.clone()
+        );
+
+        assert_eq!(
+            execute(&backend, &["json.get", "doc"])?,
+            bulk(r#"{"a":{"b":[1],"c":"x"}}"#)
+        );
+        assert_eq!(
+            execute(&backend, &["json.get", "doc", "$.a.c"])?,
+            bulk(r#"["x"]"#)
+        );
+        assert_eq!(execute(&backend, &["json.get", "doc", "$.x"])?, bulk("[]"));
+        assert_eq!(
+            execute(&backend, &["json.get", "doc", ".a.c"])?,
+            bulk(r#""x""#)
+        );
+        assert_eq!(
+            execute(&backend, &["json.get", "doc", ".x"])?,
+            SimpleError::new("ERR Path '.x' does not exist").into()
+        );
+        assert_eq!(
+            execute(&backend, &["json.get", "doc", ".a.c", "$.a.b"])?,
+            bulk(r#"{"$.a.b":[[1]],".a.c":"x"}"#)
+        );
+        assert_eq!(
+            execute(&backend, &["json.get", "missing"])?,
+            RespFrame::Null(RespNull)
+        );
+
+        assert_eq!(
+            execute(&backend, &["json.arrappend", "doc", "$.a.b", "2", "3"])?,
+            RespArray::new(vec![RespFrame::Integer(3)]).into()
+        );
+        assert_eq!(
+            execute(&backend, &["json.arrappend", "doc", "$.a.c", "2"])?,
+            RespArray::new(vec![RespFrame::Null(RespNull)]).into()
+        );
+        assert_eq!(
+            execute(&backend, &["json.arrappend", "doc", ".a.b", "4"])?,
+            RespFrame::Integer(4)
+        );
+        assert_eq!(
+            execute(&backend, &["json.arrappend", "missing", ".a", "4"])?,
+            SimpleError::new("ERR could not perform this operation on a key that doesn't exist")
+                .into()
+        );
+
+        assert_eq!(
+            execute(&backend, &["json.del", "doc", "$.a.b[0]"])?,
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            execute(&backend, &["json.get", "doc", "$.a.b"])?,
+            bulk("[[2,3,4]]")
+        );
+        assert_eq!(
+            execute(&backend, &["json.del", "doc"])?,
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            execute(&backend, &["json.del", "doc"])?,
+            RespFrame::Integer(0)
+        );
+        assert!(!backend.exists("doc"));
+
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
-use super::{extract_args, validate_command, CommandError, CommandExecutor, RESP_OK};
-use crate::{Backend, BulkString, RespArray, RespFrame, RespNull};
+use super::{
+    extract_args, parse_expiry_with_jitter, validate_command, validate_dynamic_command,
+    CommandError, CommandExecutor, Expiry, ExpiryOptions, RESP_OK,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError};
+use bytes::Bytes;
 
 #[derive(Debug)]
 pub struct Get {
@@ -9,25 +13,70 @@ pub struct Get {
 #[derive(Debug)]
 pub struct Set {
     key: String,
-    value: RespFrame,
+    value: Bytes,
+    expiry: Option<Expiry>,
+    // overrides the server-wide TTL jitter
+    jitter: Option<u8>,
+}
+
+#[derive(Debug)]
+pub struct GetEx {
+    key: String,
+    expiry: Option<Expiry>,
+    // overrides the server-wide TTL jitter
+    jitter: Option<u8>,
 }
 
 impl CommandExecutor for Get {
     fn execute(self, backend: &Backend) -> RespFrame {
         match backend.get(&self.key) {
-            Some(value) => value,
-            None => RespFrame::Null(RespNull),
+            Ok(Some(value)) => BulkString::new(value).into(),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => SimpleError::new(e.to_string()).into(),
         }
     }
 }
 
 impl CommandExecutor for Set {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.set(self.key, self.value);
+        match self.expiry {
+            Some(Expiry::KeepTtl) => backend.set_keepttl(self.key, self.value),
+            expiry => {
+                backend.set(self.key.clone(), self.value);
+                let jitter = self.jitter.unwrap_or_else(|| backend.ttl_jitter());
+                if let Some(at) = expiry.and_then(|e| e.deadline(jitter)) {
+                    backend.expire_at(&self.key, at);
+                }
+            }
+        }
         RESP_OK.clone()
     }
 }
 
+impl CommandExecutor for GetEx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let value = match backend.get(&self.key) {
+            Ok(Some(value)) => value,
+            Ok(None) => return RespFrame::Null(RespNull),
+            Err(e) => return SimpleError::new(e.to_string()).into(),
+        };
+
+        match self.expiry {
+            Some(Expiry::Persist) => {
+                backend.persist(&self.key);
+            }
+            Some(expiry) => {
+                let jitter = self.jitter.unwrap_or_else(|| backend.ttl_jitter());
+                if let Some(at) = expiry.deadline(jitter) {
+                    backend.expire_at(&self.key, at);
+                }
+            }
+            None => (),
+        }
+        BulkString::new(value).into()
+    }
+}
+
 impl TryFrom<RespArray> for Get {
     type Error = CommandError;
 
@@ -49,15 +98,27 @@ impl TryFrom<RespArray> for Set {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, "set", 2)?;
+        validate_dynamic_command(&value, "set", 2)?;
 
         let mut args = extract_args(value, 1)?.into_iter();
 
         match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(BulkString(Some(key)))), Some(value)) => Ok(Set {
-                key: String::from_utf8(key)?,
-                value,
-            }),
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(value)))),
+            ) => {
+                let options = ExpiryOptions {
+                    persist: false,
+                    keepttl: true,
+                };
+                let (expiry, jitter) = parse_expiry_with_jitter(args, "set", options)?;
+                Ok(Set {
+                    key: String::from_utf8(key)?,
+                    value: value.into(),
+                    expiry,
+                    jitter,
+                })
+            }
             _ => Err(CommandError::InvalidArgument(
                 "Invalid key or value".to_string(),
             )),
@@ -65,10 +126,36 @@ impl TryFrom<RespArray> for Set {
     }
 }
 
+impl TryFrom<RespArray> for GetEx {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "getex", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => {
+                let options = ExpiryOptions {
+                    persist: true,
+                    keepttl: false,
+                };
+                let (expiry, jitter) = parse_expiry_with_jitter(args, "getex", options)?;
+                Ok(GetEx {
+                    key: String::from_utf8(key)?,
+                    expiry,
+                    jitter,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{BulkString, RespFrame};
+    use crate::{now_ms, BulkString, RespFrame};
     use anyhow::Result;
 
     #[test]
@@ -96,10 +183,42 @@ mod tests {
         let result = Set::try_from(input)?;
 
         assert_eq!(result.key, "hello".to_string());
-        assert_eq!(
-            result.value,
-            RespFrame::BulkString(BulkString::new("world".as_bytes()))
-        );
+        assert_eq!(result.value, Bytes::from("world"));
+        assert_eq!(result.expiry, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_with_expiry_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("set".as_bytes())),
+            RespFrame::BulkString(BulkString::new("hello".as_bytes())),
+            RespFrame::BulkString(BulkString::new("world".as_bytes())),
+            RespFrame::BulkString(BulkString::new("px".as_bytes())),
+            RespFrame::BulkString(BulkString::new("100".as_bytes())),
+        ]);
+
+        let result = Set::try_from(input)?;
+
+        assert_eq!(result.key, "hello".to_string());
+        assert_eq!(result.expiry, Some(Expiry::Px(100)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_getex_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("getex".as_bytes())),
+            RespFrame::BulkString(BulkString::new("hello".as_bytes())),
+            RespFrame::BulkString(BulkString::new("persist".as_bytes())),
+        ]);
+
+        let result = GetEx::try_from(input)?;
+
+        assert_eq!(result.key, "hello".to_string());
+        assert_eq!(result.expiry, Some(Expiry::Persist));
 
         Ok(())
     }
@@ -110,7 +229,9 @@ mod tests {
 
         let set = Set {
             key: "hello".to_string(),
-            value: RespFrame::BulkString(BulkString::new("world".as_bytes())),
+            value: "world".into(),
+            expiry: None,
+            jitter: None,
         };
         let result = set.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
@@ -126,4 +247,72 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_getex_expiry_command() -> Result<()> {
+        let backend = Backend::new();
+        let value = RespFrame::BulkString(BulkString::new("world".as_bytes()));
+
+        let set = Set {
+            key: "hello".to_string(),
+            value: "world".into(),
+            expiry: Some(Expiry::Ex(100)),
+            jitter: None,
+        };
+        set.execute(&backend);
+        assert!(backend.expire_time("hello").is_some());
+
+        let set = Set {
+            key: "hello".to_string(),
+            value: "world".into(),
+            expiry: Some(Expiry::KeepTtl),
+            jitter: None,
+        };
+        set.execute(&backend);
+        assert!(backend.expire_time("hello").is_some());
+
+        let getex = GetEx {
+            key: "hello".to_string(),
+            expiry: Some(Expiry::Persist),
+            jitter: None,
+        };
+        assert_eq!(getex.execute(&backend), value);
+        assert_eq!(backend.expire_time("hello"), None);
+
+        let getex = GetEx {
+            key: "hello".to_string(),
+            expiry: Some(Expiry::PxAt(1)),
+            jitter: None,
+        };
+        assert_eq!(getex.execute(&backend), value);
+
+        let get = Get {
+            key: "hello".to_string(),
+        };
+        assert_eq!(get.execute(&backend), RespFrame::Null(RespNull));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_ttl_jitter_command() {
+        let backend = Backend::new();
+        backend.set_ttl_jitter(50);
+
+        let set = |jitter| Set {
+            key: "key".to_string(),
+            value: "value".into(),
+            expiry: Some(Expiry::Px(10_000)),
+            jitter,
+        };
+        let now = now_ms();
+        set(None).execute(&backend);
+        let at = backend.expire_time("key").unwrap();
+        assert!((now + 10_000..now + 15_100).contains(&at));
+
+        // the command option overrides the server-wide jitter
+        set(Some(0)).execute(&backend);
+        let at = backend.expire_time("key").unwrap();
+        assert!((now + 10_000..now + 10_100).contains(&at));
+    }
 }
@@ -1,22 +1,142 @@
+mod bitmap;
+mod bloom;
+mod command;
+mod debug;
+mod dump;
 mod echo;
+mod expiry;
+#[cfg(feature = "wasm")]
+mod function;
+mod generic;
 mod hmap;
 mod hset;
+mod hyperloglog;
+#[cfg(feature = "json")]
+mod json;
 mod map;
+mod pubsub;
+mod server;
+mod sketch;
+mod timeseries;
 
-use crate::{Backend, BulkString, RespArray, RespError, RespFrame, SimpleString};
+use crate::{Backend, BulkString, RespArray, RespError, RespFrame, SimpleError, SimpleString};
+use bitmap::*;
+use bloom::*;
+use command::*;
+use debug::*;
+use dump::*;
 use echo::*;
 use enum_dispatch::enum_dispatch;
+use expiry::*;
+#[cfg(feature = "wasm")]
+use function::*;
+use generic::*;
 use hmap::*;
 use hset::*;
+use hyperloglog::*;
+#[cfg(feature = "json")]
+use json::*;
 use lazy_static::lazy_static;
 use map::*;
+use phf::phf_map;
+use pubsub::*;
+use server::*;
+use sketch::*;
+use std::time::Duration;
 use thiserror::Error;
+use timeseries::*;
 use tracing::info;
 
 lazy_static! {
     static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
 }
 
+type CommandParser = fn(RespArray) -> Result<Command, CommandError>;
+
+// length of the longest command name in COMMANDS
+const MAX_COMMAND_LEN: usize = 16;
+
+static COMMANDS: phf::Map<&'static [u8], CommandParser> = phf_map! {
+    b"get" => parse::<Get>,
+    b"set" => parse::<Set>,
+    b"getex" => parse::<GetEx>,
+    b"hget" => parse::<HGet>,
+    b"hset" => parse::<HSet>,
+    b"hsetnx" => parse::<HSetNx>,
+    b"hgetall" => parse::<HGetAll>,
+    b"hmget" => parse::<HMGet>,
+    b"hexists" => parse::<HExists>,
+    b"hlen" => parse::<HLen>,
+    b"hstrlen" => parse::<HStrLen>,
+    b"echo" => parse::<Echo>,
+    b"sadd" => parse::<SAdd>,
+    b"sismember" => parse::<SIsMember>,
+    b"smismember" => parse::<SMIsMember>,
+    b"scard" => parse::<SCard>,
+    b"smembers" => parse::<SMembers>,
+    b"sunionstore" => parse::<SUnionStore>,
+    b"sinterstore" => parse::<SInterStore>,
+    b"sdiffstore" => parse::<SDiffStore>,
+    b"sintercard" => parse::<SInterCard>,
+    b"setbit" => parse::<SetBit>,
+    b"getbit" => parse::<GetBit>,
+    b"bitcount" => parse::<BitCount>,
+    b"bitop" => parse::<BitOp>,
+    b"bitpos" => parse::<BitPos>,
+    b"pfadd" => parse::<PfAdd>,
+    b"pfcount" => parse::<PfCount>,
+    b"pfmerge" => parse::<PfMerge>,
+    b"bf.reserve" => parse::<BfReserve>,
+    b"bf.add" => parse::<BfAdd>,
+    b"bf.exists" => parse::<BfExists>,
+    b"cf.reserve" => parse::<CfReserve>,
+    b"cf.add" => parse::<CfAdd>,
+    b"cf.exists" => parse::<CfExists>,
+    b"cf.del" => parse::<CfDel>,
+    b"cms.initbydim" => parse::<CmsInitByDim>,
+    b"cms.initbyprob" => parse::<CmsInitByProb>,
+    b"cms.incrby" => parse::<CmsIncrBy>,
+    b"cms.query" => parse::<CmsQuery>,
+    b"cms.info" => parse::<CmsInfo>,
+    b"topk.reserve" => parse::<TopKReserve>,
+    b"topk.add" => parse::<TopKAdd>,
+    b"topk.query" => parse::<TopKQuery>,
+    b"topk.list" => parse::<TopKList>,
+    b"topk.info" => parse::<TopKInfo>,
+    b"ts.create" => parse::<TsCreate>,
+    b"ts.add" => parse::<TsAdd>,
+    b"ts.get" => parse::<TsGet>,
+    b"ts.range" => parse::<TsRange>,
+    b"ts.createrule" => parse::<TsCreateRule>,
+    b"keys" => parse::<Keys>,
+    b"scan" => parse::<Scan>,
+    b"copy" => parse::<CopyCommand>,
+    b"move" => parse::<Move>,
+    b"expire" => parse::<Expire>,
+    b"pexpire" => parse::<PExpire>,
+    b"expireat" => parse::<ExpireAt>,
+    b"pexpireat" => parse::<PExpireAt>,
+    b"ttl" => parse::<Ttl>,
+    b"pttl" => parse::<PTtl>,
+    b"expiretime" => parse::<ExpireTime>,
+    b"pexpiretime" => parse::<PExpireTime>,
+    b"unlink" => parse::<Unlink>,
+    b"touch" => parse::<Touch>,
+    b"object" => parse::<Object>,
+    b"dump" => parse::<Dump>,
+    b"restore" => parse::<Restore>,
+    b"role" => parse::<Role>,
+    b"lastsave" => parse::<LastSave>,
+    b"info" => parse::<Info>,
+    b"memory" => parse::<Memory>,
+    b"bigkeys" => parse::<BigKeys>,
+    b"config" => parse::<Config>,
+    b"debug" => parse::<DebugCommand>,
+    b"command" => parse::<CommandQuery>,
+    b"shutdown" => parse::<Shutdown>,
+    b"spublish" => parse::<SPublish>,
+};
+
 #[derive(Error, Debug)]
 pub enum CommandError {
     #[error("Invalid command: {0}")]
@@ -29,6 +149,13 @@ pub enum CommandError {
     Utf8Error(#[from] std::string::FromUtf8Error),
 }
 
+// a command that fails to parse replies with an error, the connection stays usable
+impl From<CommandError> for RespFrame {
+    fn from(e: CommandError) -> Self {
+        SimpleError::new(format!("ERR {}", e)).into()
+    }
+}
+
 #[enum_dispatch]
 pub trait CommandExecutor {
     fn execute(self, backend: &Backend) -> RespFrame;
@@ -39,22 +166,141 @@ pub trait CommandExecutor {
 pub enum Command {
     Get(Get),
     Set(Set),
+    GetEx(GetEx),
     HGet(HGet),
     HSet(HSet),
+    HSetNx(HSetNx),
     HMGet(HMGet),
     HGetAll(HGetAll),
+    HExists(HExists),
+    HLen(HLen),
+    HStrLen(HStrLen),
     SAdd(SAdd),
     SIsMember(SIsMember),
+    SMIsMember(SMIsMember),
+    SCard(SCard),
+    SMembers(SMembers),
+    SUnionStore(SUnionStore),
+    SInterStore(SInterStore),
+    SDiffStore(SDiffStore),
+    SInterCard(SInterCard),
+    SetBit(SetBit),
+    GetBit(GetBit),
+    BitCount(BitCount),
+    BitOp(BitOp),
+    BitPos(BitPos),
+    PfAdd(PfAdd),
+    PfCount(PfCount),
+    PfMerge(PfMerge),
+    BfReserve(BfReserve),
+    BfAdd(BfAdd),
+    BfExists(BfExists),
+    CfReserve(CfReserve),
+    CfAdd(CfAdd),
+    CfExists(CfExists),
+    CfDel(CfDel),
+    CmsInitByDim(CmsInitByDim),
+    CmsInitByProb(CmsInitByProb),
+    CmsIncrBy(CmsIncrBy),
+    CmsQuery(CmsQuery),
+    CmsInfo(CmsInfo),
+    TopKReserve(TopKReserve),
+    TopKAdd(TopKAdd),
+    TopKQuery(TopKQuery),
+    TopKList(TopKList),
+    TopKInfo(TopKInfo),
+    TsCreate(TsCreate),
+    TsAdd(TsAdd),
+    TsGet(TsGet),
+    TsRange(TsRange),
+    TsCreateRule(TsCreateRule),
+    #[cfg(feature = "json")]
+    JsonSet(JsonSet),
+    #[cfg(feature = "json")]
+    JsonGet(JsonGet),
+    #[cfg(feature = "json")]
+    JsonDel(JsonDel),
+    #[cfg(feature = "json")]
+    JsonArrAppend(JsonArrAppend),
     Echo(Echo),
+    Keys(Keys),
+    Scan(Scan),
+    Copy(CopyCommand),
+    Move(Move),
+    Expire(Expire),
+    PExpire(PExpire),
+    ExpireAt(ExpireAt),
+    PExpireAt(PExpireAt),
+    Ttl(Ttl),
+    PTtl(PTtl),
+    ExpireTime(ExpireTime),
+    PExpireTime(PExpireTime),
+    Unlink(Unlink),
+    Touch(Touch),
+    Object(Object),
+    Dump(Dump),
+    Restore(Restore),
+    Role(Role),
+    LastSave(LastSave),
+    Info(Info),
+    Memory(Memory),
+    BigKeys(BigKeys),
+    Config(Config),
+    Debug(DebugCommand),
+    Command(CommandQuery),
+    Shutdown(Shutdown),
+    SPublish(SPublish),
+    #[cfg(feature = "wasm")]
+    Function(FunctionCommand),
+    #[cfg(feature = "wasm")]
+    FCall(FCall),
     Unrecognized(Unrecognized),
 }
 
+impl Command {
+    /// How long the server stalls before running the command, only DEBUG SLEEP waits.
+    pub fn delay(&self) -> Option<Duration> {
+        match self {
+            Command::Debug(DebugCommand::Sleep(sleep)) => Some(sleep.duration),
+            _ => None,
+        }
+    }
+}
+
+/// A command that is not one of the built-in commands, its name and arguments as sent.
 #[derive(Debug)]
-pub struct Unrecognized;
+pub struct Unrecognized {
+    name: String,
+    args: Vec<String>,
+}
+
+// longest part of the name or of an argument quoted by the unknown command error
+const MAX_UNRECOGNIZED_LEN: usize = 128;
+
+impl Unrecognized {
+    fn new(args: &[RespFrame]) -> Self {
+        let mut args = args.iter().map(|arg| match arg {
+            RespFrame::BulkString(BulkString(Some(arg))) => {
+                let arg = &arg[..arg.len().min(MAX_UNRECOGNIZED_LEN)];
+                String::from_utf8_lossy(arg).into_owned()
+            }
+            _ => String::new(),
+        });
+        Self {
+            name: args.next().unwrap_or_default(),
+            args: args.collect(),
+        }
+    }
+}
 
 impl CommandExecutor for Unrecognized {
     fn execute(self, _: &Backend) -> RespFrame {
-        RESP_OK.clone()
+        let args: String = self.args.iter().map(|arg| format!("'{}' ", arg)).collect();
+        SimpleError::new(format!(
+            "ERR unknown command '{}', with args beginning with: {}",
+            self.name, args
+        ))
+        .into()
     }
 }
 
@@ -76,53 +322,82 @@ impl TryFrom<RespArray> for Command {
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
         info!("Command: {:?}", value);
-        match &value.0 {
-            None => Err(CommandError::InvalidCommand(
-                "Invalid command, Command must not be RespNullArray".to_string(),
-            )),
-            Some(vec) => {
-                let mut args = vec.iter();
-                match args.next() {
-                    Some(RespFrame::BulkString(BulkString(Some(ref command)))) => {
-                        match command.to_ascii_lowercase().as_slice() {
-                            b"get" => Ok(Get::try_from(value)?.into()),
-                            b"set" => Ok(Set::try_from(value)?.into()),
-                            b"hget" => Ok(HGet::try_from(value)?.into()),
-                            b"hset" => Ok(HSet::try_from(value)?.into()),
-                            b"hgetall" => Ok(HGetAll::try_from(value)?.into()),
-                            b"hmget" => Ok(HMGet::try_from(value)?.into()),
-                            b"echo" => Ok(Echo::try_from(value)?.into()),
-                            b"sadd" => Ok(SAdd::try_from(value)?.into()),
-                            b"sismember" => Ok(SIsMember::try_from(value)?.into()),
-                            _ => Ok(Unrecognized.into()),
-                        }
-                    }
-                    _ => Err(CommandError::InvalidCommand(
+        let parser = match &value.0 {
+            None => {
+                return Err(CommandError::InvalidCommand(
+                    "Invalid command, Command must not be RespNullArray".to_string(),
+                ))
+            }
+            Some(vec) => match vec.first() {
+                Some(RespFrame::BulkString(BulkString(Some(ref command)))) => {
+                    lookup_command(command)
+                }
+                _ => {
+                    return Err(CommandError::InvalidCommand(
                         "Invalid command, command must have a BulkString as the first arg"
                             .to_string(),
-                    )),
+                    ))
                 }
-            }
+            },
+        };
+
+        match parser {
+            Some(parser) => parser(value),
+            None => Ok(Unrecognized::new(value.0.as_deref().unwrap_or_default()).into()),
         }
     }
 }
 
+/// Whether a command name is one of the built-in commands, case-insensitive.
+pub fn is_builtin(name: &[u8]) -> bool {
+    lookup_command(name).is_some()
+}
+
+fn parse<T>(value: RespArray) -> Result<Command, CommandError>
+where
+    T: TryFrom<RespArray, Error = CommandError> + Into<Command>,
+{
+    Ok(T::try_from(value)?.into())
+}
+
+// the lower case of a command name written to a buffer, None if it is longer than any command
+fn lowercase_name<'a>(name: &[u8], buf: &'a mut [u8; MAX_COMMAND_LEN]) -> Option<&'a [u8]> {
+    let buf = buf.get_mut(..name.len())?;
+    buf.copy_from_slice(name);
+    buf.make_ascii_lowercase();
+    Some(buf)
+}
+
+// case-insensitive lookup of a command name without allocating
+fn lookup_command(name: &[u8]) -> Option<CommandParser> {
+    let mut buf = [0u8; MAX_COMMAND_LEN];
+    let buf = lowercase_name(name, &mut buf)?;
+    // phf_map! can not conditionally compile entries, feature gated commands have their own map
+    #[cfg(feature = "json")]
+    if let Some(parser) = JSON_COMMANDS.get(buf) {
+        return Some(*parser);
+    }
+    #[cfg(feature = "wasm")]
+    if let Some(parser) = FUNCTION_COMMANDS.get(buf) {
+        return Some(*parser);
+    }
+    COMMANDS.get(buf).copied()
+}
+
 pub fn validate_command(
     args: &RespArray,
     name: &str,
     expected_len: usize,
 ) -> Result<(), CommandError> {
     validate_command_name(args, name)?;
-    match args {
-        RespArray(Some(ref args)) => {
-            if args.len() != expected_len + 1 {
-                return Err(CommandError::InvalidArgument(format!(
-                    "{} command must have exactly {} arguments",
-                    name, expected_len
-                )));
-            }
+    // RespArray(None) is rejected by validate_command_name
+    if let RespArray(Some(ref args)) = args {
+        if args.len() != expected_len + 1 {
+            return Err(CommandError::InvalidArgument(format!(
+                "{} command must have exactly {} arguments",
+                name, expected_len
+            )));
         }
-        RespArray(None) => (), // This should never happen
     }
 
     Ok(())
@@ -134,16 +409,14 @@ pub fn validate_dynamic_command(
     at_least: usize,
 ) -> Result<(), CommandError> {
     validate_command_name(args, name)?;
-    match args {
-        RespArray(Some(ref args)) => {
-            if args.len() < at_least + 1 {
-                return Err(CommandError::InvalidArgument(format!(
-                    "{} command must have at least {} arguments",
-                    name, at_least
-                )));
-            }
+    // RespArray(None) is rejected by validate_command_name
+    if let RespArray(Some(ref args)) = args {
+        if args.len() < at_least + 1 {
+            return Err(CommandError::InvalidArgument(format!(
+                "{} command must have at least {} arguments",
+                name, at_least
+            )));
         }
-        RespArray(None) => (), // This should never happen
     }
 
     Ok(())
@@ -158,7 +431,7 @@ fn validate_command_name(args: &RespArray, name: &str) -> Result<(), CommandErro
         }
         RespArray(Some(ref args)) => match args[0] {
             RespFrame::BulkString(BulkString(Some(ref command))) => {
-                if command.to_ascii_lowercase() != name.as_bytes() {
+                if !command.eq_ignore_ascii_case(name.as_bytes()) {
                     return Err(CommandError::InvalidCommand(format!(
                         "Invalid command: expected {}",
                         name
@@ -177,6 +450,15 @@ fn validate_command_name(args: &RespArray, name: &str) -> Result<(), CommandErro
     Ok(())
 }
 
+// parse a numeric argument, None if it is not a number or out of the valid range
+fn parse_number<T: std::str::FromStr>(arg: Vec<u8>, valid: impl Fn(&T) -> bool) -> Option<T> {
+    std::str::from_utf8(&arg)
+        .ok()?
+        .parse::<T>()
+        .ok()
+        .filter(valid)
+}
+
 pub fn extract_args(args: RespArray, start: usize) -> Result<Vec<RespFrame>, CommandError> {
     match args.0 {
         None => Err(CommandError::InvalidCommand(
@@ -185,3 +467,92 @@ pub fn extract_args(args: RespArray, start: usize) -> Result<Vec<RespFrame>, Com
         Some(args) => Ok(args.into_iter().skip(start).collect()),
     }
 }
+
+/// The flags of the built-in command named by the arguments, as listed by COMMAND.
+///
+/// Module commands declare no flags and have none.
+pub(crate) fn command_flags(args: &RespArray) -> &'static [&'static str] {
+    match args.0.as_ref().and_then(|args| args.first()) {
+        Some(RespFrame::BulkString(BulkString(Some(name)))) => command_spec(name)
+            .map(|(_, spec)| spec.flags)
+            .unwrap_or_default(),
+        _ => &[],
+    }
+}
+
+// every argument after the command name as a string
+pub(crate) fn parse_strings(value: RespArray) -> Result<Vec<String>, CommandError> {
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|arg| match arg {
+            RespFrame::BulkString(BulkString(Some(arg))) => Ok(String::from_utf8(arg)?),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_lookup_command() {
+        assert!(lookup_command(b"get").is_some());
+        assert!(lookup_command(b"GeT").is_some());
+        assert!(lookup_command(b"SISMEMBER").is_some());
+        assert!(lookup_command(b"unknown").is_none());
+        assert!(lookup_command(b"").is_none());
+        assert!(lookup_command(&[b'a'; MAX_COMMAND_LEN + 1]).is_none());
+        assert!(COMMANDS.keys().all(|name| name.len() <= MAX_COMMAND_LEN));
+        #[cfg(feature = "json")]
+        assert!(JSON_COMMANDS
+            .keys()
+            .all(|name| name.len() <= MAX_COMMAND_LEN));
+        #[cfg(feature = "wasm")]
+        assert!(FUNCTION_COMMANDS
+            .keys()
+            .all(|name| name.len() <= MAX_COMMAND_LEN));
+    }
+
+    #[test]
+    fn test_command_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("GET".as_bytes())),
+            RespFrame::BulkString(BulkString::new("hello".as_bytes())),
+        ]);
+        let cmd = Command::try_from(input)?;
+        assert!(matches!(cmd, Command::Get(_)));
+
+        let input = RespArray::new(vec![RespFrame::BulkString(BulkString::new(
+            "unknown".as_bytes(),
+        ))]);
+        let cmd = Command::try_from(input)?;
+        assert!(matches!(cmd, Command::Unrecognized(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unrecognized_command() -> Result<()> {
+        let backend = Backend::new();
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("sett".as_bytes())),
+            RespFrame::BulkString(BulkString::new("key".as_bytes())),
+            RespFrame::BulkString(BulkString::new("value".as_bytes())),
+        ]);
+        let cmd = Command::try_from(input)?;
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new(
+                "ERR unknown command 'sett', with args beginning with: 'key' 'value' "
+            )
+            .into()
+        );
+        assert_eq!(backend.get("key")?, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use super::{extract_args, validate_command, CommandError, CommandExecutor};
+use crate::{Backend, BulkString, RespArray, RespFrame};
+
+/// SPUBLISH shardchannel message, replies with the number of subscribers reached.
+#[derive(Debug)]
+pub struct SPublish {
+    channel: String,
+    message: Vec<u8>,
+}
+
+impl CommandExecutor for SPublish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        (backend.spublish(&self.channel, &self.message) as i64).into()
+    }
+}
+
+impl TryFrom<RespArray> for SPublish {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "spublish", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(channel)))),
+                Some(RespFrame::BulkString(BulkString(Some(message)))),
+            ) => Ok(SPublish {
+                channel: String::from_utf8(channel)?,
+                message,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid channel or message".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_spublish_try_from() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+
+        let result = SPublish::try_from(args(&["SPUBLISH", "news", "hello"]))?;
+        assert_eq!(result.channel, "news");
+        assert_eq!(result.message, b"hello");
+        assert!(SPublish::try_from(args(&["spublish", "news"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spublish_command() {
+        let backend = Backend::new();
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        backend.ssubscribe("news", 1, sender);
+
+        let cmd = SPublish {
+            channel: "news".to_string(),
+            message: b"hello".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), 1.into());
+    }
+}
@@ -0,0 +1,747 @@
+use super::{
+    extract_args, validate_command, validate_dynamic_command, CommandError, CommandExecutor,
+    RESP_OK,
+};
+use crate::{Backend, BigKeysReport, BulkString, RespArray, RespFrame, RespNull, SimpleError};
+
+#[derive(Debug)]
+pub struct Role;
+
+/// LASTSAVE
+#[derive(Debug)]
+pub struct LastSave;
+
+/// INFO [section]
+///
+/// Only the persistence, memory and latency sections are reported so far.
+#[derive(Debug)]
+pub struct Info {
+    section: Option<String>,
+}
+
+/// MEMORY USAGE key [SAMPLES count] | MEMORY STATS
+#[derive(Debug, PartialEq)]
+pub enum Memory {
+    Usage(String),
+    Stats,
+}
+
+/// BIGKEYS START | BIGKEYS STATUS
+///
+/// Start a background scan for the largest key of each type, or report its progress.
+#[derive(Debug, PartialEq)]
+pub enum BigKeys {
+    Start,
+    Status,
+}
+
+/// CONFIG GET pattern [pattern ...] | CONFIG SET parameter value [parameter value ...] |
+/// CONFIG REWRITE
+#[derive(Debug, PartialEq)]
+pub enum Config {
+    Get(Vec<String>),
+    Set(Vec<(String, String)>),
+    Rewrite,
+}
+
+/// SHUTDOWN [NOSAVE | SAVE]
+///
+/// The dataset only lives in memory, so NOSAVE is what shutting down does anyway and SAVE is
+/// refused rather than losing the data it was meant to keep.
+#[derive(Debug, PartialEq)]
+pub struct Shutdown {
+    save: bool,
+}
+
+impl CommandExecutor for Role {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        // replication is not supported, so the instance is always a master without replicas
+        RespArray::new(vec![
+            BulkString::new("master").into(),
+            0.into(),
+            RespArray::new(vec![]).into(),
+        ])
+        .into()
+    }
+}
+
+impl TryFrom<RespArray> for Role {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "role", 0)?;
+        Ok(Role)
+    }
+}
+
+impl CommandExecutor for LastSave {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.stats().last_save())
+    }
+}
+
+impl TryFrom<RespArray> for LastSave {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "lastsave", 0)?;
+        Ok(LastSave)
+    }
+}
+
+impl CommandExecutor for Info {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut info = String::new();
+        let all = matches!(
+            self.section.as_deref(),
+            None | Some("all" | "default" | "everything")
+        );
+        if all || self.section.as_deref() == Some("persistence") {
+            let warmup = backend.stats().warmup().unwrap_or_default();
+            info.push_str("# Persistence\r\n");
+            info.push_str("loading:0\r\n");
+            // snapshots and the append only file are not supported, nothing is ever in progress
+            info.push_str("rdb_bgsave_in_progress:0\r\n");
+            info.push_str(&format!(
+                "rdb_last_save_time:{}\r\n",
+                backend.stats().last_save()
+            ));
+            info.push_str("aof_enabled:0\r\n");
+            info.push_str("aof_rewrite_in_progress:0\r\n");
+            info.push_str(&format!("warmup_commands:{}\r\n", warmup.commands));
+            info.push_str(&format!("warmup_errors:{}\r\n", warmup.errors));
+            info.push_str(&format!(
+                "warmup_duration_ms:{}\r\n",
+                warmup.elapsed.as_millis()
+            ));
+        }
+        if all || self.section.as_deref() == Some("memory") {
+            let used = backend.used_memory();
+            info.push_str("# Memory\r\n");
+            info.push_str(&format!("used_memory:{}\r\n", used));
+            info.push_str(&format!("used_memory_dataset:{}\r\n", used));
+            info.push_str(&format!("maxmemory:{}\r\n", backend.maxmemory()));
+            info.push_str(&format!(
+                "maxmemory_policy:{}\r\n",
+                backend.maxmemory_policy()
+            ));
+        }
+        if all || self.section.as_deref() == Some("latency") {
+            let stats = backend.stats();
+            let delay = |p| {
+                stats
+                    .queue_delay()
+                    .percentile(p)
+                    .unwrap_or_default()
+                    .as_micros()
+            };
+            info.push_str("# Latency\r\n");
+            info.push_str(&format!("queue_delay_p50_usec:{}\r\n", delay(50.0)));
+            info.push_str(&format!("queue_delay_p99_usec:{}\r\n", delay(99.0)));
+            info.push_str(&format!(
+                "event_loop_lag_usec:{}\r\n",
+                stats.event_loop_lag().as_micros()
+            ));
+        }
+        BulkString::new(info).into()
+    }
+}
+
+impl TryFrom<RespArray> for Info {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "info", 0)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (None, None) => Ok(Info { section: None }),
+            (Some(RespFrame::BulkString(BulkString(Some(section)))), None) => Ok(Info {
+                section: Some(String::from_utf8(section)?.to_ascii_lowercase()),
+            }),
+            _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+        }
+    }
+}
+
+impl CommandExecutor for Memory {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            Memory::Usage(key) => match backend.memory_usage(&key) {
+                Some(size) => RespFrame::Integer(size as i64),
+                None => RespFrame::Null(RespNull),
+            },
+            Memory::Stats => {
+                let stats = backend.memory_stats();
+                let total = stats.total();
+                let mut reply = vec![
+                    BulkString::new("dataset.bytes").into(),
+                    RespFrame::Integer(total as i64),
+                    BulkString::new("keys.count").into(),
+                    RespFrame::Integer(stats.keys as i64),
+                    BulkString::new("keys.bytes-per-key").into(),
+                    RespFrame::Integer(total.checked_div(stats.keys).unwrap_or_default() as i64),
+                    BulkString::new("overhead.expires").into(),
+                    RespFrame::Integer(stats.expires as i64),
+                    BulkString::new("overhead.access-times").into(),
+                    RespFrame::Integer(stats.access_times as i64),
+                    // the keyspace is a single database
+                    BulkString::new("db.0").into(),
+                    RespArray::new(vec![
+                        BulkString::new("keys.count").into(),
+                        RespFrame::Integer(stats.keys as i64),
+                        BulkString::new("overhead.hashtable.expires").into(),
+                        RespFrame::Integer(stats.expires as i64),
+                    ])
+                    .into(),
+                ];
+                for (kind, bytes) in stats.types {
+                    reply.push(BulkString::new(format!("dataset.{}.bytes", kind.as_str())).into());
+                    reply.push(RespFrame::Integer(bytes as i64));
+                }
+                RespArray::new(reply).into()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Memory {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "memory", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(sub))))
+                if sub.eq_ignore_ascii_case(b"stats") =>
+            {
+                return match args.next() {
+                    None => Ok(Memory::Stats),
+                    Some(_) => Err(CommandError::InvalidArgument("syntax error".to_string())),
+                };
+            }
+            Some(RespFrame::BulkString(BulkString(Some(sub))))
+                if sub.eq_ignore_ascii_case(b"usage") => {}
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "unknown subcommand for 'memory'".to_string(),
+                ))
+            }
+        }
+
+        let key = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => String::from_utf8(key)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        // sizes are computed exactly, SAMPLES is accepted for compatibility only
+        match (args.next(), args.next(), args.next()) {
+            (None, None, None) => {}
+            (
+                Some(RespFrame::BulkString(BulkString(Some(option)))),
+                Some(RespFrame::BulkString(BulkString(Some(count)))),
+                None,
+            ) if option.eq_ignore_ascii_case(b"samples")
+                && std::str::from_utf8(&count)
+                    .map(|c| c.parse::<u64>().is_ok())
+                    .unwrap_or(false) => {}
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        }
+
+        Ok(Memory::Usage(key))
+    }
+}
+
+impl CommandExecutor for BigKeys {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            BigKeys::Start if backend.start_bigkeys_scan() => RESP_OK.clone(),
+            BigKeys::Start => SimpleError::new("ERR a BIGKEYS scan is already running").into(),
+            BigKeys::Status => bigkeys_report_frame(backend.bigkeys_report()),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BigKeys {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "bigkeys", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(sub))))
+                if sub.eq_ignore_ascii_case(b"start") =>
+            {
+                Ok(BigKeys::Start)
+            }
+            Some(RespFrame::BulkString(BulkString(Some(sub))))
+                if sub.eq_ignore_ascii_case(b"status") =>
+            {
+                Ok(BigKeys::Status)
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "unknown subcommand for 'bigkeys'".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for Config {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            Config::Get(patterns) => RespArray::new(
+                backend
+                    .config_get(&patterns)
+                    .into_iter()
+                    .flat_map(|(name, value)| {
+                        [BulkString::new(name).into(), BulkString::new(value).into()]
+                    })
+                    .collect(),
+            )
+            .into(),
+            Config::Set(values) => match backend.config_set(&values) {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => SimpleError::new(e.to_string()).into(),
+            },
+            Config::Rewrite => match backend.config_rewrite() {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => SimpleError::new(e.to_string()).into(),
+            },
+        }
+    }
+}
+
+impl CommandExecutor for Shutdown {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.save {
+            return SimpleError::new(
+                "ERR Errors trying to SHUTDOWN. The server has no persistence to save to.",
+            )
+            .into();
+        }
+        backend.shutdown();
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for Shutdown {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "shutdown", 0)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (None, None) => Ok(Shutdown { save: false }),
+            (Some(RespFrame::BulkString(BulkString(Some(option)))), None)
+                if option.eq_ignore_ascii_case(b"nosave") =>
+            {
+                Ok(Shutdown { save: false })
+            }
+            (Some(RespFrame::BulkString(BulkString(Some(option)))), None)
+                if option.eq_ignore_ascii_case(b"save") =>
+            {
+                Ok(Shutdown { save: true })
+            }
+            _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Config {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "config", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let sub = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(sub)))) => sub.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+        let args = args
+            .map(|arg| match arg {
+                RespFrame::BulkString(BulkString(Some(arg))) => Ok(String::from_utf8(arg)?),
+                _ => Err(CommandError::InvalidArgument(
+                    "Invalid argument".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+
+        match sub.as_slice() {
+            b"get" if !args.is_empty() => Ok(Config::Get(args)),
+            b"get" => Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'config|get' command".to_string(),
+            )),
+            b"set" if !args.is_empty() && args.len() % 2 == 0 => Ok(Config::Set(
+                args.chunks(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect(),
+            )),
+            b"set" => Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'config|set' command".to_string(),
+            )),
+            b"rewrite" if args.is_empty() => Ok(Config::Rewrite),
+            b"rewrite" => Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'config|rewrite' command".to_string(),
+            )),
+            _ => Err(CommandError::InvalidArgument(
+                "unknown subcommand for 'config'".to_string(),
+            )),
+        }
+    }
+}
+
+// field-value pairs, the per type stats are nested the same way
+fn bigkeys_report_frame(report: BigKeysReport) -> RespFrame {
+    let types = report
+        .types
+        .into_iter()
+        .map(|t| {
+            RespArray::new(vec![
+                BulkString::new("type").into(),
+                BulkString::new(t.kind.as_str()).into(),
+                BulkString::new("keys").into(),
+                (t.keys as i64).into(),
+                BulkString::new("total-elements").into(),
+                (t.total_len as i64).into(),
+                BulkString::new("total-bytes").into(),
+                (t.total_size as i64).into(),
+                BulkString::new("biggest").into(),
+                BulkString::new(t.biggest.key).into(),
+                BulkString::new("biggest-elements").into(),
+                (t.biggest.len as i64).into(),
+                BulkString::new("biggest-bytes").into(),
+                (t.biggest.size as i64).into(),
+            ])
+            .into()
+        })
+        .collect();
+    RespArray::new(vec![
+        BulkString::new("running").into(),
+        (report.running as i64).into(),
+        BulkString::new("scanned").into(),
+        (report.scanned as i64).into(),
+        BulkString::new("types").into(),
+        RespArray::new(types).into(),
+    ])
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_role_try_from() -> Result<()> {
+        let input = RespArray::new(vec![RespFrame::BulkString(BulkString::new(
+            "role".as_bytes(),
+        ))]);
+        Role::try_from(input)?;
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("role".as_bytes())),
+            RespFrame::BulkString(BulkString::new("extra".as_bytes())),
+        ]);
+        assert!(Role::try_from(input).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_role_command() {
+        let backend = Backend::new();
+        let result = Role.execute(&backend);
+        let expected = RespArray::new(vec![
+            BulkString::new("master").into(),
+            0.into(),
+            RespArray::new(vec![]).into(),
+        ]);
+        assert_eq!(result, expected.into());
+    }
+
+    #[test]
+    fn test_memory_usage_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("memory".as_bytes())),
+            RespFrame::BulkString(BulkString::new("usage".as_bytes())),
+            RespFrame::BulkString(BulkString::new("key".as_bytes())),
+            RespFrame::BulkString(BulkString::new("samples".as_bytes())),
+            RespFrame::BulkString(BulkString::new("5".as_bytes())),
+        ]);
+        let result = Memory::try_from(input)?;
+        assert_eq!(result, Memory::Usage("key".to_string()));
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("memory".as_bytes())),
+            RespFrame::BulkString(BulkString::new("doctor".as_bytes())),
+        ]);
+        assert!(Memory::try_from(input).is_err());
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("memory".as_bytes())),
+            RespFrame::BulkString(BulkString::new("STATS".as_bytes())),
+        ]);
+        assert_eq!(Memory::try_from(input)?, Memory::Stats);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_usage_command() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), vec![b'x'; 1000]);
+
+        let cmd = Memory::Usage("key".to_string());
+        match cmd.execute(&backend) {
+            RespFrame::Integer(size) => assert!((1000..1100).contains(&size)),
+            frame => panic!("unexpected response {:?}", frame),
+        }
+
+        let cmd = Memory::Usage("missing".to_string());
+        assert_eq!(cmd.execute(&backend), RespNull.into());
+
+        let RespFrame::Array(RespArray(Some(stats))) = Memory::Stats.execute(&backend) else {
+            panic!("expected an array");
+        };
+        assert_eq!(stats[0], BulkString::new("dataset.bytes").into());
+        assert_eq!(stats[3], RespFrame::Integer(1));
+        assert!(stats.contains(&BulkString::new("dataset.string.bytes").into()));
+        assert_eq!(stats[8], BulkString::new("overhead.access-times").into());
+        assert_eq!(stats[10], BulkString::new("db.0").into());
+    }
+
+    #[test]
+    fn test_bigkeys_try_from() -> Result<()> {
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("bigkeys".as_bytes())),
+            RespFrame::BulkString(BulkString::new("START".as_bytes())),
+        ]);
+        assert_eq!(BigKeys::try_from(input)?, BigKeys::Start);
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("bigkeys".as_bytes())),
+            RespFrame::BulkString(BulkString::new("stop".as_bytes())),
+        ]);
+        assert!(BigKeys::try_from(input).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bigkeys_command() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        assert_eq!(BigKeys::Start.execute(&backend), RESP_OK.clone());
+        while backend.bigkeys_report().running {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let size = backend.bigkeys(1)[0].size as i64;
+        let expected = RespArray::new(vec![
+            BulkString::new("running").into(),
+            0.into(),
+            BulkString::new("scanned").into(),
+            1.into(),
+            BulkString::new("types").into(),
+            RespArray::new(vec![RespArray::new(vec![
+                BulkString::new("type").into(),
+                BulkString::new("string").into(),
+                BulkString::new("keys").into(),
+                1.into(),
+                BulkString::new("total-elements").into(),
+                5.into(),
+                BulkString::new("total-bytes").into(),
+                size.into(),
+                BulkString::new("biggest").into(),
+                BulkString::new("key").into(),
+                BulkString::new("biggest-elements").into(),
+                5.into(),
+                BulkString::new("biggest-bytes").into(),
+                size.into(),
+            ])
+            .into()])
+            .into(),
+        ]);
+        assert_eq!(BigKeys::Status.execute(&backend), expected.into());
+    }
+
+    #[test]
+    fn test_config_try_from() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+        assert_eq!(
+            Config::try_from(args(&["config", "GET", "max*", "ttl-jitter"]))?,
+            Config::Get(vec!["max*".to_string(), "ttl-jitter".to_string()])
+        );
+        assert_eq!(
+            Config::try_from(args(&["config", "set", "ttl-jitter", "5"]))?,
+            Config::Set(vec![("ttl-jitter".to_string(), "5".to_string())])
+        );
+        assert!(Config::try_from(args(&["config", "set", "ttl-jitter"])).is_err());
+        assert_eq!(
+            Config::try_from(args(&["config", "rewrite"]))?,
+            Config::Rewrite
+        );
+        assert!(Config::try_from(args(&["config", "rewrite", "now"])).is_err());
+        assert!(Config::try_from(args(&["config", "get"])).is_err());
+        assert!(Config::try_from(args(&["config", "set"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_command() {
+        let backend = Backend::new();
+        let set = Config::Set(vec![("maxmemory".to_string(), "2kb".to_string())]);
+        assert_eq!(set.execute(&backend), RESP_OK.clone());
+        let get = Config::Get(vec!["maxmemory".to_string()]);
+        let expected = RespArray::new(vec![
+            BulkString::new("maxmemory").into(),
+            BulkString::new("2048").into(),
+        ]);
+        assert_eq!(get.execute(&backend), expected.into());
+
+        let set = Config::Set(vec![("unknown".to_string(), "1".to_string())]);
+        assert_eq!(
+            set.execute(&backend),
+            SimpleError::new(
+                "ERR Unknown option or number of arguments for CONFIG SET - 'unknown'"
+            )
+            .into()
+        );
+        assert_eq!(
+            Config::Rewrite.execute(&backend),
+            SimpleError::new("ERR The server is running without a config file").into()
+        );
+    }
+
+    #[test]
+    fn test_info_try_from() -> Result<()> {
+        let input = RespArray::new(vec![RespFrame::BulkString(BulkString::new(
+            "info".as_bytes(),
+        ))]);
+        assert_eq!(Info::try_from(input)?.section, None);
+
+        let input = RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("info".as_bytes())),
+            RespFrame::BulkString(BulkString::new("Latency".as_bytes())),
+        ]);
+        assert_eq!(Info::try_from(input)?.section, Some("latency".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_info_command() {
+        let backend = Backend::new();
+        backend
+            .stats()
+            .record_queue_delay(std::time::Duration::from_micros(10));
+
+        let info = Info {
+            section: Some("latency".to_string()),
+        };
+        let expected = "# Latency\r\nqueue_delay_p50_usec:15\r\nqueue_delay_p99_usec:15\r\nevent_loop_lag_usec:0\r\n";
+        assert_eq!(info.execute(&backend), BulkString::new(expected).into());
+
+        backend.stats().set_warmup(crate::WarmupStats {
+            commands: 3,
+            errors: 1,
+            elapsed: std::time::Duration::from_millis(2),
+        });
+        let info = Info {
+            section: Some("persistence".to_string()),
+        };
+        let expected = format!(
+            "# Persistence\r\nloading:0\r\nrdb_bgsave_in_progress:0\r\nrdb_last_save_time:{}\r\naof_enabled:0\r\naof_rewrite_in_progress:0\r\nwarmup_commands:3\r\nwarmup_errors:1\r\nwarmup_duration_ms:2\r\n",
+            backend.stats().last_save()
+        );
+        assert_eq!(info.execute(&backend), BulkString::new(expected).into());
+
+        let info = Info { section: None };
+        match info.execute(&backend) {
+            RespFrame::BulkString(BulkString(Some(info))) => {
+                assert!(info.starts_with(b"# Persistence\r\n"));
+            }
+            frame => panic!("unexpected response {:?}", frame),
+        }
+
+        // the memory is measured once a second, on a new backend it is measured now
+        let backend = Backend::new();
+        backend.set("key".to_string(), "value");
+        let info = Info {
+            section: Some("memory".to_string()),
+        };
+        let expected = format!(
+            "# Memory\r\nused_memory:{0}\r\nused_memory_dataset:{0}\r\nmaxmemory:0\r\nmaxmemory_policy:noeviction\r\n",
+            backend.used_memory()
+        );
+        assert_eq!(info.execute(&backend), BulkString::new(expected).into());
+
+        let info = Info {
+            section: Some("keyspace".to_string()),
+        };
+        assert_eq!(info.execute(&backend), BulkString::new("").into());
+    }
+
+    #[test]
+    fn test_lastsave_command() -> Result<()> {
+        let backend = Backend::new();
+        let input = RespArray::new(vec![BulkString::new("lastsave").into()]);
+        let ret = LastSave::try_from(input)?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(backend.stats().last_save()));
+        assert!(backend.stats().last_save() > 0);
+
+        let input = RespArray::new(vec![
+            BulkString::new("lastsave").into(),
+            BulkString::new("x").into(),
+        ]);
+        assert!(LastSave::try_from(input).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shutdown_try_from() -> Result<()> {
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+        assert_eq!(
+            Shutdown::try_from(args(&["shutdown"]))?,
+            Shutdown { save: false }
+        );
+        assert_eq!(
+            Shutdown::try_from(args(&["shutdown", "NOSAVE"]))?,
+            Shutdown { save: false }
+        );
+        assert_eq!(
+            Shutdown::try_from(args(&["shutdown", "save"]))?,
+            Shutdown { save: true }
+        );
+        assert!(Shutdown::try_from(args(&["shutdown", "now"])).is_err());
+        assert!(Shutdown::try_from(args(&["shutdown", "save", "nosave"])).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_command() {
+        let backend = Backend::new();
+        let ret = Shutdown { save: true }.execute(&backend);
+        assert!(matches!(ret, RespFrame::Error(_)));
+        assert_eq!(Shutdown { save: false }.execute(&backend), RESP_OK.clone());
+        backend.shutdown_requested().await;
+    }
+}
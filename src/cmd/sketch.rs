@@ -0,0 +1,616 @@
+use super::{
+    extract_args, parse_number, validate_command, validate_dynamic_command, CommandError,
+    CommandExecutor, RESP_OK,
+};
+use crate::{
+    Backend, BulkString, CountMinSketch, RespArray, RespFrame, RespNull, SimpleError, TopK,
+};
+
+/// CMS.INITBYDIM key width depth
+#[derive(Debug)]
+pub struct CmsInitByDim {
+    key: String,
+    width: usize,
+    depth: usize,
+}
+
+/// CMS.INITBYPROB key error probability
+#[derive(Debug)]
+pub struct CmsInitByProb {
+    key: String,
+    error: f64,
+    probability: f64,
+}
+
+#[derive(Debug)]
+pub struct CmsIncrBy {
+    key: String,
+    items: Vec<(String, u64)>,
+}
+
+#[derive(Debug)]
+pub struct CmsQuery {
+    key: String,
+    items: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CmsInfo {
+    key: String,
+}
+
+/// TOPK.RESERVE key topk [width depth decay]
+#[derive(Debug)]
+pub struct TopKReserve {
+    key: String,
+    k: usize,
+    width: usize,
+    depth: usize,
+    decay: f64,
+}
+
+#[derive(Debug)]
+pub struct TopKAdd {
+    key: String,
+    items: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct TopKQuery {
+    key: String,
+    items: Vec<String>,
+}
+
+/// TOPK.LIST key [WITHCOUNT]
+#[derive(Debug)]
+pub struct TopKList {
+    key: String,
+    with_count: bool,
+}
+
+#[derive(Debug)]
+pub struct TopKInfo {
+    key: String,
+}
+
+impl CommandExecutor for CmsInitByDim {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        cms_init(
+            backend,
+            self.key,
+            CountMinSketch::new(self.width, self.depth),
+        )
+    }
+}
+
+impl CommandExecutor for CmsInitByProb {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let sketch = CountMinSketch::with_error(self.error, self.probability);
+        cms_init(backend, self.key, sketch)
+    }
+}
+
+impl CommandExecutor for CmsIncrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.cms_incrby(&self.key, &self.items) {
+            Ok(Some(counts)) => integers(counts),
+            Ok(None) => SimpleError::new("ERR CMS: key does not exist").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for CmsQuery {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.cms_query(&self.key, &self.items) {
+            Ok(Some(counts)) => integers(counts),
+            Ok(None) => SimpleError::new("ERR CMS: key does not exist").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for CmsInfo {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.cms_info(&self.key) {
+            Ok(Some((width, depth, count))) => RespArray::new(vec![
+                BulkString::new("width").into(),
+                RespFrame::Integer(width as i64),
+                BulkString::new("depth").into(),
+                RespFrame::Integer(depth as i64),
+                BulkString::new("count").into(),
+                RespFrame::Integer(count as i64),
+            ])
+            .into(),
+            Ok(None) => SimpleError::new("ERR CMS: key does not exist").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for TopKReserve {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let topk = TopK::new(self.k, self.width, self.depth, self.decay);
+        if backend.topk_reserve(self.key, topk) {
+            RESP_OK.clone()
+        } else {
+            SimpleError::new("ERR TopK: key already exists").into()
+        }
+    }
+}
+
+impl CommandExecutor for TopKAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.topk_add(&self.key, &self.items) {
+            Ok(Some(expelled)) => RespArray::new(
+                expelled
+                    .into_iter()
+                    .map(|item| match item {
+                        Some(item) => BulkString::new(item).into(),
+                        None => RespFrame::Null(RespNull),
+                    })
+                    .collect(),
+            )
+            .into(),
+            Ok(None) => SimpleError::new("ERR TopK: key does not exist").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for TopKQuery {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.topk_query(&self.key, &self.items) {
+            Ok(Some(found)) => integers(found.into_iter().map(u64::from).collect()),
+            Ok(None) => SimpleError::new("ERR TopK: key does not exist").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for TopKList {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let list = match backend.topk_list(&self.key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return SimpleError::new("ERR TopK: key does not exist").into(),
+            Err(e) => return SimpleError::new(e.to_string()).into(),
+        };
+        let mut ret = Vec::with_capacity(list.len() * 2);
+        for (item, count) in list {
+            ret.push(BulkString::new(item).into());
+            if self.with_count {
+                ret.push(RespFrame::Integer(count as i64));
+            }
+        }
+        RespArray::new(ret).into()
+    }
+}
+
+impl CommandExecutor for TopKInfo {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.topk_info(&self.key) {
+            Ok(Some((k, width, depth, decay))) => RespArray::new(vec![
+                BulkString::new("k").into(),
+                RespFrame::Integer(k as i64),
+                BulkString::new("width").into(),
+                RespFrame::Integer(width as i64),
+                BulkString::new("depth").into(),
+                RespFrame::Integer(depth as i64),
+                BulkString::new("decay").into(),
+                BulkString::new(decay.to_string()).into(),
+            ])
+            .into(),
+            Ok(None) => SimpleError::new("ERR TopK: key does not exist").into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CmsInitByDim {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "cms.initbydim", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(width)))),
+                Some(RespFrame::BulkString(BulkString(Some(depth)))),
+            ) => Ok(CmsInitByDim {
+                key: String::from_utf8(key)?,
+                width: parse_number(width, |w: &usize| *w > 0).ok_or_else(|| {
+                    CommandError::InvalidArgument("CMS: invalid width".to_string())
+                })?,
+                depth: parse_number(depth, |d: &usize| *d > 0).ok_or_else(|| {
+                    CommandError::InvalidArgument("CMS: invalid depth".to_string())
+                })?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CmsInitByProb {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "cms.initbyprob", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(error)))),
+                Some(RespFrame::BulkString(BulkString(Some(probability)))),
+            ) => Ok(CmsInitByProb {
+                key: String::from_utf8(key)?,
+                error: parse_number(error, |e: &f64| *e > 0.0 && *e < 1.0).ok_or_else(|| {
+                    CommandError::InvalidArgument("CMS: invalid overestimation value".to_string())
+                })?,
+                probability: parse_number(probability, |p: &f64| *p > 0.0 && *p < 1.0).ok_or_else(
+                    || CommandError::InvalidArgument("CMS: invalid prob value".to_string()),
+                )?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CmsIncrBy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "cms.incrby", 3)?;
+
+        let (key, args) = extract_key_items(value)?;
+        if args.len() % 2 != 0 {
+            return Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'cms.incrby' command".to_string(),
+            ));
+        }
+        let mut items = Vec::with_capacity(args.len() / 2);
+        let mut args = args.into_iter();
+        while let (Some(item), Some(increment)) = (args.next(), args.next()) {
+            let increment = increment.parse::<u64>().map_err(|_| {
+                CommandError::InvalidArgument("CMS: Cannot parse number".to_string())
+            })?;
+            items.push((item, increment));
+        }
+        Ok(CmsIncrBy { key, items })
+    }
+}
+
+impl TryFrom<RespArray> for CmsQuery {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "cms.query", 2)?;
+        let (key, items) = extract_key_items(value)?;
+        Ok(CmsQuery { key, items })
+    }
+}
+
+impl TryFrom<RespArray> for CmsInfo {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "cms.info", 1)?;
+        let (key, _) = extract_key_items(value)?;
+        Ok(CmsInfo { key })
+    }
+}
+
+impl TryFrom<RespArray> for TopKReserve {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "topk.reserve", 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, k) = match (args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(k)))),
+            ) => (String::from_utf8(key)?, k),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let k = parse_number(k, |k: &usize| *k > 0)
+            .ok_or_else(|| CommandError::InvalidArgument("TopK: invalid k".to_string()))?;
+
+        let mut cmd = TopKReserve {
+            key,
+            k,
+            width: TopK::DEFAULT_WIDTH,
+            depth: TopK::DEFAULT_DEPTH,
+            decay: TopK::DEFAULT_DECAY,
+        };
+        match (args.next(), args.next(), args.next(), args.next()) {
+            (None, None, None, None) => {}
+            (
+                Some(RespFrame::BulkString(BulkString(Some(width)))),
+                Some(RespFrame::BulkString(BulkString(Some(depth)))),
+                Some(RespFrame::BulkString(BulkString(Some(decay)))),
+                None,
+            ) => {
+                cmd.width = parse_number(width, |w: &usize| *w > 0).ok_or_else(|| {
+                    CommandError::InvalidArgument("TopK: invalid width".to_string())
+                })?;
+                cmd.depth = parse_number(depth, |d: &usize| *d > 0).ok_or_else(|| {
+                    CommandError::InvalidArgument("TopK: invalid depth".to_string())
+                })?;
+                cmd.decay =
+                    parse_number(decay, |d: &f64| *d > 0.0 && *d <= 1.0).ok_or_else(|| {
+                        CommandError::InvalidArgument("TopK: invalid decay value".to_string())
+                    })?;
+            }
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        }
+
+        Ok(cmd)
+    }
+}
+
+impl TryFrom<RespArray> for TopKAdd {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "topk.add", 2)?;
+        let (key, items) = extract_key_items(value)?;
+        Ok(TopKAdd { key, items })
+    }
+}
+
+impl TryFrom<RespArray> for TopKQuery {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "topk.query", 2)?;
+        let (key, items) = extract_key_items(value)?;
+        Ok(TopKQuery { key, items })
+    }
+}
+
+impl TryFrom<RespArray> for TopKList {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "topk.list", 1)?;
+        let (key, options) = extract_key_items(value)?;
+        let with_count = match options.as_slice() {
+            [] => false,
+            [option] if option.eq_ignore_ascii_case("withcount") => true,
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        Ok(TopKList { key, with_count })
+    }
+}
+
+impl TryFrom<RespArray> for TopKInfo {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "topk.info", 1)?;
+        let (key, _) = extract_key_items(value)?;
+        Ok(TopKInfo { key })
+    }
+}
+
+fn cms_init(backend: &Backend, key: String, sketch: CountMinSketch) -> RespFrame {
+    if backend.cms_init(key, sketch) {
+        RESP_OK.clone()
+    } else {
+        SimpleError::new("ERR CMS: key already exists").into()
+    }
+}
+
+fn integers(values: Vec<u64>) -> RespFrame {
+    RespArray::new(
+        values
+            .into_iter()
+            .map(|v| RespFrame::Integer(v as i64))
+            .collect(),
+    )
+    .into()
+}
+
+// extract `key [item ...]`
+fn extract_key_items(value: RespArray) -> Result<(String, Vec<String>), CommandError> {
+    let mut items = Vec::new();
+    for arg in extract_args(value, 1)? {
+        match arg {
+            RespFrame::BulkString(BulkString(Some(item))) => items.push(String::from_utf8(item)?),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid argument".to_string(),
+                ))
+            }
+        }
+    }
+    let key = items.remove(0);
+    Ok((key, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn parse_args(args: &[&str]) -> RespArray {
+        RespArray::new(
+            args.iter()
+                .map(|a| BulkString::new(a.as_bytes()).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+    }
+
+    #[test]
+    fn test_cms_try_from() -> Result<()> {
+        let result = CmsInitByDim::try_from(parse_args(&["cms.initbydim", "key", "100", "5"]))?;
+        assert_eq!((result.width, result.depth), (100, 5));
+
+        let result =
+            CmsInitByProb::try_from(parse_args(&["CMS.INITBYPROB", "key", "0.001", "0.01"]))?;
+        assert_eq!((result.error, result.probability), (0.001, 0.01));
+
+        let result = CmsIncrBy::try_from(parse_args(&["cms.incrby", "key", "a", "1", "b", "2"]))?;
+        assert_eq!(
+            result.items,
+            vec![("a".to_string(), 1), ("b".to_string(), 2)]
+        );
+
+        assert!(CmsIncrBy::try_from(parse_args(&["cms.incrby", "key", "a", "1", "b"])).is_err());
+        assert!(CmsIncrBy::try_from(parse_args(&["cms.incrby", "key", "a", "x"])).is_err());
+        assert!(CmsInitByDim::try_from(parse_args(&["cms.initbydim", "key", "0", "5"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cms_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = CmsIncrBy {
+            key: "cms".to_string(),
+            items: vec![("a".to_string(), 1)],
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR CMS: key does not exist").into()
+        );
+
+        let cmd = CmsInitByDim {
+            key: "cms".to_string(),
+            width: 100,
+            depth: 5,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd = CmsInitByProb {
+            key: "cms".to_string(),
+            error: 0.01,
+            probability: 0.01,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR CMS: key already exists").into()
+        );
+
+        let cmd = CmsIncrBy {
+            key: "cms".to_string(),
+            items: vec![
+                ("a".to_string(), 3),
+                ("b".to_string(), 1),
+                ("a".to_string(), 2),
+            ],
+        };
+        assert_eq!(cmd.execute(&backend), integers(vec![3, 1, 5]));
+
+        let cmd = CmsQuery {
+            key: "cms".to_string(),
+            items: vec!["a".to_string(), "c".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), integers(vec![5, 0]));
+
+        let cmd = CmsInfo {
+            key: "cms".to_string(),
+        };
+        let expected = RespArray::new(vec![
+            BulkString::new("width").into(),
+            RespFrame::Integer(100),
+            BulkString::new("depth").into(),
+            RespFrame::Integer(5),
+            BulkString::new("count").into(),
+            RespFrame::Integer(6),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_topk_try_from() -> Result<()> {
+        let result = TopKReserve::try_from(parse_args(&["topk.reserve", "key", "10"]))?;
+        assert_eq!(result.k, 10);
+        assert_eq!(
+            (result.width, result.depth, result.decay),
+            (
+                TopK::DEFAULT_WIDTH,
+                TopK::DEFAULT_DEPTH,
+                TopK::DEFAULT_DECAY
+            )
+        );
+
+        let result =
+            TopKReserve::try_from(parse_args(&["TOPK.RESERVE", "key", "10", "50", "4", "0.8"]))?;
+        assert_eq!((result.width, result.depth, result.decay), (50, 4, 0.8));
+
+        assert!(TopKReserve::try_from(parse_args(&["topk.reserve", "key", "10", "50"])).is_err());
+
+        let result = TopKList::try_from(parse_args(&["topk.list", "key", "WITHCOUNT"]))?;
+        assert!(result.with_count);
+        assert!(TopKList::try_from(parse_args(&["topk.list", "key", "foo"])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_topk_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = TopKReserve {
+            key: "topk".to_string(),
+            k: 2,
+            width: 50,
+            depth: 4,
+            decay: 0.9,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        let cmd = TopKAdd {
+            key: "topk".to_string(),
+            items: ["a", "b", "a", "c", "c", "c"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        };
+        let expected = RespArray::new(vec![
+            RespFrame::Null(RespNull),
+            RespFrame::Null(RespNull),
+            RespFrame::Null(RespNull),
+            RespFrame::Null(RespNull),
+            BulkString::new("b").into(),
+            RespFrame::Null(RespNull),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = TopKQuery {
+            key: "topk".to_string(),
+            items: vec!["a".to_string(), "b".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), integers(vec![1, 0]));
+
+        let cmd = TopKList {
+            key: "topk".to_string(),
+            with_count: true,
+        };
+        let expected = RespArray::new(vec![
+            BulkString::new("c").into(),
+            RespFrame::Integer(3),
+            BulkString::new("a").into(),
+            RespFrame::Integer(2),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = TopKAdd {
+            key: "missing".to_string(),
+            items: vec!["a".to_string()],
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR TopK: key does not exist").into()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,466 @@
+use super::{
+    extract_args, parse_number, validate_command, validate_dynamic_command, CommandError,
+    CommandExecutor, RESP_OK,
+};
+use crate::{
+    now_ms, Aggregation, Backend, BulkString, RespArray, RespFrame, SimpleError, TimeSeriesError,
+};
+
+/// TS.CREATE key [RETENTION retention]
+#[derive(Debug)]
+pub struct TsCreate {
+    key: String,
+    retention: i64,
+}
+
+/// TS.ADD key timestamp value [RETENTION retention]
+#[derive(Debug)]
+pub struct TsAdd {
+    key: String,
+    // None for `*`, the current time
+    timestamp: Option<i64>,
+    value: f64,
+    retention: i64,
+}
+
+#[derive(Debug)]
+pub struct TsGet {
+    key: String,
+}
+
+/// TS.RANGE key from to [AGGREGATION aggregator bucket]
+#[derive(Debug)]
+pub struct TsRange {
+    key: String,
+    from: i64,
+    to: i64,
+    aggregation: Option<(Aggregation, i64)>,
+}
+
+/// TS.CREATERULE source destination AGGREGATION aggregator bucket
+#[derive(Debug)]
+pub struct TsCreateRule {
+    src: String,
+    dest: String,
+    aggregation: Aggregation,
+    bucket: i64,
+}
+
+impl CommandExecutor for TsCreate {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.ts_create(self.key, self.retention) {
+            RESP_OK.clone()
+        } else {
+            SimpleError::new(TimeSeriesError::KeyExists.to_string()).into()
+        }
+    }
+}
+
+impl CommandExecutor for TsAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let timestamp = self.timestamp.unwrap_or_else(now_ms);
+        match backend.ts_add(self.key, timestamp, self.value, self.retention) {
+            Ok(timestamp) => RespFrame::Integer(timestamp),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for TsGet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.ts_get(&self.key) {
+            Ok(Some(Some(sample))) => sample_frame(sample),
+            Ok(Some(None)) => RespArray::new(vec![]).into(),
+            Ok(None) => SimpleError::new(TimeSeriesError::KeyMissing.to_string()).into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for TsRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.ts_range(&self.key, self.from, self.to, self.aggregation) {
+            Ok(Some(samples)) => {
+                RespArray::new(samples.into_iter().map(sample_frame).collect()).into()
+            }
+            Ok(None) => SimpleError::new(TimeSeriesError::KeyMissing.to_string()).into(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl CommandExecutor for TsCreateRule {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.ts_createrule(&self.src, &self.dest, self.aggregation, self.bucket) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for TsCreate {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "ts.create", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => String::from_utf8(key)?,
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let retention = parse_retention(args)?;
+
+        Ok(TsCreate { key, retention })
+    }
+}
+
+impl TryFrom<RespArray> for TsAdd {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "ts.add", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, timestamp, value) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(timestamp)))),
+                Some(RespFrame::BulkString(BulkString(Some(value)))),
+            ) => (String::from_utf8(key)?, timestamp, value),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let timestamp = match timestamp.as_slice() {
+            b"*" => None,
+            _ => Some(parse_number(timestamp, |ts: &i64| *ts >= 0).ok_or_else(|| {
+                CommandError::InvalidArgument("TSDB: invalid timestamp".to_string())
+            })?),
+        };
+        let value = parse_number(value, |v: &f64| v.is_finite())
+            .ok_or_else(|| CommandError::InvalidArgument("TSDB: invalid value".to_string()))?;
+        let retention = parse_retention(args)?;
+
+        Ok(TsAdd {
+            key,
+            timestamp,
+            value,
+            retention,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for TsGet {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "ts.get", 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(BulkString(Some(key)))) => Ok(TsGet {
+                key: String::from_utf8(key)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for TsRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_dynamic_command(&value, "ts.range", 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, from, to) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(key)))),
+                Some(RespFrame::BulkString(BulkString(Some(from)))),
+                Some(RespFrame::BulkString(BulkString(Some(to)))),
+            ) => (String::from_utf8(key)?, from, to),
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+        let from = match from.as_slice() {
+            b"-" => 0,
+            _ => parse_number(from, |_: &i64| true).ok_or_else(|| {
+                CommandError::InvalidArgument("TSDB: invalid fromTimestamp".to_string())
+            })?,
+        };
+        let to = match to.as_slice() {
+            b"+" => i64::MAX,
+            _ => parse_number(to, |_: &i64| true).ok_or_else(|| {
+                CommandError::InvalidArgument("TSDB: invalid toTimestamp".to_string())
+            })?,
+        };
+        let aggregation = match args.next() {
+            None => None,
+            Some(RespFrame::BulkString(BulkString(Some(option))))
+                if option.eq_ignore_ascii_case(b"aggregation") =>
+            {
+                Some(parse_aggregation(&mut args)?)
+            }
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        if args.next().is_some() {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+
+        Ok(TsRange {
+            key,
+            from,
+            to,
+            aggregation,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for TsCreateRule {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, "ts.createrule", 5)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (src, dest) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(BulkString(Some(src)))),
+                Some(RespFrame::BulkString(BulkString(Some(dest)))),
+                Some(RespFrame::BulkString(BulkString(Some(option)))),
+            ) if option.eq_ignore_ascii_case(b"aggregation") => {
+                (String::from_utf8(src)?, String::from_utf8(dest)?)
+            }
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        let (aggregation, bucket) = parse_aggregation(&mut args)?;
+
+        Ok(TsCreateRule {
+            src,
+            dest,
+            aggregation,
+            bucket,
+        })
+    }
+}
+
+fn sample_frame((timestamp, value): (i64, f64)) -> RespFrame {
+    RespArray::new(vec![
+        RespFrame::Integer(timestamp),
+        BulkString::new(value.to_string()).into(),
+    ])
+    .into()
+}
+
+// parse the optional `RETENTION retention` tail of a command, 0 keeps samples forever
+fn parse_retention(mut args: impl Iterator<Item = RespFrame>) -> Result<i64, CommandError> {
+    match (args.next(), args.next(), args.next()) {
+        (None, None, None) => Ok(0),
+        (
+            Some(RespFrame::BulkString(BulkString(Some(option)))),
+            Some(RespFrame::BulkString(BulkString(Some(retention)))),
+            None,
+        ) if option.eq_ignore_ascii_case(b"retention") => parse_number(retention, |r: &i64| {
+            *r >= 0
+        })
+        .ok_or_else(|| CommandError::InvalidArgument("TSDB: invalid retention value".to_string())),
+        _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+    }
+}
+
+// parse `aggregator bucket` following the AGGREGATION option
+fn parse_aggregation(
+    args: &mut impl Iterator<Item = RespFrame>,
+) -> Result<(Aggregation, i64), CommandError> {
+    match (args.next(), args.next()) {
+        (
+            Some(RespFrame::BulkString(BulkString(Some(aggregation)))),
+            Some(RespFrame::BulkString(BulkString(Some(bucket)))),
+        ) => {
+            let aggregation =
+                Aggregation::parse(&String::from_utf8(aggregation)?).ok_or_else(|| {
+                    CommandError::InvalidArgument("TSDB: Unknown aggregation type".to_string())
+                })?;
+            let bucket = parse_number(bucket, |b: &i64| *b > 0).ok_or_else(|| {
+                CommandError::InvalidArgument(
+                    "TSDB: bucketDuration must be greater than zero".to_string(),
+                )
+            })?;
+            Ok((aggregation, bucket))
+        }
+        _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn parse_args(args: &[&str]) -> RespArray {
+        RespArray::new(
+            args.iter()
+                .map(|a| BulkString::new(a.as_bytes()).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+    }
+
+    fn sample(timestamp: i64, value: &str) -> RespFrame {
+        RespArray::new(vec![
+            RespFrame::Integer(timestamp),
+            BulkString::new(value).into(),
+        ])
+        .into()
+    }
+
+    #[test]
+    fn test_ts_try_from() -> Result<()> {
+        let result = TsCreate::try_from(parse_args(&["ts.create", "ts", "RETENTION", "1000"]))?;
+        assert_eq!((result.key.as_str(), result.retention), ("ts", 1000));
+
+        let result = TsAdd::try_from(parse_args(&["TS.ADD", "ts", "*", "1.5"]))?;
+        assert_eq!((result.timestamp, result.value), (None, 1.5));
+        let result = TsAdd::try_from(parse_args(&["ts.add", "ts", "10", "2"]))?;
+        assert_eq!(result.timestamp, Some(10));
+        assert!(TsAdd::try_from(parse_args(&["ts.add", "ts", "10", "x"])).is_err());
+        assert!(TsAdd::try_from(parse_args(&["ts.add", "ts", "10", "1", "retention"])).is_err());
+
+        let result = TsRange::try_from(parse_args(&[
+            "ts.range",
+            "ts",
+            "-",
+            "+",
+            "AGGREGATION",
+            "avg",
+            "60000",
+        ]))?;
+        assert_eq!((result.from, result.to), (0, i64::MAX));
+        assert_eq!(result.aggregation, Some((Aggregation::Avg, 60000)));
+        assert!(TsRange::try_from(parse_args(&[
+            "ts.range",
+            "ts",
+            "0",
+            "1",
+            "aggregation",
+            "foo",
+            "1"
+        ]))
+        .is_err());
+
+        let result = TsCreateRule::try_from(parse_args(&[
+            "ts.createrule",
+            "src",
+            "dest",
+            "AGGREGATION",
+            "max",
+            "10",
+        ]))?;
+        assert_eq!((result.src.as_str(), result.dest.as_str()), ("src", "dest"));
+        assert_eq!((result.aggregation, result.bucket), (Aggregation::Max, 10));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ts_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = TsCreate {
+            key: "src".to_string(),
+            retention: 0,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd = TsCreate {
+            key: "src".to_string(),
+            retention: 0,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR TSDB: key already exists").into()
+        );
+        let cmd = TsCreate {
+            key: "dest".to_string(),
+            retention: 0,
+        };
+        cmd.execute(&backend);
+
+        let cmd = TsCreateRule {
+            src: "src".to_string(),
+            dest: "dest".to_string(),
+            aggregation: Aggregation::Avg,
+            bucket: 10,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd = TsCreateRule {
+            src: "dest".to_string(),
+            dest: "src".to_string(),
+            aggregation: Aggregation::Avg,
+            bucket: 10,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR TSDB: the source key already has a src rule").into()
+        );
+
+        for (ts, value) in [(1, 1.0), (2, 2.0), (11, 5.0), (25, 7.0)] {
+            let cmd = TsAdd {
+                key: "src".to_string(),
+                timestamp: Some(ts),
+                value,
+                retention: 0,
+            };
+            assert_eq!(cmd.execute(&backend), RespFrame::Integer(ts));
+        }
+        let cmd = TsAdd {
+            key: "src".to_string(),
+            timestamp: Some(25),
+            value: 1.0,
+            retention: 0,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new(TimeSeriesError::OutOfOrder.to_string()).into()
+        );
+
+        let cmd = TsGet {
+            key: "src".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), sample(25, "7"));
+
+        let cmd = TsRange {
+            key: "src".to_string(),
+            from: 2,
+            to: 11,
+            aggregation: None,
+        };
+        let expected = RespArray::new(vec![sample(2, "2"), sample(11, "5")]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = TsRange {
+            key: "src".to_string(),
+            from: 0,
+            to: i64::MAX,
+            aggregation: Some((Aggregation::Max, 10)),
+        };
+        let expected = RespArray::new(vec![sample(0, "2"), sample(10, "5"), sample(20, "7")]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        // the downsampled series only has the closed buckets
+        let cmd = TsRange {
+            key: "dest".to_string(),
+            from: 0,
+            to: i64::MAX,
+            aggregation: None,
+        };
+        let expected = RespArray::new(vec![sample(0, "1.5"), sample(10, "5")]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = TsGet {
+            key: "missing".to_string(),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR TSDB: the key does not exist").into()
+        );
+
+        Ok(())
+    }
+}
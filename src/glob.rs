@@ -0,0 +1,133 @@
+/// Redis style glob matching.
+///
+/// Supports `*` (any sequence), `?` (any single byte), `[...]` character classes with ranges
+/// and `^` negation, and `\` to escape a special character.
+pub fn glob_match(pattern: &[u8], s: &[u8]) -> bool {
+    let (mut p, mut i) = (0, 0);
+    // position in pattern after the last `*`, and the input position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while i < s.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            // collapse consecutive stars
+            while p < pattern.len() && pattern[p] == b'*' {
+                p += 1;
+            }
+            if p == pattern.len() {
+                return true;
+            }
+            backtrack = Some((p, i));
+            continue;
+        }
+
+        if let Some(next) = match_one(pattern, p, s[i]) {
+            p = next;
+            i += 1;
+            continue;
+        }
+
+        match backtrack {
+            Some((bp, bi)) => {
+                // let the last star swallow one more byte
+                p = bp;
+                i = bi + 1;
+                backtrack = Some((bp, bi + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+// try to match a single byte against the pattern token at `p`, returns the position after it
+fn match_one(pattern: &[u8], p: usize, c: u8) -> Option<usize> {
+    match pattern.get(p)? {
+        b'?' => Some(p + 1),
+        b'[' => match_class(pattern, p + 1, c),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then_some(p + 2),
+        &pc => (pc == c).then_some(p + 1),
+    }
+}
+
+// match a character class starting right after `[`
+fn match_class(pattern: &[u8], mut p: usize, c: u8) -> Option<usize> {
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    loop {
+        match pattern.get(p) {
+            // an unterminated class matches up to the end of the pattern
+            None => break,
+            Some(b']') => {
+                p += 1;
+                break;
+            }
+            Some(b'\\') if p + 1 < pattern.len() => {
+                matched |= pattern[p + 1] == c;
+                p += 2;
+            }
+            Some(&start) if pattern.get(p + 1) == Some(&b'-') && p + 2 < pattern.len() => {
+                let end = pattern[p + 2];
+                let (lo, hi) = if start <= end {
+                    (start, end)
+                } else {
+                    (end, start)
+                };
+                matched |= lo <= c && c <= hi;
+                p += 3;
+            }
+            Some(&pc) => {
+                matched |= pc == c;
+                p += 1;
+            }
+        }
+    }
+
+    (matched != negate).then_some(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(pattern: &str, s: &str) -> bool {
+        glob_match(pattern.as_bytes(), s.as_bytes())
+    }
+
+    #[test]
+    fn test_glob_star_and_question() {
+        assert!(m("*", ""));
+        assert!(m("*", "hello"));
+        assert!(m("h*o", "hello"));
+        assert!(m("h*llo*", "hello"));
+        assert!(m("h?llo", "hello"));
+        assert!(m("**lo", "hello"));
+        assert!(!m("h?llo", "hllo"));
+        assert!(!m("h*x", "hello"));
+        assert!(!m("hello", "hello!"));
+        assert!(m("a*b*c", "aXbYbZc"));
+    }
+
+    #[test]
+    fn test_glob_class() {
+        assert!(m("h[ae]llo", "hello"));
+        assert!(m("h[ae]llo", "hallo"));
+        assert!(!m("h[ae]llo", "hillo"));
+        assert!(m("h[^e]llo", "hallo"));
+        assert!(!m("h[^e]llo", "hello"));
+        assert!(m("h[a-b]llo", "hbllo"));
+        assert!(m("h[z-a]llo", "hbllo"));
+        assert!(!m("h[a-b]llo", "hcllo"));
+    }
+
+    #[test]
+    fn test_glob_escape() {
+        assert!(m("h\\*llo", "h*llo"));
+        assert!(!m("h\\*llo", "hello"));
+        assert!(m("h[\\]]llo", "h]llo"));
+    }
+}
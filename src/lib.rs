@@ -1,7 +1,20 @@
 mod backend;
+pub mod client;
+mod cluster;
 pub mod cmd;
+pub mod glob;
+pub mod middleware;
+pub mod module;
 pub mod network;
+mod pubsub;
+pub mod record;
+pub mod repl;
 mod resp;
+pub mod selftest;
+mod server;
+mod transaction;
+pub mod warmup;
 
 pub use backend::*;
 pub use resp::*;
+pub use server::*;
@@ -1,29 +1,78 @@
-use anyhow::Result;
-use simple_redis::{network, Backend};
-use tokio::net::TcpListener;
-use tracing::{info, warn};
+use anyhow::{bail, Result};
+use simple_redis::{
+    record::{self, Recorder},
+    repl, selftest, warmup, Backend, Server, ServerConfig,
+};
+use tracing::info;
+
+const USAGE: &str = "usage: simple-redis [--config <file>] [--warmup <file>] \
+    [--interactive | --record <file>] | selftest | replay <file> <addr> [speed]";
 
 #[tokio::main()]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
-    let addr = "0.0.0.0:6379";
-    info!("Listening on {}", addr);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    let (config, args) = match args[..] {
+        ["--config", path, ref rest @ ..] => (Some(path), rest),
+        ref rest => (None, rest),
+    };
+    let (warmup, args) = match args[..] {
+        ["--warmup", path, ref rest @ ..] => (Some(path), rest),
+        ref rest => (None, rest),
+    };
+    match args {
+        ["selftest"] => return selftest::run().await,
+        ["replay", path, addr] => {
+            record::replay(path, addr.parse()?, 1.0).await?;
+            return Ok(());
+        }
+        ["replay", path, addr, speed] => {
+            record::replay(path, addr.parse()?, speed.parse()?).await?;
+            return Ok(());
+        }
+        [] | ["--interactive"] | ["--record", _] => {}
+        _ => bail!(USAGE),
+    }
 
-    let listener = TcpListener::bind(addr).await?;
+    // the warmup runs before the listener is bound, clients only see a warm keyspace
     let backend = Backend::new();
+    let server_config = match config {
+        Some(path) => {
+            info!("Loading config file {}", path);
+            backend.load_config_file(path)?
+        }
+        None => ServerConfig::default(),
+    };
+    if let Some(path) = warmup {
+        warmup::run(&backend, path)?;
+    }
 
-    loop {
-        let (socket, raddr) = listener.accept().await?;
-        info!("Accepted connection from {}", raddr);
-
-        let backend = backend.clone();
+    let addr = server_config.addr();
+    info!("Listening on {}", addr);
 
-        tokio::spawn(async move {
-            match network::stream_handler(socket, backend).await {
-                Ok(_) => info!("Connection closed"),
-                Err(e) => warn!("Stream handle error: {:?}", e),
-            }
-        });
+    let mut server = Server::bind(&addr, backend).await?;
+    #[cfg(feature = "dylib")]
+    {
+        let mut modules = simple_redis::module::ModuleRegistry::new();
+        for path in &server_config.modules {
+            info!("Loading module {}", path);
+            modules.load_dylib(path)?;
+        }
+        server = server.with_modules(modules);
+    }
+    if let ["--record", path] = args {
+        info!("Recording commands to {}", path);
+        server = server.with_recorder(Recorder::create(path)?);
+    }
+    if let ["--interactive"] = args {
+        // the console shares the backend with the clients, the server stops when it exits
+        let server = server.spawn()?;
+        let backend = server.backend().clone();
+        return tokio::task::spawn_blocking(move || repl::run(backend)).await?;
     }
+    server.run().await?;
+    info!("Server stopped");
+    Ok(())
 }
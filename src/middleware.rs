@@ -0,0 +1,86 @@
+use crate::{cmd::Command, RespArray, RespFrame};
+use std::{
+    fmt,
+    net::SocketAddr,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The client connection a command was received on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionContext {
+    /// Unique in the process, in order of connection.
+    pub id: u64,
+    pub peer: SocketAddr,
+}
+
+/// Hooks run around the commands of every connection, e.g. for authentication, auditing or
+/// rate limiting, see [`crate::Server::with_middleware`].
+pub trait Middleware: Send + Sync {
+    /// Called before a built-in command runs, breaking skips the command and replies with the
+    /// given frame instead.
+    fn before(&self, _ctx: &ConnectionContext, _command: &Command) -> ControlFlow<RespFrame> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called before a command served by a loaded module, or a MODULE command, runs.
+    fn before_module(&self, _ctx: &ConnectionContext, _args: &RespArray) -> ControlFlow<RespFrame> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called with every reply before it is sent, including the replies of skipped commands.
+    fn after(&self, _ctx: &ConnectionContext, _response: &mut RespFrame) {}
+}
+
+/// Middlewares in the order they were added, which is the order their hooks run in.
+#[derive(Default, Clone)]
+pub struct MiddlewareChain {
+    layers: Vec<Arc<dyn Middleware>>,
+}
+
+impl ConnectionContext {
+    pub fn new(peer: SocketAddr) -> Self {
+        Self {
+            id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            peer,
+        }
+    }
+}
+
+impl MiddlewareChain {
+    pub fn push(&mut self, middleware: impl Middleware + 'static) {
+        self.layers.push(Arc::new(middleware));
+    }
+
+    /// Run the before hooks until one of them breaks.
+    pub fn before(&self, ctx: &ConnectionContext, command: &Command) -> ControlFlow<RespFrame> {
+        self.layers.iter().try_for_each(|m| m.before(ctx, command))
+    }
+
+    pub fn before_module(
+        &self,
+        ctx: &ConnectionContext,
+        args: &RespArray,
+    ) -> ControlFlow<RespFrame> {
+        self.layers
+            .iter()
+            .try_for_each(|m| m.before_module(ctx, args))
+    }
+
+    pub fn after(&self, ctx: &ConnectionContext, response: &mut RespFrame) {
+        self.layers.iter().for_each(|m| m.after(ctx, response));
+    }
+}
+
+impl fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MiddlewareChain")
+            .field("layers", &self.layers.len())
+            .finish()
+    }
+}
@@ -0,0 +1,90 @@
+use super::{CommandHandler, CommandModule, ModuleError, ModuleRegistry};
+use libloading::{Library, Symbol};
+use std::{ffi::OsStr, sync::Arc};
+
+/// Version of the interface between the server and the modules it loads from dynamic libraries.
+///
+/// Modules are Rust trait objects, a library must also be built with the same compiler and the
+/// same version of this crate as the server.
+pub const MODULE_ABI_VERSION: u32 = 1;
+
+/// Export a module from a `cdylib` crate, `$constructor` builds the module.
+#[macro_export]
+macro_rules! declare_module {
+    ($constructor:expr) => {
+        #[no_mangle]
+        pub extern "C" fn simple_redis_module_abi_version() -> u32 {
+            $crate::module::MODULE_ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub fn simple_redis_module_create() -> Box<dyn $crate::module::CommandModule> {
+            Box::new($constructor)
+        }
+    };
+}
+
+type AbiVersionFn = extern "C" fn() -> u32;
+type CreateFn = fn() -> Box<dyn CommandModule>;
+
+impl ModuleRegistry {
+    /// Load a module from a dynamic library exporting it with [`declare_module!`].
+    ///
+    /// The library stays loaded as long as any of the commands of the module is registered or
+    /// running.
+    pub fn load_dylib(&mut self, path: impl AsRef<OsStr>) -> Result<(), ModuleError> {
+        // SAFETY: loading runs the initializers of the library, which is trusted like any
+        // module linked into the server
+        let library =
+            unsafe { Library::new(path) }.map_err(|e| ModuleError::Library(e.to_string()))?;
+        let module = {
+            // SAFETY: the symbols are declared by declare_module! with these signatures
+            let abi_version: Symbol<AbiVersionFn> =
+                unsafe { library.get(b"simple_redis_module_abi_version\0") }
+                    .map_err(|e| ModuleError::Library(e.to_string()))?;
+            let version = abi_version();
+            if version != MODULE_ABI_VERSION {
+                return Err(ModuleError::AbiMismatch(version));
+            }
+            let create: Symbol<CreateFn> = unsafe { library.get(b"simple_redis_module_create\0") }
+                .map_err(|e| ModuleError::Library(e.to_string()))?;
+            create()
+        };
+
+        let library = Arc::new(library);
+        self.library = Some(library.clone());
+        let ret = self.load(module.as_ref());
+        self.library = None;
+        // the module code lives in the library, drop it while the library is still loaded
+        drop(module);
+        drop(library);
+        ret
+    }
+}
+
+// keep the library loaded for as long as the handler lives, fields drop in declaration order
+struct PinnedHandler {
+    handler: CommandHandler,
+    _library: Arc<Library>,
+}
+
+pub(super) fn pin_library(handler: CommandHandler, library: Arc<Library>) -> CommandHandler {
+    let pinned = PinnedHandler {
+        handler,
+        _library: library,
+    };
+    Arc::new(move |backend, args| (pinned.handler)(backend, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_dylib_missing_library() {
+        let mut registry = ModuleRegistry::new();
+        let ret = registry.load_dylib("/nonexistent/libmodule.so");
+        assert!(matches!(ret, Err(ModuleError::Library(_))));
+        assert!(registry.modules().is_empty());
+    }
+}
@@ -0,0 +1,352 @@
+#[cfg(feature = "dylib")]
+mod dylib;
+
+use crate::{
+    cmd::{self, CommandError},
+    Backend, BulkString, RespArray, RespFrame, SimpleError, SimpleString,
+};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+use thiserror::Error;
+
+#[cfg(feature = "dylib")]
+pub use dylib::MODULE_ABI_VERSION;
+
+/// Handler of a module command, called with the whole command including its name.
+pub type CommandHandler =
+    Arc<dyn Fn(&Backend, RespArray) -> Result<RespFrame, CommandError> + Send + Sync>;
+
+/// An extension adding commands to the server, loaded into a [`ModuleRegistry`] at startup.
+pub trait CommandModule {
+    fn name(&self) -> &str;
+
+    fn register(&self, registry: &mut ModuleRegistry) -> Result<(), ModuleError>;
+}
+
+/// Commands added by the loaded modules, by lowercase name.
+#[derive(Default)]
+pub struct ModuleRegistry {
+    commands: HashMap<String, ModuleCommand>,
+    modules: Vec<String>,
+    // module being loaded, owner of the commands registered meanwhile
+    loading: Option<String>,
+    // library of the module being loaded, if it comes from one
+    #[cfg(feature = "dylib")]
+    library: Option<Arc<libloading::Library>>,
+}
+
+struct ModuleCommand {
+    module: String,
+    handler: CommandHandler,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ModuleError {
+    #[error("module {0} is already loaded")]
+    AlreadyLoaded(String),
+    #[error("command {0} already exists")]
+    CommandExists(String),
+    #[error("commands can only be registered while loading a module")]
+    NotLoading,
+    #[error("no such module with that name")]
+    NotLoaded,
+    #[error("{0}")]
+    Library(String),
+    #[error("module ABI version {0} is not supported")]
+    AbiMismatch(u32),
+}
+
+impl ModuleRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a module, nothing it registered is kept if it fails.
+    pub fn load(&mut self, module: &dyn CommandModule) -> Result<(), ModuleError> {
+        let name = module.name().to_string();
+        if self.modules.contains(&name) {
+            return Err(ModuleError::AlreadyLoaded(name));
+        }
+
+        self.loading = Some(name.clone());
+        let ret = module.register(self);
+        self.loading = None;
+        if let Err(e) = ret {
+            self.commands.retain(|_, c| c.module != name);
+            return Err(e);
+        }
+        self.modules.push(name);
+        Ok(())
+    }
+
+    /// Register a command of the module being loaded, built-in commands can not be overridden.
+    pub fn register_command<F>(&mut self, name: &str, handler: F) -> Result<(), ModuleError>
+    where
+        F: Fn(&Backend, RespArray) -> Result<RespFrame, CommandError> + Send + Sync + 'static,
+    {
+        let module = self.loading.clone().ok_or(ModuleError::NotLoading)?;
+        let name = name.to_ascii_lowercase();
+        if cmd::is_builtin(name.as_bytes()) || self.commands.contains_key(&name) {
+            return Err(ModuleError::CommandExists(name));
+        }
+        let handler: CommandHandler = Arc::new(handler);
+        #[cfg(feature = "dylib")]
+        let handler = match &self.library {
+            Some(library) => dylib::pin_library(handler, library.clone()),
+            None => handler,
+        };
+        self.commands
+            .insert(name, ModuleCommand { module, handler });
+        Ok(())
+    }
+
+    /// Remove a module and its commands, running commands complete normally.
+    pub fn unload(&mut self, name: &str) -> Result<(), ModuleError> {
+        let index = self
+            .modules
+            .iter()
+            .position(|m| m == name)
+            .ok_or(ModuleError::NotLoaded)?;
+        self.modules.remove(index);
+        self.commands.retain(|_, c| c.module != name);
+        Ok(())
+    }
+
+    /// Names of the loaded modules, in loading order.
+    pub fn modules(&self) -> Vec<&str> {
+        self.modules.iter().map(|m| m.as_str()).collect()
+    }
+
+    /// Handler of the module command invoked by a request, if any.
+    pub(crate) fn handler(&self, args: &RespArray) -> Option<CommandHandler> {
+        if self.commands.is_empty() {
+            return None;
+        }
+        match args.0.as_ref()?.first()? {
+            RespFrame::BulkString(BulkString(Some(name))) => {
+                let name = std::str::from_utf8(name).ok()?.to_ascii_lowercase();
+                self.commands.get(&name).map(|c| c.handler.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether a request is the MODULE command, which manages the registry itself.
+pub(crate) fn is_admin_command(args: &RespArray) -> bool {
+    matches!(
+        args.0.as_ref().and_then(|a| a.first()),
+        Some(RespFrame::BulkString(BulkString(Some(name)))) if name.eq_ignore_ascii_case(b"module")
+    )
+}
+
+/// MODULE LIST | MODULE UNLOAD name | MODULE LOAD path
+///
+/// LOAD requires the `dylib` feature.
+pub(crate) fn admin_command(
+    registry: &RwLock<ModuleRegistry>,
+    args: RespArray,
+) -> Result<RespFrame, CommandError> {
+    let mut args = cmd::extract_args(args, 1)?
+        .into_iter()
+        .map(|arg| match arg {
+            RespFrame::BulkString(BulkString(Some(arg))) => Ok(String::from_utf8(arg)?),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        });
+    let subcommand = args.next().transpose()?.unwrap_or_default();
+    let arg = args.next().transpose()?;
+    if args.next().is_some() {
+        return Err(CommandError::InvalidArgument("syntax error".to_string()));
+    }
+
+    let ret = match (subcommand.to_ascii_lowercase().as_str(), arg) {
+        ("list", None) => {
+            let registry = registry.read().unwrap();
+            let modules = registry
+                .modules()
+                .into_iter()
+                .map(|m| {
+                    RespArray::new(vec![
+                        BulkString::new("name").into(),
+                        BulkString::new(m).into(),
+                    ])
+                    .into()
+                })
+                .collect();
+            return Ok(RespArray::new(modules).into());
+        }
+        ("unload", Some(name)) => registry
+            .write()
+            .unwrap()
+            .unload(&name)
+            .map_err(|e| format!("Error unloading module: {}", e)),
+        #[cfg(feature = "dylib")]
+        ("load", Some(path)) => registry
+            .write()
+            .unwrap()
+            .load_dylib(path)
+            .map_err(|e| format!("Error loading the extension: {}", e)),
+        _ => {
+            return Err(CommandError::InvalidArgument(format!(
+                "unknown subcommand '{}'",
+                subcommand
+            )))
+        }
+    };
+
+    Ok(match ret {
+        Ok(()) => SimpleString::new("OK").into(),
+        Err(e) => SimpleError::new(format!("ERR {}", e)).into(),
+    })
+}
+
+impl fmt::Debug for ModuleRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut commands: Vec<_> = self.commands.keys().collect();
+        commands.sort();
+        f.debug_struct("ModuleRegistry")
+            .field("modules", &self.modules)
+            .field("commands", &commands)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{network::RespFrameCodec, Server};
+    use anyhow::Result;
+    use futures::SinkExt;
+    use tokio::net::TcpStream;
+    use tokio_stream::StreamExt;
+    use tokio_util::codec::Framed;
+
+    struct Counter;
+
+    impl CommandModule for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn register(&self, registry: &mut ModuleRegistry) -> Result<(), ModuleError> {
+            registry.register_command("counter.incr", |backend, args| {
+                let key = match args.0.and_then(|a| a.into_iter().nth(1)) {
+                    Some(RespFrame::BulkString(BulkString(Some(key)))) => String::from_utf8(key)?,
+                    _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+                };
+                let n = match backend.hlen(&key) {
+                    Ok(len) => len as i64 + 1,
+                    Err(e) => return Ok(SimpleError::new(e.to_string()).into()),
+                };
+                if let Err(e) = backend.hset(key, n.to_string(), n.into()) {
+                    return Ok(SimpleError::new(e.to_string()).into());
+                }
+                Ok(RespFrame::Integer(n))
+            })
+        }
+    }
+
+    struct Clashing;
+
+    impl CommandModule for Clashing {
+        fn name(&self) -> &str {
+            "clashing"
+        }
+
+        fn register(&self, registry: &mut ModuleRegistry) -> Result<(), ModuleError> {
+            registry.register_command("clashing.ok", |_, _| Ok(RespFrame::Integer(0)))?;
+            registry.register_command("GET", |_, _| Ok(RespFrame::Integer(0)))
+        }
+    }
+
+    #[test]
+    fn test_module_registry_load() {
+        let mut registry = ModuleRegistry::new();
+        assert!(registry.load(&Counter).is_ok());
+        assert_eq!(
+            registry.load(&Counter),
+            Err(ModuleError::AlreadyLoaded("counter".to_string()))
+        );
+        assert_eq!(
+            registry.load(&Clashing),
+            Err(ModuleError::CommandExists("get".to_string()))
+        );
+        assert_eq!(registry.modules(), vec!["counter"]);
+
+        let args = |name: &str| RespArray::new(vec![BulkString::new(name).into()]);
+        assert!(registry.handler(&args("COUNTER.INCR")).is_some());
+        assert!(registry.handler(&args("clashing.ok")).is_none());
+        assert_eq!(
+            registry.register_command("late", |_, _| Ok(RespFrame::Integer(0))),
+            Err(ModuleError::NotLoading)
+        );
+    }
+
+    #[test]
+    fn test_module_admin_command() -> Result<()> {
+        let mut registry = ModuleRegistry::new();
+        registry.load(&Counter)?;
+        let registry = RwLock::new(registry);
+        let args = |args: &[&str]| {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+        };
+
+        assert!(is_admin_command(&args(&["MODULE", "list"])));
+        assert!(!is_admin_command(&args(&["counter.incr", "key"])));
+
+        let ret = admin_command(&registry, args(&["module", "list"]))?;
+        let expected = RespArray::new(vec![RespArray::new(vec![
+            BulkString::new("name").into(),
+            BulkString::new("counter").into(),
+        ])
+        .into()]);
+        assert_eq!(ret, expected.into());
+
+        let ret = admin_command(&registry, args(&["module", "unload", "counter"]))?;
+        assert_eq!(ret, SimpleString::new("OK").into());
+        assert!(registry
+            .read()
+            .unwrap()
+            .handler(&args(&["counter.incr"]))
+            .is_none());
+
+        let ret = admin_command(&registry, args(&["module", "unload", "counter"]))?;
+        assert_eq!(
+            ret,
+            SimpleError::new("ERR Error unloading module: no such module with that name").into()
+        );
+        assert!(admin_command(&registry, args(&["module", "reload"])).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_module_command_is_served() -> Result<()> {
+        let mut modules = ModuleRegistry::new();
+        modules.load(&Counter)?;
+        let server = Server::bind("127.0.0.1:0", Backend::new())
+            .await?
+            .with_modules(modules)
+            .spawn()?;
+
+        let stream = TcpStream::connect(server.addr()).await?;
+        let mut framed = Framed::new(stream, RespFrameCodec::default());
+        for expected in [1, 2] {
+            let args = vec![
+                BulkString::new("counter.incr").into(),
+                BulkString::new("key").into(),
+            ];
+            framed.send(RespArray::new(args).into()).await?;
+            let ret = framed.next().await.expect("connection closed")?;
+            assert_eq!(ret, RespFrame::Integer(expected));
+        }
+        assert_eq!(server.backend().hlen("key").unwrap(), 2);
+
+        Ok(())
+    }
+}
@@ -1,47 +1,135 @@
 use crate::{
-    cmd::{Command, CommandExecutor},
-    Backend, RespDecode, RespEncode, RespError, RespFrame, SimpleError,
+    client::{self, ClientRegistry},
+    cluster,
+    cmd::{self, Command, CommandExecutor},
+    middleware::{ConnectionContext, MiddlewareChain},
+    module::{self, ModuleRegistry},
+    pubsub::Subscriptions,
+    record::Recorder,
+    transaction::{self, Queued, Transaction},
+    Backend, EvictionError, RespDecode, RespEncode, RespError, RespFrame, SimpleError,
+    BUF_CAPACITY,
 };
 use anyhow::Result;
+use bytes::BytesMut;
 use futures::SinkExt;
+use std::{
+    net::SocketAddr,
+    ops::ControlFlow,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
 use tokio::net::TcpStream;
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
 use tracing::{info, warn};
 
-#[derive(Debug)]
-struct RespFrameCodec;
+// number of recent frame sizes kept to size the read buffer
+const FRAME_SIZE_SAMPLES: usize = 64;
+// the read buffer is reserved for this percentile of recent frame sizes
+const FRAME_SIZE_PERCENTILE: usize = 90;
+// frame sizes recorded between two updates of the cached percentile
+const FRAME_SIZE_REFRESH: usize = 8;
+// upper bound of the read buffer reserve, larger frames still grow the buffer on demand
+const MAX_READ_RESERVE: usize = 1024 * 1024;
+// reply to the connection level commands sent after MULTI
+const NOT_IN_TRANSACTION: &str = "ERR Command not allowed inside a transaction";
+
+#[derive(Debug, Default)]
+pub(crate) struct RespFrameCodec {
+    frame_sizes: FrameSizes,
+    // when the latest bytes were read, and how many bytes were left after the latest frame
+    arrived: Option<Instant>,
+    remaining: usize,
+}
+
+// ring buffer of the sizes of recently decoded frames
+#[derive(Debug, Default)]
+struct FrameSizes {
+    samples: Vec<usize>,
+    next: usize,
+    // FRAME_SIZE_PERCENTILE of the samples, so reading it does not sort them on every frame
+    typical: Option<usize>,
+}
 
 #[derive(Debug)]
 struct RedisRequest {
     frame: RespFrame,
     backend: Backend,
+    modules: Arc<RwLock<ModuleRegistry>>,
+    context: ConnectionContext,
+    middleware: Arc<MiddlewareChain>,
+    clients: Arc<ClientRegistry>,
+    // the address the client connected to, announced by CLUSTER
+    local: SocketAddr,
 }
 
 #[derive(Debug)]
 struct RedisResponse {
-    frame: RespFrame,
+    // a command may reply with several frames, as SSUBSCRIBE does with one per channel
+    frames: Vec<RespFrame>,
 }
 
-pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
-    let mut framed = Framed::new(stream, RespFrameCodec);
+// state of a connection carried from one request to the next
+struct ConnectionState {
+    transaction: Transaction,
+    subscriptions: Subscriptions,
+}
+
+pub async fn stream_handler(
+    stream: TcpStream,
+    backend: Backend,
+    modules: Arc<RwLock<ModuleRegistry>>,
+    recorder: Option<Arc<Recorder>>,
+    middleware: Arc<MiddlewareChain>,
+    clients: Arc<ClientRegistry>,
+) -> Result<()> {
+    let context = ConnectionContext::new(stream.peer_addr()?);
+    let local = stream.local_addr()?;
+    // the registration, the subscriptions and a queued transaction are dropped on every way out
+    // of the loop, a failed write included, so a closed connection leaves nothing behind
+    let _registration = clients.register(&context);
+    let mut framed = Framed::new(stream, RespFrameCodec::default());
+    let mut state = ConnectionState {
+        transaction: Transaction::default(),
+        subscriptions: Subscriptions::new(backend.clone(), context.id),
+    };
 
     loop {
-        let result: Result<Option<()>> = match framed.next().await {
+        let next = tokio::select! {
+            next = framed.next() => next,
+            message = state.subscriptions.message() => {
+                framed.send(message).await?;
+                continue;
+            }
+            // the replies of the previous requests are sent, the server waits for this
+            _ = backend.shutdown_requested() => return Ok(()),
+        };
+        let result: Result<Option<()>> = match next {
             Some(Ok(frame)) => {
+                if let Some(arrived) = framed.codec().arrived {
+                    backend.stats().record_queue_delay(arrived.elapsed());
+                }
+                if let Some(recorder) = &recorder {
+                    if let Err(e) = recorder.record(&frame) {
+                        warn!("Recording error: {:?}", e);
+                    }
+                }
                 let request = RedisRequest {
                     frame,
                     backend: backend.clone(),
+                    modules: modules.clone(),
+                    context: context.clone(),
+                    middleware: middleware.clone(),
+                    clients: clients.clone(),
+                    local,
                 };
-                let response = request_handler(request).await;
-                // do not close the connection if there is an error in the request
-                match response {
-                    Ok(response) => {
-                        framed.send(response.frame).await?;
-                        Ok(Some(()))
-                    }
-                    Err(e) => Err(e),
+                // a command that fails replies with an error frame and the connection goes on
+                let response = request_handler(request, &mut state).await;
+                for frame in response.frames {
+                    framed.send(frame).await?;
                 }
+                Ok(Some(()))
             }
             Some(Err(e)) => Err(e),
             None => Ok(None),
@@ -56,30 +144,183 @@ pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
                 return Ok(());
             }
             Err(e) => {
-                // response with an error frame, otherwise the connection will be closed
-                let response = RedisResponse {
-                    frame: SimpleError::new(e.to_string()).into(),
-                };
-                warn!("Handle Exception: {:?}", e);
-                framed.send(response.frame).await?;
+                // the stream cannot be decoded past corrupted input, reply and close
+                let frame: RespFrame =
+                    SimpleError::new(format!("ERR Protocol error: {}", e)).into();
+                warn!("Protocol error: {:?}", e);
+                framed.send(frame).await?;
+                return Err(e);
             }
         }
     }
 }
 
-async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
-    let (frame, backend) = (request.frame, request.backend);
-    let cmd: Command = frame.try_into()?;
-    info!("Executing command: {:?}", cmd);
-    let ret = cmd.execute(&backend);
+async fn request_handler(request: RedisRequest, state: &mut ConnectionState) -> RedisResponse {
+    let (frame, backend, modules) = (request.frame, request.backend, request.modules);
+    let (ctx, middleware) = (&request.context, &request.middleware);
+    let ConnectionState {
+        transaction,
+        subscriptions,
+    } = state;
+    if let RespFrame::Array(args) = &frame {
+        request.clients.record_command(ctx.id, args);
+    }
+    let frame = match frame {
+        RespFrame::Array(args) if subscriptions.handles(&args) => {
+            let mut frames = match middleware.before_module(ctx, &args) {
+                ControlFlow::Break(frame) => vec![frame],
+                ControlFlow::Continue(()) if transaction.is_active() => {
+                    transaction.abort();
+                    vec![SimpleError::new(NOT_IN_TRANSACTION).into()]
+                }
+                ControlFlow::Continue(()) => {
+                    info!("Executing subscription command: {:?}", args);
+                    subscriptions
+                        .command(args)
+                        .unwrap_or_else(|e| vec![e.into()])
+                }
+            };
+            frames
+                .iter_mut()
+                .for_each(|frame| middleware.after(ctx, frame));
+            return RedisResponse { frames };
+        }
+        RespFrame::Array(args) if subscriptions.is_active() => {
+            let mut frame = subscriptions.reject(&args);
+            middleware.after(ctx, &mut frame);
+            return RedisResponse {
+                frames: vec![frame],
+            };
+        }
+        frame => frame,
+    };
+    let mut ret = match frame {
+        RespFrame::Array(args) if transaction::is_transaction_command(&args) => transaction
+            .command(&backend, args)
+            .await
+            .unwrap_or_else(RespFrame::from),
+        // connection and server administration is not transactional
+        RespFrame::Array(args)
+            if transaction.is_active()
+                && (client::is_client_command(&args)
+                    || cluster::is_cluster_command(&args)
+                    || module::is_admin_command(&args)) =>
+        {
+            transaction.abort();
+            SimpleError::new(NOT_IN_TRANSACTION).into()
+        }
+        RespFrame::Array(args) if client::is_client_command(&args) => {
+            match middleware.before_module(ctx, &args) {
+                ControlFlow::Break(frame) => frame,
+                ControlFlow::Continue(()) => {
+                    info!("Executing client command: {:?}", args);
+                    client::client_command(&request.clients, ctx, args)
+                        .unwrap_or_else(RespFrame::from)
+                }
+            }
+        }
+        RespFrame::Array(args) if cluster::is_cluster_command(&args) => {
+            match middleware.before_module(ctx, &args) {
+                ControlFlow::Break(frame) => frame,
+                ControlFlow::Continue(()) => {
+                    info!("Executing cluster command: {:?}", args);
+                    cluster::cluster_command(request.local, args).unwrap_or_else(RespFrame::from)
+                }
+            }
+        }
+        RespFrame::Array(args) if module::is_admin_command(&args) => {
+            match middleware.before_module(ctx, &args) {
+                ControlFlow::Break(frame) => frame,
+                ControlFlow::Continue(()) => {
+                    info!("Executing module admin command: {:?}", args);
+                    module::admin_command(&modules, args).unwrap_or_else(RespFrame::from)
+                }
+            }
+        }
+        frame => {
+            // the registry lock is not held while the command runs
+            let handler = match &frame {
+                RespFrame::Array(args) => modules.read().unwrap().handler(args),
+                _ => None,
+            };
+            match (handler, frame) {
+                (Some(handler), RespFrame::Array(args)) => {
+                    match middleware.before_module(ctx, &args) {
+                        ControlFlow::Break(frame) => {
+                            transaction.abort();
+                            frame
+                        }
+                        ControlFlow::Continue(()) if transaction.is_active() => {
+                            transaction.queue(Queued::Module(handler, args))
+                        }
+                        ControlFlow::Continue(()) => {
+                            info!("Executing module command: {:?}", args);
+                            backend
+                                .shared(|| handler(&backend, args))
+                                .await
+                                .unwrap_or_else(RespFrame::from)
+                        }
+                    }
+                }
+                (_, frame) => {
+                    let flags = match &frame {
+                        RespFrame::Array(args) => cmd::command_flags(args),
+                        _ => &[],
+                    };
+                    match Command::try_from(frame) {
+                        // a command that fails to parse aborts the transaction
+                        Err(e) => {
+                            transaction.abort();
+                            e.into()
+                        }
+                        Ok(cmd) => match middleware.before(ctx, &cmd) {
+                            ControlFlow::Break(frame) => {
+                                transaction.abort();
+                                frame
+                            }
+                            // an unknown command is not queued, it aborts the transaction
+                            ControlFlow::Continue(())
+                                if matches!(cmd, Command::Unrecognized(_)) =>
+                            {
+                                transaction.abort();
+                                cmd.execute(&backend)
+                            }
+                            // make room before writes that may grow the dataset
+                            ControlFlow::Continue(())
+                                if flags.contains(&"denyoom") && backend.free_memory().is_err() =>
+                            {
+                                transaction.abort();
+                                SimpleError::new(EvictionError::OutOfMemory.to_string()).into()
+                            }
+                            ControlFlow::Continue(()) if transaction.is_active() => {
+                                transaction.queue(Queued::Command(cmd))
+                            }
+                            ControlFlow::Continue(()) => {
+                                info!("Executing command: {:?}", cmd);
+                                match cmd.delay() {
+                                    Some(delay) => {
+                                        backend
+                                            .exclusive_after(delay, || cmd.execute(&backend))
+                                            .await
+                                    }
+                                    None => backend.shared(|| cmd.execute(&backend)).await,
+                                }
+                            }
+                        },
+                    }
+                }
+            }
+        }
+    };
+    middleware.after(ctx, &mut ret);
     info!("Command executed, response: {:?}", ret);
-    Ok(RedisResponse { frame: ret })
+    RedisResponse { frames: vec![ret] }
 }
 
 impl Encoder<RespFrame> for RespFrameCodec {
     type Error = anyhow::Error;
 
-    fn encode(&mut self, item: RespFrame, dst: &mut bytes::BytesMut) -> Result<()> {
+    fn encode(&mut self, item: RespFrame, dst: &mut BytesMut) -> Result<()> {
         let encoded = item.encode();
         info!("Encoded Response: {:?}", String::from_utf8_lossy(&encoded));
         dst.extend_from_slice(&encoded);
@@ -91,11 +332,171 @@ impl Decoder for RespFrameCodec {
     type Item = RespFrame;
     type Error = anyhow::Error;
 
-    fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>> {
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+        let len = src.len();
+        // pipelined frames already in the buffer keep the arrival time of their read
+        if len != self.remaining {
+            self.arrived = Some(Instant::now());
+        }
         match RespFrame::decode(src) {
-            Ok(frame) => Ok(Some(frame)),
-            Err(RespError::NotComplete) => Ok(None),
+            Ok(frame) => {
+                self.remaining = src.len();
+                self.frame_sizes.record(len - src.len());
+                self.shrink_idle_buffer(src);
+                Ok(Some(frame))
+            }
+            Err(RespError::NotComplete) => {
+                self.remaining = src.len();
+                // make room for a typical frame at once instead of growing on every read
+                let reserve = self.read_reserve();
+                if reserve > src.len() {
+                    src.reserve(reserve - src.len());
+                }
+                Ok(None)
+            }
             Err(e) => Err(e.into()),
         }
     }
 }
+
+impl RespFrameCodec {
+    // size of the read buffer to reserve for an incoming frame
+    fn read_reserve(&self) -> usize {
+        self.frame_sizes
+            .typical
+            .unwrap_or(BUF_CAPACITY)
+            .clamp(BUF_CAPACITY, MAX_READ_RESERVE)
+    }
+
+    // release the memory held by an empty buffer that grew well beyond recent frame sizes
+    fn shrink_idle_buffer(&self, src: &mut BytesMut) {
+        let reserve = self.read_reserve();
+        if src.is_empty() && src.capacity() > reserve * 2 {
+            *src = BytesMut::with_capacity(reserve);
+        }
+    }
+}
+
+impl FrameSizes {
+    fn record(&mut self, size: usize) {
+        if self.samples.len() < FRAME_SIZE_SAMPLES {
+            self.samples.push(size);
+        } else {
+            self.samples[self.next] = size;
+        }
+        self.next = (self.next + 1) % FRAME_SIZE_SAMPLES;
+        if self.typical.is_none() || self.next.is_multiple_of(FRAME_SIZE_REFRESH) {
+            self.typical = self.percentile(FRAME_SIZE_PERCENTILE);
+        }
+    }
+
+    fn percentile(&self, p: usize) -> Option<usize> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let idx = (sorted.len() * p / 100).min(sorted.len() - 1);
+        Some(sorted[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespArray};
+
+    fn set_request(value_len: usize) -> Vec<u8> {
+        let frame: RespFrame = RespArray::new(vec![
+            BulkString::new("set").into(),
+            BulkString::new("key").into(),
+            BulkString::new(vec![b'x'; value_len]).into(),
+        ])
+        .into();
+        frame.encode()
+    }
+
+    #[test]
+    fn test_frame_sizes_percentile() {
+        let mut sizes = FrameSizes::default();
+        assert_eq!(sizes.percentile(90), None);
+
+        for size in 1..=100 {
+            sizes.record(size);
+        }
+        // only the latest FRAME_SIZE_SAMPLES sizes are kept
+        assert_eq!(sizes.samples.len(), FRAME_SIZE_SAMPLES);
+        assert_eq!(sizes.percentile(0), Some(100 - FRAME_SIZE_SAMPLES + 1));
+        assert_eq!(sizes.percentile(100), Some(100));
+    }
+
+    #[test]
+    fn test_frame_sizes_typical_is_cached() {
+        let mut sizes = FrameSizes::default();
+        sizes.record(10);
+        assert_eq!(sizes.typical, Some(10));
+
+        // the cached percentile is only refreshed every FRAME_SIZE_REFRESH sizes
+        for _ in 2..FRAME_SIZE_REFRESH {
+            sizes.record(1000);
+            assert_eq!(sizes.typical, Some(10));
+        }
+        sizes.record(1000);
+        assert_eq!(sizes.typical, Some(1000));
+    }
+
+    #[test]
+    fn test_codec_reserves_for_large_frames() -> Result<()> {
+        let mut codec = RespFrameCodec::default();
+        assert_eq!(codec.read_reserve(), BUF_CAPACITY);
+
+        let request = set_request(64 * 1024);
+        for _ in 0..FRAME_SIZE_SAMPLES {
+            let mut buf = BytesMut::from(&request[..]);
+            assert!(codec.decode(&mut buf)?.is_some());
+        }
+        assert_eq!(codec.read_reserve(), request.len());
+
+        // a partial large frame gets the whole typical frame size reserved at once
+        let mut buf = BytesMut::from(&request[..100]);
+        assert!(codec.decode(&mut buf)?.is_none());
+        assert!(buf.capacity() >= request.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_shrinks_idle_buffer() -> Result<()> {
+        let mut codec = RespFrameCodec::default();
+        let request = set_request(16);
+
+        let mut buf = BytesMut::with_capacity(1024 * 1024);
+        buf.extend_from_slice(&request);
+        assert!(codec.decode(&mut buf)?.is_some());
+        assert!(buf.is_empty());
+        assert!(buf.capacity() <= BUF_CAPACITY * 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_tracks_arrival_of_pipelined_frames() -> Result<()> {
+        let mut codec = RespFrameCodec::default();
+        let mut buf = BytesMut::from(&set_request(16)[..]);
+        buf.extend_from_slice(&set_request(16));
+
+        assert!(codec.decode(&mut buf)?.is_some());
+        let arrived = codec.arrived;
+        assert!(arrived.is_some());
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        // the second frame came with the same read
+        assert!(codec.decode(&mut buf)?.is_some());
+        assert_eq!(codec.arrived, arrived);
+
+        buf.extend_from_slice(&set_request(16));
+        assert!(codec.decode(&mut buf)?.is_some());
+        assert!(codec.arrived > arrived);
+
+        Ok(())
+    }
+}
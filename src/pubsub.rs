@@ -0,0 +1,216 @@
+use crate::{
+    cmd::{self, CommandError},
+    Backend, BulkString, RespArray, RespFrame, SimpleError,
+};
+use std::collections::BTreeSet;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// The shard channels a connection is subscribed to, and the messages published to them.
+///
+/// While subscribed, a connection only accepts SSUBSCRIBE, SUNSUBSCRIBE and PING.
+pub(crate) struct Subscriptions {
+    backend: Backend,
+    id: u64,
+    channels: BTreeSet<String>,
+    sender: UnboundedSender<RespFrame>,
+    receiver: UnboundedReceiver<RespFrame>,
+}
+
+impl Subscriptions {
+    pub(crate) fn new(backend: Backend, id: u64) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            backend,
+            id,
+            channels: BTreeSet::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        !self.channels.is_empty()
+    }
+
+    /// The next message published to one of the channels.
+    pub(crate) async fn message(&mut self) -> RespFrame {
+        match self.receiver.recv().await {
+            Some(frame) => frame,
+            // never closed, the sender is held along with the receiver
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Whether the command is handled by [`Subscriptions::command`], any other is rejected while
+    /// the connection is subscribed.
+    pub(crate) fn handles(&self, args: &RespArray) -> bool {
+        match name(args).as_deref() {
+            Some(b"ssubscribe" | b"sunsubscribe") => true,
+            Some(b"ping") => self.is_active(),
+            _ => false,
+        }
+    }
+
+    /// The reply to a command rejected while the connection is subscribed.
+    pub(crate) fn reject(&self, args: &RespArray) -> RespFrame {
+        let name = name(args).unwrap_or_default();
+        SimpleError::new(format!(
+            "ERR Can't execute '{}': only SSUBSCRIBE / SUNSUBSCRIBE / PING are allowed in this context",
+            String::from_utf8_lossy(&name)
+        ))
+        .into()
+    }
+
+    /// Handle SSUBSCRIBE, SUNSUBSCRIBE or PING, replies with a frame per channel for the first
+    /// two.
+    pub(crate) fn command(&mut self, args: RespArray) -> Result<Vec<RespFrame>, CommandError> {
+        let name = name(&args).unwrap_or_default();
+        let args = cmd::parse_strings(args)?;
+        let ret = match (name.as_slice(), args.as_slice()) {
+            (b"ssubscribe", []) => {
+                return Err(CommandError::InvalidArgument(
+                    "wrong number of arguments for 'ssubscribe' command".to_string(),
+                ))
+            }
+            (b"ssubscribe", channels) => channels
+                .iter()
+                .map(|channel| {
+                    if self.channels.insert(channel.clone()) {
+                        self.backend
+                            .ssubscribe(channel, self.id, self.sender.clone());
+                    }
+                    self.reply("ssubscribe", Some(channel))
+                })
+                .collect(),
+            (b"sunsubscribe", []) if !self.is_active() => vec![self.reply("sunsubscribe", None)],
+            (b"sunsubscribe", []) => {
+                let channels = std::mem::take(&mut self.channels);
+                channels
+                    .iter()
+                    .map(|channel| {
+                        self.backend.sunsubscribe(channel, self.id);
+                        self.reply("sunsubscribe", Some(channel))
+                    })
+                    .collect()
+            }
+            (b"sunsubscribe", channels) => channels
+                .iter()
+                .map(|channel| {
+                    if self.channels.remove(channel) {
+                        self.backend.sunsubscribe(channel, self.id);
+                    }
+                    self.reply("sunsubscribe", Some(channel))
+                })
+                .collect(),
+            (b"ping", [] | [_]) => {
+                let message = args.first().map(String::as_str).unwrap_or_default();
+                vec![RespArray::new(vec![
+                    BulkString::new("pong").into(),
+                    BulkString::new(message).into(),
+                ])
+                .into()]
+            }
+            (name, _) => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "wrong number of arguments for '{}' command",
+                    String::from_utf8_lossy(name)
+                )))
+            }
+        };
+        Ok(ret)
+    }
+
+    // confirmation of a subscription change, with the number of channels subscribed after it
+    fn reply(&self, kind: &str, channel: Option<&str>) -> RespFrame {
+        RespArray::new(vec![
+            BulkString::new(kind).into(),
+            channel
+                .map_or_else(BulkString::new_null, BulkString::new)
+                .into(),
+            (self.channels.len() as i64).into(),
+        ])
+        .into()
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        for channel in &self.channels {
+            self.backend.sunsubscribe(channel, self.id);
+        }
+    }
+}
+
+// name of a command in lower case
+fn name(args: &RespArray) -> Option<Vec<u8>> {
+    match args.0.as_ref()?.first()? {
+        RespFrame::BulkString(BulkString(Some(name))) => Some(name.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    fn reply(kind: &str, channel: &str, count: i64) -> RespFrame {
+        RespArray::new(vec![
+            BulkString::new(kind).into(),
+            BulkString::new(channel).into(),
+            count.into(),
+        ])
+        .into()
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions() -> Result<()> {
+        let backend = Backend::new();
+        let mut subscriptions = Subscriptions::new(backend.clone(), 1);
+        assert!(!subscriptions.handles(&args(&["ping"])));
+        assert!(subscriptions.handles(&args(&["SSUBSCRIBE", "a"])));
+
+        let ret = subscriptions.command(args(&["ssubscribe", "a", "b", "a"]))?;
+        assert_eq!(
+            ret,
+            vec![
+                reply("ssubscribe", "a", 1),
+                reply("ssubscribe", "b", 2),
+                reply("ssubscribe", "a", 2)
+            ]
+        );
+        assert!(subscriptions.handles(&args(&["ping"])));
+        assert!(!subscriptions.handles(&args(&["get", "key"])));
+
+        assert_eq!(backend.spublish("a", b"hello"), 1);
+        let expected = RespArray::new(vec![
+            BulkString::new("smessage").into(),
+            BulkString::new("a").into(),
+            BulkString::new("hello").into(),
+        ]);
+        assert_eq!(subscriptions.message().await, expected.into());
+
+        let ret = subscriptions.command(args(&["sunsubscribe", "a"]))?;
+        assert_eq!(ret, vec![reply("sunsubscribe", "a", 1)]);
+        assert_eq!(backend.spublish("a", b"hello"), 0);
+        let ret = subscriptions.command(args(&["sunsubscribe"]))?;
+        assert_eq!(ret, vec![reply("sunsubscribe", "b", 0)]);
+        assert!(!subscriptions.is_active());
+
+        assert!(subscriptions.command(args(&["ssubscribe"])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscriptions_dropped() {
+        let backend = Backend::new();
+        let mut subscriptions = Subscriptions::new(backend.clone(), 1);
+        subscriptions.command(args(&["ssubscribe", "a"])).unwrap();
+        drop(subscriptions);
+        assert_eq!(backend.spublish("a", b"hello"), 0);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{network, RespDecode, RespEncode, RespFrame};
+use anyhow::{anyhow, Result};
+use bytes::{Buf, BytesMut};
+use futures::SinkExt;
+use std::{
+    fs::File,
+    io::Write,
+    net::SocketAddr,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::net::TcpStream;
+use tokio_stream::StreamExt;
+use tokio_util::codec::Framed;
+use tracing::info;
+
+/// Appends the command frames received by a server to a file, along with their arrival time.
+///
+/// Each entry is the number of microseconds since recording started on its own line, followed
+/// by the frame in RESP encoding. Entries are written as they arrive so that a recording
+/// survives the server being killed.
+#[derive(Debug)]
+pub struct Recorder {
+    start: Instant,
+    file: Mutex<File>,
+}
+
+/// A command frame read back from a recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedFrame {
+    pub offset: Duration,
+    pub frame: RespFrame,
+}
+
+/// Outcome of replaying a recording.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReplayStats {
+    pub commands: usize,
+    pub errors: usize,
+    pub elapsed: Duration,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            start: Instant::now(),
+            file: Mutex::new(File::create(path)?),
+        })
+    }
+
+    pub fn record(&self, frame: &RespFrame) -> Result<()> {
+        let offset = self.start.elapsed().as_micros();
+        let mut entry = format!("{}\r\n", offset).into_bytes();
+        entry.extend(frame.clone().encode());
+        self.file.lock().unwrap().write_all(&entry)?;
+        Ok(())
+    }
+}
+
+/// Read all the frames of a recording, in arrival order.
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<RecordedFrame>> {
+    let mut buf = BytesMut::from(&std::fs::read(path)?[..]);
+    let mut frames = Vec::new();
+    while !buf.is_empty() {
+        let end = buf
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| anyhow!("truncated recording entry"))?;
+        let offset = std::str::from_utf8(&buf[..end])?.parse()?;
+        buf.advance(end + 2);
+        let frame = RespFrame::decode(&mut buf)?;
+        frames.push(RecordedFrame {
+            offset: Duration::from_micros(offset),
+            frame,
+        });
+    }
+    Ok(frames)
+}
+
+/// Re-send the frames of a recording to a server over a single connection.
+///
+/// Frames are sent at their original pace divided by `speed`, e.g. 2.0 replays twice as fast,
+/// a speed of 0 sends them back to back. Each response is awaited before sending the next frame.
+pub async fn replay(path: impl AsRef<Path>, addr: SocketAddr, speed: f64) -> Result<ReplayStats> {
+    let frames = read(path)?;
+    let stream = TcpStream::connect(addr).await?;
+    let mut framed = Framed::new(stream, network::RespFrameCodec::default());
+
+    let start = tokio::time::Instant::now();
+    let mut stats = ReplayStats::default();
+    for recorded in frames {
+        if speed > 0.0 {
+            tokio::time::sleep_until(start + recorded.offset.div_f64(speed)).await;
+        }
+        framed.send(recorded.frame).await?;
+        let response = framed
+            .next()
+            .await
+            .ok_or_else(|| anyhow!("connection closed during replay"))??;
+        stats.commands += 1;
+        if let RespFrame::Error(_) = response {
+            stats.errors += 1;
+        }
+    }
+    stats.elapsed = start.elapsed();
+    info!(
+        "Replayed {} commands in {:?}, {} errors",
+        stats.commands, stats.elapsed, stats.errors
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Backend, BulkString, RespArray, Server};
+
+    fn request(args: &[&str]) -> RespFrame {
+        let args = args.iter().map(|a| BulkString::new(*a).into()).collect();
+        RespArray::new(args).into()
+    }
+
+    #[test]
+    fn test_record_and_read() -> Result<()> {
+        let path = std::env::temp_dir().join("simple-redis-test-record-and-read.rec");
+        let recorder = Recorder::create(&path)?;
+        recorder.record(&request(&["set", "key", "a b"]))?;
+        std::thread::sleep(Duration::from_millis(2));
+        recorder.record(&request(&["get", "key"]))?;
+
+        let frames = read(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].frame, request(&["set", "key", "a b"]));
+        assert_eq!(frames[1].frame, request(&["get", "key"]));
+        assert!(frames[1].offset >= frames[0].offset + Duration::from_millis(2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_traffic() -> Result<()> {
+        let path = std::env::temp_dir().join("simple-redis-test-replay.rec");
+        let recorder = Recorder::create(&path)?;
+        let source = Server::bind("127.0.0.1:0", Backend::new())
+            .await?
+            .with_recorder(recorder)
+            .spawn()?;
+        let stream = TcpStream::connect(source.addr()).await?;
+        let mut framed = Framed::new(stream, network::RespFrameCodec::default());
+        for args in [
+            &["set", "a", "1"][..],
+            &["hset", "h", "f", "v"],
+            &["get", "a"],
+        ] {
+            framed.send(request(args)).await?;
+            framed.next().await.expect("connection closed")?;
+        }
+
+        let target = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+        let stats = replay(&path, target.addr(), 0.0).await?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(stats.commands, 3);
+        assert_eq!(stats.errors, 0);
+        assert_eq!(target.backend().get("a").unwrap(), Some("1".into()));
+        assert_eq!(target.backend().hlen("h").unwrap(), 1);
+
+        Ok(())
+    }
+}
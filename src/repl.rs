@@ -0,0 +1,228 @@
+use crate::{
+    cmd::{Command, CommandExecutor},
+    Backend, BulkString, RespArray, RespFrame,
+};
+use anyhow::Result;
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+const PROMPT: &str = "simple-redis> ";
+
+/// Run an interactive console executing commands directly against the backend.
+///
+/// Lines are split like redis-cli does, replies are printed in its human readable format.
+/// Returns when the input is closed or on `quit`/`exit`.
+pub fn run(backend: Backend) -> Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+        if line.eq_ignore_ascii_case("quit") || line.eq_ignore_ascii_case("exit") {
+            return Ok(());
+        }
+
+        match split_args(line) {
+            Some(args) => println!("{}", format_reply(&execute(&backend, args))),
+            None => println!("Invalid argument(s)"),
+        }
+    }
+}
+
+/// Execute one command given as its arguments, errors are returned as error replies.
+pub fn execute(backend: &Backend, args: Vec<Vec<u8>>) -> RespFrame {
+    let args = args
+        .into_iter()
+        .map(|a| BulkString::new(a).into())
+        .collect();
+    match Command::try_from(RespArray::new(args)) {
+        Ok(cmd) => {
+            if let Some(delay) = cmd.delay() {
+                std::thread::sleep(delay);
+            }
+            cmd.execute(backend)
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Split a command line into arguments, honoring double and single quotes.
+///
+/// Double quoted arguments support the `\n`, `\r`, `\t`, `\xHH` escapes, returns None if a
+/// quote is not closed or not followed by a space.
+pub fn split_args(line: &str) -> Option<Vec<Vec<u8>>> {
+    let mut args = Vec::new();
+    let mut chars = line.bytes().peekable();
+    loop {
+        while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+        let Some(first) = chars.next() else {
+            return Some(args);
+        };
+
+        let mut arg = Vec::new();
+        match first {
+            b'"' => loop {
+                match chars.next()? {
+                    b'"' => break,
+                    b'\\' => match chars.next()? {
+                        b'n' => arg.push(b'\n'),
+                        b'r' => arg.push(b'\r'),
+                        b't' => arg.push(b'\t'),
+                        b'x' => {
+                            let hex = [chars.next()?, chars.next()?];
+                            let hex = std::str::from_utf8(&hex).ok()?;
+                            arg.push(u8::from_str_radix(hex, 16).ok()?);
+                        }
+                        c => arg.push(c),
+                    },
+                    c => arg.push(c),
+                }
+            },
+            b'\'' => loop {
+                match chars.next()? {
+                    b'\'' => break,
+                    b'\\' if chars.peek() == Some(&b'\'') => arg.push(chars.next()?),
+                    c => arg.push(c),
+                }
+            },
+            c => {
+                arg.push(c);
+                while let Some(c) = chars.next_if(|c| !c.is_ascii_whitespace()) {
+                    arg.push(c);
+                }
+            }
+        }
+        // a closing quote must end the argument
+        if matches!(first, b'"' | b'\'') && chars.peek().is_some_and(|c| !c.is_ascii_whitespace()) {
+            return None;
+        }
+        args.push(arg);
+    }
+}
+
+/// Format a reply the way redis-cli prints it.
+pub fn format_reply(frame: &RespFrame) -> String {
+    match frame {
+        RespFrame::SimpleString(s) => s.0.clone(),
+        RespFrame::Error(e) => format!("(error) {}", e.0),
+        RespFrame::Integer(n) => format!("(integer) {}", n),
+        RespFrame::BulkString(BulkString(Some(s))) => quote(s),
+        RespFrame::BulkString(BulkString(None)) | RespFrame::Null(_) => "(nil)".to_string(),
+        RespFrame::Boolean(b) => format!("({})", b),
+        RespFrame::Double(d) => format!("(double) {}", d),
+        RespFrame::Array(RespArray(None)) => "(nil)".to_string(),
+        RespFrame::Array(RespArray(Some(items))) => format_items(items.iter().map(format_reply)),
+        RespFrame::Set(items) => format_items(items.iter().map(format_reply)),
+        RespFrame::Map(map) => format_items(
+            map.iter()
+                .map(|(k, v)| format!("{} => {}", quote(k.as_bytes()), format_reply(v))),
+        ),
+    }
+}
+
+// numbered lines, nested replies are indented under their number
+fn format_items(items: impl ExactSizeIterator<Item = String>) -> String {
+    if items.len() == 0 {
+        return "(empty array)".to_string();
+    }
+    let width = items.len().to_string().len();
+    let mut lines = Vec::new();
+    for (i, item) in items.enumerate() {
+        let prefix = format!("{:>width$}) ", i + 1);
+        for (j, line) in item.lines().enumerate() {
+            if j == 0 {
+                lines.push(format!("{}{}", prefix, line));
+            } else {
+                lines.push(format!("{:indent$}{}", "", line, indent = prefix.len()));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn quote(s: &[u8]) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for &c in s {
+        match c {
+            b'"' => ret.push_str("\\\""),
+            b'\\' => ret.push_str("\\\\"),
+            b'\n' => ret.push_str("\\n"),
+            b'\r' => ret.push_str("\\r"),
+            b'\t' => ret.push_str("\\t"),
+            c if c.is_ascii_graphic() || c == b' ' => ret.push(c as char),
+            c => ret.push_str(&format!("\\x{:02x}", c)),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RespNull, SimpleString};
+
+    #[test]
+    fn test_split_args() {
+        let args = |args: &[&str]| Some(args.iter().map(|a| a.as_bytes().to_vec()).collect());
+        assert_eq!(
+            split_args("  set key  value "),
+            args(&["set", "key", "value"])
+        );
+        assert_eq!(
+            split_args(r#"set "hello world" 'it\'s'"#),
+            args(&["set", "hello world", "it's"])
+        );
+        assert_eq!(split_args(r#"echo "a\nb\x41""#), args(&["echo", "a\nbA"]));
+        assert_eq!(split_args(""), args(&[]));
+        assert_eq!(split_args(r#"set "key value"#), None);
+        assert_eq!(split_args(r#"set "key"value"#), None);
+    }
+
+    #[test]
+    fn test_format_reply() {
+        assert_eq!(format_reply(&SimpleString::new("OK").into()), "OK");
+        assert_eq!(format_reply(&1.into()), "(integer) 1");
+        assert_eq!(format_reply(&RespNull.into()), "(nil)");
+        assert_eq!(format_reply(&BulkString::new("a\"b").into()), r#""a\"b""#);
+        assert_eq!(
+            format_reply(&RespArray::new(vec![]).into()),
+            "(empty array)"
+        );
+
+        let nested = RespArray::new(vec![
+            BulkString::new("a").into(),
+            RespArray::new(vec![1.into(), 2.into()]).into(),
+        ]);
+        assert_eq!(
+            format_reply(&nested.into()),
+            "1) \"a\"\n2) 1) (integer) 1\n   2) (integer) 2"
+        );
+    }
+
+    #[test]
+    fn test_execute() {
+        let backend = Backend::new();
+        let args = |line: &str| split_args(line).unwrap();
+        assert_eq!(
+            execute(&backend, args("set key value")),
+            SimpleString::new("OK").into()
+        );
+        assert_eq!(
+            execute(&backend, args("get key")),
+            BulkString::new("value").into()
+        );
+        assert!(matches!(
+            execute(&backend, args("get")),
+            RespFrame::Error(_)
+        ));
+    }
+}
@@ -1,7 +1,4 @@
-use crate::{
-    extract_fixed_data, extract_simple_frame_data, parse_length, RespDecode, RespEncode, RespError,
-    CRLF_LEN,
-};
+use crate::{extract_fixed_data, parse_length, RespDecode, RespEncode, RespError, CRLF_LEN};
 use bytes::{Buf, BytesMut};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,25 +25,30 @@ impl RespDecode for BulkString {
             return Ok(BulkString(None));
         }
         let prefix = "$";
-        let end = extract_simple_frame_data(buf, prefix, 2)?;
-        let mut data = buf.split_to(end + CRLF_LEN);
-
-        let (end, len) = parse_length(&data, prefix)?;
+        let (end, len) = parse_length(buf, prefix)?;
         if len < 0 {
             return Err(RespError::InvalidFrame(
                 "Invalid bulk string length".to_string(),
             ));
         }
 
+        // the data is length-prefixed and may itself contain CRLF
         let len = len as usize;
-
-        data.advance(end + CRLF_LEN);
-
-        if data.len() != len + 2 {
+        let start = end + CRLF_LEN;
+        if buf.len() < start + len + CRLF_LEN {
             return Err(RespError::NotComplete);
         }
+        if &buf[start + len..start + len + CRLF_LEN] != b"\r\n" {
+            return Err(RespError::InvalidFrame(
+                "Bulk string is not terminated by CRLF".to_string(),
+            ));
+        }
+
+        buf.advance(start);
+        let data = buf.split_to(len);
+        buf.advance(CRLF_LEN);
 
-        Ok(BulkString::new(data[0..len].to_vec()))
+        Ok(BulkString::new(data.to_vec()))
     }
 }
 
@@ -88,6 +90,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_bulk_string_decode_binary() -> Result<()> {
+        let mut buf = BytesMut::from("$4\r\na\r\nb\r\n+OK\r\n");
+        let frame = BulkString::decode(&mut buf)?;
+        assert_eq!(frame, BulkString::new(b"a\r\nb".to_vec()));
+        assert_eq!(&buf[..], b"+OK\r\n");
+
+        let mut buf = BytesMut::from("$2\r\nabc\r\n");
+        assert!(matches!(
+            BulkString::decode(&mut buf),
+            Err(RespError::InvalidFrame(_))
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_null_bulk_string_encode() {
         let frame: RespFrame = BulkString::new_null().into();
@@ -0,0 +1,123 @@
+use crate::{network, Backend, BulkString, RespArray, RespFrame, RespNull, Server, SimpleString};
+use anyhow::{anyhow, bail, Result};
+use futures::SinkExt;
+use network::RespFrameCodec;
+use tokio::net::TcpStream;
+use tokio_stream::StreamExt;
+use tokio_util::codec::Framed;
+use tracing::info;
+
+/// Run a functional smoke test against an in-memory server listening on a random local port.
+///
+/// Returns an error describing the first command whose response is not the expected one.
+pub async fn run() -> Result<()> {
+    let server = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+    let addr = server.addr();
+    info!("Selftest server listening on {}", addr);
+
+    let stream = TcpStream::connect(addr).await?;
+    let mut framed = Framed::new(stream, RespFrameCodec::default());
+
+    let result = async {
+        for (args, expected) in battery() {
+            let response = call(&mut framed, request(&args)).await?;
+            if response != expected {
+                bail!(
+                    "{:?}: expected {:?}, got {:?}",
+                    args.join(" "),
+                    expected,
+                    response
+                );
+            }
+            info!("Selftest passed: {}", args.join(" "));
+        }
+        dump_and_restore(&mut framed).await
+    }
+    .await;
+
+    drop(server);
+    result?;
+    info!("Selftest completed successfully");
+    Ok(())
+}
+
+// send a request and wait for its response
+async fn call(
+    framed: &mut Framed<TcpStream, RespFrameCodec>,
+    request: RespFrame,
+) -> Result<RespFrame> {
+    framed.send(request.clone()).await?;
+    framed
+        .next()
+        .await
+        .ok_or_else(|| anyhow!("connection closed while running {:?}", request))?
+}
+
+// round trip a key of the battery through DUMP and RESTORE, the copy must read back the same
+async fn dump_and_restore(framed: &mut Framed<TcpStream, RespFrameCodec>) -> Result<()> {
+    let payload = match call(framed, request(&["dump", "hash"])).await? {
+        RespFrame::BulkString(BulkString(Some(payload))) => payload,
+        response => bail!("dump hash: expected a payload, got {:?}", response),
+    };
+    let restore = RespArray::new(vec![
+        BulkString::new("restore").into(),
+        BulkString::new("copy").into(),
+        BulkString::new("0").into(),
+        BulkString::new(payload).into(),
+    ]);
+    let response = call(framed, restore.into()).await?;
+    if response != SimpleString::new("OK").into() {
+        bail!("restore copy: expected OK, got {:?}", response);
+    }
+    let response = call(framed, request(&["hget", "copy", "field"])).await?;
+    if response != BulkString::new("value").into() {
+        bail!(
+            "hget copy field: expected the restored value, got {:?}",
+            response
+        );
+    }
+    info!("Selftest passed: dump and restore");
+    Ok(())
+}
+
+fn request(args: &[&str]) -> RespFrame {
+    let args = args.iter().map(|a| BulkString::new(*a).into()).collect();
+    RespArray::new(args).into()
+}
+
+fn battery() -> Vec<(Vec<&'static str>, RespFrame)> {
+    let ok: RespFrame = SimpleString::new("OK").into();
+    let bulk = |s: &str| -> RespFrame { BulkString::new(s).into() };
+
+    vec![
+        (vec!["echo", "hello"], bulk("hello")),
+        (vec!["set", "key", "value"], ok.clone()),
+        (vec!["get", "key"], bulk("value")),
+        (vec!["get", "missing"], RespNull.into()),
+        (vec!["set", "key", "value", "ex", "100"], ok.clone()),
+        (vec!["getex", "key", "persist"], bulk("value")),
+        (vec!["hset", "hash", "field", "value"], ok.clone()),
+        (vec!["hget", "hash", "field"], bulk("value")),
+        (
+            vec!["hmget", "hash", "field", "missing"],
+            RespArray::new(vec![bulk("value"), RespNull.into()]).into(),
+        ),
+        (vec!["sadd", "set", "a", "b"], 2.into()),
+        (vec!["sismember", "set", "a"], 1.into()),
+        (vec!["sismember", "set", "c"], 0.into()),
+        (
+            vec!["keys", "*"],
+            RespArray::new(vec![bulk("hash"), bulk("key"), bulk("set")]).into(),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_selftest_run() -> Result<()> {
+        run().await
+    }
+}
@@ -0,0 +1,546 @@
+use crate::{
+    client::ClientRegistry,
+    middleware::{Middleware, MiddlewareChain},
+    module::ModuleRegistry,
+    network,
+    record::Recorder,
+    Backend,
+};
+use anyhow::{bail, Result};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    task::{JoinHandle, JoinSet},
+};
+use tracing::{info, warn};
+
+// interval of the timer measuring how late the event loop runs tasks
+const LAG_PROBE_INTERVAL: Duration = Duration::from_millis(100);
+// how long a shutdown waits for the connections to send their pending replies and close
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+/// A redis server bound to a listening socket and serving a backend.
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    backend: Backend,
+    modules: Arc<RwLock<ModuleRegistry>>,
+    recorder: Option<Arc<Recorder>>,
+    middleware: MiddlewareChain,
+    clients: Arc<ClientRegistry>,
+}
+
+/// Handle of a server running in a background task, the server is stopped when dropped.
+#[derive(Debug)]
+pub struct ServerHandle {
+    addr: SocketAddr,
+    backend: Backend,
+    task: JoinHandle<()>,
+}
+
+/// Named servers running in the same process, e.g. to test several instances against each other.
+#[derive(Debug, Default)]
+pub struct ServerRegistry {
+    servers: Mutex<HashMap<String, ServerHandle>>,
+}
+
+impl Server {
+    pub async fn bind(addr: impl ToSocketAddrs, backend: Backend) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(Self {
+            listener,
+            backend,
+            modules: Arc::new(RwLock::new(ModuleRegistry::new())),
+            recorder: None,
+            middleware: MiddlewareChain::default(),
+            clients: Arc::new(ClientRegistry::new()),
+        })
+    }
+
+    /// Serve the commands of the loaded modules besides the built-in ones.
+    pub fn with_modules(mut self, modules: ModuleRegistry) -> Self {
+        self.modules = Arc::new(RwLock::new(modules));
+        self
+    }
+
+    /// Record the command frames received from all clients, see [`Recorder`].
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(Arc::new(recorder));
+        self
+    }
+
+    /// Run the hooks of a middleware around every command, after those added before it.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    pub fn backend(&self) -> &Backend {
+        &self.backend
+    }
+
+    /// Accept and serve connections until accepting fails or a shutdown is requested, see
+    /// [`Backend::shutdown`].
+    ///
+    /// Connections are owned by the accept loop, they are aborted along with it. On shutdown
+    /// the connections close once their pending replies are sent, the reply to SHUTDOWN
+    /// included, and the ones still open after a second are aborted.
+    pub async fn run(self) -> Result<()> {
+        let middleware = Arc::new(self.middleware);
+        let mut connections = JoinSet::new();
+        let probe = connections.spawn(probe_event_loop_lag(self.backend.clone()));
+        let shutdown = self.backend.shutdown_requested();
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    info!("Shutdown requested, closing {} connections", self.clients.len());
+                    probe.abort();
+                    let closed = async { while connections.join_next().await.is_some() {} };
+                    if tokio::time::timeout(SHUTDOWN_GRACE, closed).await.is_err() {
+                        warn!("Aborting {} connections after the shutdown grace", connections.len());
+                    }
+                    return Ok(());
+                }
+                accepted = self.listener.accept() => {
+                    let (socket, raddr) = accepted?;
+                    info!("Accepted connection from {}", raddr);
+
+                    let backend = self.backend.clone();
+                    let modules = self.modules.clone();
+                    let recorder = self.recorder.clone();
+                    let middleware = middleware.clone();
+                    let clients = self.clients.clone();
+                    connections.spawn(async move {
+                        let handled = network::stream_handler(
+                            socket, backend, modules, recorder, middleware, clients,
+                        );
+                        match handled.await {
+                            Ok(_) => info!("Connection closed"),
+                            Err(e) => warn!("Stream handle error: {:?}", e),
+                        }
+                    });
+                }
+                // reap finished connections
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            }
+        }
+    }
+
+    /// Run the server in a background task.
+    pub fn spawn(self) -> Result<ServerHandle> {
+        let addr = self.local_addr()?;
+        let backend = self.backend.clone();
+        let task = tokio::spawn(async move {
+            if let Err(e) = self.run().await {
+                warn!("Server {} stopped: {:?}", addr, e);
+            }
+        });
+        Ok(ServerHandle {
+            addr,
+            backend,
+            task,
+        })
+    }
+}
+
+// a timer firing late means the workers are busy, e.g. with a slow command
+async fn probe_event_loop_lag(backend: Backend) {
+    loop {
+        let start = Instant::now();
+        tokio::time::sleep(LAG_PROBE_INTERVAL).await;
+        let lag = start.elapsed().saturating_sub(LAG_PROBE_INTERVAL);
+        backend.stats().set_event_loop_lag(lag);
+    }
+}
+
+impl ServerHandle {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn backend(&self) -> &Backend {
+        &self.backend
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl ServerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Launch a server with a fresh backend under the given name, returns its bound address.
+    pub async fn launch(
+        &self,
+        name: impl Into<String>,
+        addr: impl ToSocketAddrs,
+    ) -> Result<SocketAddr> {
+        let name = name.into();
+        if self.servers.lock().unwrap().contains_key(&name) {
+            bail!("Server {} is already running", name);
+        }
+
+        let handle = Server::bind(addr, Backend::new()).await?.spawn()?;
+        let addr = handle.addr();
+        info!("Launched server {} on {}", name, addr);
+
+        let mut servers = self.servers.lock().unwrap();
+        if servers.contains_key(&name) {
+            bail!("Server {} is already running", name);
+        }
+        servers.insert(name, handle);
+        Ok(addr)
+    }
+
+    pub fn addr(&self, name: &str) -> Option<SocketAddr> {
+        self.servers.lock().unwrap().get(name).map(|s| s.addr())
+    }
+
+    pub fn backend(&self, name: &str) -> Option<Backend> {
+        self.servers
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|s| s.backend().clone())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.servers.lock().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Stop the named server, returns false if there is no such server.
+    pub fn shutdown(&self, name: &str) -> bool {
+        self.servers.lock().unwrap().remove(name).is_some()
+    }
+
+    pub fn shutdown_all(&self) {
+        self.servers.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cmd::Command, middleware::ConnectionContext, network::RespFrameCodec, BulkString,
+        RespArray, RespFrame, SimpleError, SimpleString,
+    };
+    use futures::SinkExt;
+    use std::{ops::ControlFlow, sync::atomic::AtomicUsize, sync::atomic::Ordering};
+    use tokio::net::TcpStream;
+    use tokio_stream::StreamExt;
+    use tokio_util::codec::Framed;
+
+    async fn request(addr: SocketAddr, args: &[&str]) -> Result<RespFrame> {
+        let stream = TcpStream::connect(addr).await?;
+        let mut framed = Framed::new(stream, RespFrameCodec::default());
+        let args = args.iter().map(|a| BulkString::new(*a).into()).collect();
+        framed.send(RespArray::new(args).into()).await?;
+        framed.next().await.expect("connection closed")
+    }
+
+    // denies writes and counts the replies
+    #[derive(Default)]
+    struct ReadOnly {
+        replies: Arc<AtomicUsize>,
+    }
+
+    impl Middleware for ReadOnly {
+        fn before(&self, _ctx: &ConnectionContext, command: &Command) -> ControlFlow<RespFrame> {
+            match command {
+                Command::Set(_) => ControlFlow::Break(SimpleError::new("ERR read only").into()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+
+        fn after(&self, _ctx: &ConnectionContext, _response: &mut RespFrame) {
+            self.replies.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_hooks() -> Result<()> {
+        let middleware = ReadOnly::default();
+        let replies = middleware.replies.clone();
+        let server = Server::bind("127.0.0.1:0", Backend::new())
+            .await?
+            .with_middleware(middleware)
+            .spawn()?;
+
+        let ret = request(server.addr(), &["set", "key", "value"]).await?;
+        assert_eq!(ret, SimpleError::new("ERR read only").into());
+        assert_eq!(server.backend().get("key").unwrap(), None);
+
+        let ret = request(server.addr(), &["get", "key"]).await?;
+        assert_eq!(ret, RespFrame::Null(crate::RespNull));
+        assert_eq!(replies.load(Ordering::Relaxed), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_debug_sleep_blocks_other_clients() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+
+        let addr = server.addr();
+        let sleep = tokio::spawn(async move { request(addr, &["debug", "sleep", "0.2"]).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let start = Instant::now();
+        let ret = request(server.addr(), &["get", "key"]).await?;
+        assert_eq!(ret, RespFrame::Null(crate::RespNull));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(sleep.await??, SimpleString::new("OK").into());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_maxmemory_refuses_writes() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+        request(server.addr(), &["set", "key", "value"]).await?;
+
+        request(server.addr(), &["config", "set", "maxmemory", "1"]).await?;
+        let ret = request(server.addr(), &["set", "other", "value"]).await?;
+        assert_eq!(
+            ret,
+            SimpleError::new("OOM command not allowed when used memory > 'maxmemory'.").into()
+        );
+        // reads and deletions are still served
+        let ret = request(server.addr(), &["get", "key"]).await?;
+        assert_eq!(ret, BulkString::new("value").into());
+        let ret = request(server.addr(), &["unlink", "key"]).await?;
+        assert_eq!(ret, RespFrame::Integer(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sliding_ttl_extends_on_get() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+        let addr = server.addr();
+
+        let ret = request(addr, &["config", "set", "sliding-ttl", "session: 60"]).await?;
+        assert_eq!(ret, SimpleString::new("OK").into());
+        request(addr, &["set", "session:1", "v", "ex", "10"]).await?;
+        request(addr, &["set", "other", "v", "ex", "10"]).await?;
+
+        let ret = request(addr, &["get", "session:1"]).await?;
+        assert_eq!(ret, BulkString::new("v").into());
+        request(addr, &["get", "other"]).await?;
+        let RespFrame::Integer(ttl) = request(addr, &["ttl", "session:1"]).await? else {
+            panic!("unexpected TTL reply");
+        };
+        assert!((59..=60).contains(&ttl));
+        assert_eq!(
+            request(addr, &["ttl", "other"]).await?,
+            RespFrame::Integer(10)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_info() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+
+        let ret = request(server.addr(), &["client", "info"]).await?;
+        let RespFrame::BulkString(BulkString(Some(info))) = ret else {
+            panic!("unexpected reply: {:?}", ret);
+        };
+        let info = String::from_utf8(info)?;
+        assert!(info.starts_with("id="));
+        assert!(info.ends_with(" db=0 cmd=client\n"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+        let stream = TcpStream::connect(server.addr()).await?;
+        let mut framed = Framed::new(stream, RespFrameCodec::default());
+        for args in [
+            &["multi"][..],
+            &["set", "key", "value"],
+            &["client", "id"],
+            &["exec"],
+        ] {
+            let args = args.iter().map(|a| BulkString::new(*a).into()).collect();
+            framed.send(RespArray::new(args).into()).await?;
+        }
+
+        let mut replies = Vec::new();
+        for _ in 0..4 {
+            replies.push(framed.next().await.expect("connection closed")?);
+        }
+        assert_eq!(replies[1], SimpleString::new("QUEUED").into());
+        assert_eq!(
+            replies[2],
+            SimpleError::new("ERR Command not allowed inside a transaction").into()
+        );
+        assert_eq!(
+            replies[3],
+            SimpleError::new("EXECABORT Transaction discarded because of previous errors.").into()
+        );
+        assert_eq!(server.backend().get("key").unwrap(), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_errors_keep_the_connection() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+        let stream = TcpStream::connect(server.addr()).await?;
+        let mut framed = Framed::new(stream, RespFrameCodec::default());
+        for args in [&["get"][..], &["set", "key", "value"], &["get", "key"]] {
+            let args = args.iter().map(|a| BulkString::new(*a).into()).collect();
+            framed.send(RespArray::new(args).into()).await?;
+        }
+
+        let mut replies = Vec::new();
+        for _ in 0..3 {
+            replies.push(framed.next().await.expect("connection closed")?);
+        }
+        assert!(matches!(&replies[0], RespFrame::Error(e) if e.starts_with("ERR ")));
+        assert_eq!(replies[1], SimpleString::new("OK").into());
+        assert_eq!(replies[2], BulkString::new("value").into());
+
+        // corrupted input closes the connection after an error reply
+        let stream = framed.into_inner();
+        stream.writable().await?;
+        stream.try_write(b"!corrupted\r\n")?;
+        let mut framed = Framed::new(stream, RespFrameCodec::default());
+        let ret = framed.next().await.expect("connection closed")?;
+        assert!(matches!(&ret, RespFrame::Error(e) if e.starts_with("ERR Protocol error")));
+        assert!(framed.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sharded_pubsub() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?.spawn()?;
+        let stream = TcpStream::connect(server.addr()).await?;
+        let mut subscriber = Framed::new(stream, RespFrameCodec::default());
+        let args = vec![
+            BulkString::new("ssubscribe").into(),
+            BulkString::new("news").into(),
+        ];
+        subscriber.send(RespArray::new(args).into()).await?;
+        subscriber.next().await.expect("connection closed")?;
+
+        let ret = request(server.addr(), &["spublish", "news", "hello"]).await?;
+        assert_eq!(ret, RespFrame::Integer(1));
+        let message = RespArray::new(vec![
+            BulkString::new("smessage").into(),
+            BulkString::new("news").into(),
+            BulkString::new("hello").into(),
+        ]);
+        let ret = subscriber.next().await.expect("connection closed")?;
+        assert_eq!(ret, message.into());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_closed_connections_leave_nothing_behind() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?;
+        let clients = server.clients.clone();
+        let server = server.spawn()?;
+
+        let command = |args: &[&str]| -> RespFrame {
+            RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect()).into()
+        };
+        let mut subscriber = Framed::new(
+            TcpStream::connect(server.addr()).await?,
+            RespFrameCodec::default(),
+        );
+        subscriber.send(command(&["ssubscribe", "news"])).await?;
+        subscriber.next().await.expect("connection closed")?;
+        let mut transaction = Framed::new(
+            TcpStream::connect(server.addr()).await?,
+            RespFrameCodec::default(),
+        );
+        transaction.send(command(&["multi"])).await?;
+        transaction.next().await.expect("connection closed")?;
+        transaction.send(command(&["set", "key", "value"])).await?;
+        transaction.next().await.expect("connection closed")?;
+        assert_eq!(clients.len(), 2);
+
+        drop(subscriber);
+        drop(transaction);
+        let start = Instant::now();
+        while !clients.is_empty() && start.elapsed() < Duration::from_secs(1) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(clients.is_empty());
+        assert_eq!(server.backend().spublish("news", b"hello"), 0);
+        // the queued command is discarded along with its connection
+        assert_eq!(server.backend().get("key").unwrap(), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_the_server() -> Result<()> {
+        let server = Server::bind("127.0.0.1:0", Backend::new()).await?;
+        let addr = server.local_addr()?;
+        let running = tokio::spawn(server.run());
+
+        // an idle connection does not hold the shutdown back
+        let _idle = TcpStream::connect(addr).await?;
+        let start = Instant::now();
+        let ret = request(addr, &["shutdown", "nosave"]).await?;
+        assert_eq!(ret, SimpleString::new("OK").into());
+        running.await??;
+        assert!(start.elapsed() < SHUTDOWN_GRACE);
+        assert!(TcpStream::connect(addr).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_registry_instances_are_isolated() -> Result<()> {
+        let registry = ServerRegistry::new();
+        let a = registry.launch("a", "127.0.0.1:0").await?;
+        let b = registry.launch("b", "127.0.0.1:0").await?;
+        assert_ne!(a, b);
+        assert_eq!(registry.names(), vec!["a", "b"]);
+        assert!(registry.launch("a", "127.0.0.1:0").await.is_err());
+
+        let ret = request(a, &["set", "key", "value"]).await?;
+        assert_eq!(ret, SimpleString::new("OK").into());
+
+        let backend_a = registry.backend("a").unwrap();
+        let backend_b = registry.backend("b").unwrap();
+        assert_eq!(backend_a.get("key").unwrap(), Some("value".into()));
+        assert_eq!(backend_b.get("key").unwrap(), None);
+
+        assert!(registry.shutdown("a"));
+        assert!(!registry.shutdown("a"));
+        assert_eq!(registry.addr("a"), None);
+        assert_eq!(registry.addr("b"), Some(b));
+
+        registry.shutdown_all();
+        assert!(registry.names().is_empty());
+
+        Ok(())
+    }
+}
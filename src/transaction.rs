@@ -0,0 +1,233 @@
+use crate::{
+    cmd::{Command, CommandError, CommandExecutor},
+    module::CommandHandler,
+    Backend, BulkString, RespArray, RespFrame, SimpleError, SimpleString,
+};
+use tracing::info;
+
+/// A command validated after MULTI, run by EXEC.
+pub(crate) enum Queued {
+    Command(Command),
+    Module(CommandHandler, RespArray),
+}
+
+/// The transaction state of a connection, the commands queued since MULTI if any.
+#[derive(Default)]
+pub(crate) struct Transaction {
+    queue: Option<Vec<Queued>>,
+    // a command failed to queue, EXEC discards the transaction
+    aborted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TransactionCommand {
+    Multi,
+    Exec,
+    Discard,
+}
+
+impl TransactionCommand {
+    fn name(&self) -> &'static str {
+        match self {
+            TransactionCommand::Multi => "multi",
+            TransactionCommand::Exec => "exec",
+            TransactionCommand::Discard => "discard",
+        }
+    }
+}
+
+impl Transaction {
+    /// Whether commands are queued rather than run.
+    pub(crate) fn is_active(&self) -> bool {
+        self.queue.is_some()
+    }
+
+    pub(crate) fn queue(&mut self, queued: Queued) -> RespFrame {
+        if let Some(queue) = self.queue.as_mut() {
+            queue.push(queued);
+        }
+        SimpleString::new("QUEUED").into()
+    }
+
+    /// Record that a command could not be queued, EXEC will then run none of them.
+    pub(crate) fn abort(&mut self) {
+        if self.is_active() {
+            self.aborted = true;
+        }
+    }
+
+    /// Handle MULTI, EXEC or DISCARD, the commands of a transaction run at once on `backend`.
+    pub(crate) async fn command(
+        &mut self,
+        backend: &Backend,
+        args: RespArray,
+    ) -> Result<RespFrame, CommandError> {
+        let command = parse(&args).ok_or_else(|| {
+            CommandError::InvalidCommand("Invalid command: expected a transaction".to_string())
+        })?;
+        if args.0.as_ref().map(Vec::len) != Some(1) {
+            self.abort();
+            return Err(CommandError::InvalidArgument(format!(
+                "wrong number of arguments for '{}' command",
+                command.name()
+            )));
+        }
+
+        let ret = match (command, self.queue.take()) {
+            (TransactionCommand::Multi, None) => {
+                self.queue = Some(Vec::new());
+                SimpleString::new("OK").into()
+            }
+            (TransactionCommand::Multi, queue) => {
+                self.queue = queue;
+                SimpleError::new("ERR MULTI calls can not be nested").into()
+            }
+            (TransactionCommand::Exec, None) => SimpleError::new("ERR EXEC without MULTI").into(),
+            (TransactionCommand::Discard, None) => {
+                SimpleError::new("ERR DISCARD without MULTI").into()
+            }
+            (TransactionCommand::Exec, Some(_)) if std::mem::take(&mut self.aborted) => {
+                SimpleError::new("EXECABORT Transaction discarded because of previous errors.")
+                    .into()
+            }
+            (TransactionCommand::Exec, Some(queue)) => {
+                info!("Executing transaction of {} commands", queue.len());
+                // DEBUG SLEEP is waited once the transaction holds the lock
+                let delay = queue
+                    .iter()
+                    .filter_map(|queued| match queued {
+                        Queued::Command(cmd) => cmd.delay(),
+                        Queued::Module(..) => None,
+                    })
+                    .sum();
+                let replies = backend
+                    .exclusive_after(delay, || {
+                        queue
+                            .into_iter()
+                            .map(|queued| match queued {
+                                Queued::Command(cmd) => cmd.execute(backend),
+                                Queued::Module(handler, args) => {
+                                    handler(backend, args).unwrap_or_else(RespFrame::from)
+                                }
+                            })
+                            .collect()
+                    })
+                    .await;
+                RespArray::new(replies).into()
+            }
+            (TransactionCommand::Discard, Some(_)) => {
+                self.aborted = false;
+                SimpleString::new("OK").into()
+            }
+        };
+        Ok(ret)
+    }
+}
+
+pub(crate) fn is_transaction_command(args: &RespArray) -> bool {
+    parse(args).is_some()
+}
+
+fn parse(args: &RespArray) -> Option<TransactionCommand> {
+    match args.0.as_ref()?.first()? {
+        RespFrame::BulkString(BulkString(Some(name))) => {
+            match name.to_ascii_lowercase().as_slice() {
+                b"multi" => Some(TransactionCommand::Multi),
+                b"exec" => Some(TransactionCommand::Exec),
+                b"discard" => Some(TransactionCommand::Discard),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn args(args: &[&str]) -> RespArray {
+        RespArray::new(args.iter().map(|a| BulkString::new(*a).into()).collect())
+    }
+
+    fn queue(transaction: &mut Transaction, command: &[&str]) -> Result<RespFrame> {
+        let cmd = Command::try_from(args(command))?;
+        Ok(transaction.queue(Queued::Command(cmd)))
+    }
+
+    #[tokio::test]
+    async fn test_transaction_exec() -> Result<()> {
+        let backend = Backend::new();
+        let mut transaction = Transaction::default();
+
+        let ret = transaction.command(&backend, args(&["MULTI"])).await?;
+        assert_eq!(ret, SimpleString::new("OK").into());
+        assert!(transaction.is_active());
+        let ret = transaction.command(&backend, args(&["multi"])).await?;
+        assert_eq!(
+            ret,
+            SimpleError::new("ERR MULTI calls can not be nested").into()
+        );
+
+        let ret = queue(&mut transaction, &["set", "key", "value"])?;
+        assert_eq!(ret, SimpleString::new("QUEUED").into());
+        queue(&mut transaction, &["get", "key"])?;
+        assert_eq!(backend.get("key").unwrap(), None);
+
+        let ret = transaction.command(&backend, args(&["exec"])).await?;
+        let expected = RespArray::new(vec![
+            SimpleString::new("OK").into(),
+            BulkString::new("value").into(),
+        ]);
+        assert_eq!(ret, expected.into());
+        assert!(!transaction.is_active());
+
+        let ret = transaction.command(&backend, args(&["exec"])).await?;
+        assert_eq!(ret, SimpleError::new("ERR EXEC without MULTI").into());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_discard_and_abort() -> Result<()> {
+        let backend = Backend::new();
+        let mut transaction = Transaction::default();
+
+        let ret = transaction.command(&backend, args(&["discard"])).await?;
+        assert_eq!(ret, SimpleError::new("ERR DISCARD without MULTI").into());
+
+        transaction.command(&backend, args(&["multi"])).await?;
+        queue(&mut transaction, &["set", "key", "value"])?;
+        let ret = transaction.command(&backend, args(&["discard"])).await?;
+        assert_eq!(ret, SimpleString::new("OK").into());
+        assert!(!transaction.is_active());
+        assert_eq!(backend.get("key").unwrap(), None);
+
+        // a command that fails to queue discards the whole transaction
+        transaction.command(&backend, args(&["multi"])).await?;
+        queue(&mut transaction, &["set", "key", "value"])?;
+        transaction.abort();
+        let ret = transaction.command(&backend, args(&["exec"])).await?;
+        assert_eq!(
+            ret,
+            SimpleError::new("EXECABORT Transaction discarded because of previous errors.").into()
+        );
+        assert_eq!(backend.get("key").unwrap(), None);
+
+        // aborting outside of a transaction has no effect on the next one
+        transaction.abort();
+        transaction.command(&backend, args(&["multi"])).await?;
+        let ret = transaction.command(&backend, args(&["exec"])).await?;
+        assert_eq!(ret, RespArray::new(vec![]).into());
+
+        assert!(transaction
+            .command(&backend, args(&["multi", "x"]))
+            .await
+            .is_err());
+        assert!(is_transaction_command(&args(&["Exec"])));
+        assert!(!is_transaction_command(&args(&["get", "key"])));
+
+        Ok(())
+    }
+}
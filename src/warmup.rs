@@ -0,0 +1,81 @@
+use crate::{repl, Backend, RespFrame, WarmupStats};
+use anyhow::Result;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    time::Instant,
+};
+use tracing::{info, warn};
+
+// commands executed between two progress messages
+const PROGRESS_INTERVAL: usize = 10_000;
+
+/// Execute the commands of a warmup file against a backend, before the server accepts clients.
+///
+/// The file holds one command per line, quoted as in the interactive console. Blank lines and
+/// lines starting with `#` are skipped. Commands failing or that cannot be parsed are logged and
+/// counted, they do not stop the warmup. The outcome is reported by INFO persistence.
+pub fn run(backend: &Backend, path: impl AsRef<Path>) -> Result<WarmupStats> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path)?);
+    info!("Warming up from {}", path.display());
+
+    let start = Instant::now();
+    let mut stats = WarmupStats::default();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        stats.commands += 1;
+        let failed = match repl::split_args(line) {
+            Some(args) => match repl::execute(backend, args) {
+                RespFrame::Error(e) => Some(e.0),
+                _ => None,
+            },
+            None => Some("Invalid argument(s)".to_string()),
+        };
+        if let Some(e) = failed {
+            stats.errors += 1;
+            warn!("Warmup line {}: {}", i + 1, e);
+        }
+
+        if stats.commands.is_multiple_of(PROGRESS_INTERVAL) {
+            info!("Warmup: {} commands executed", stats.commands);
+        }
+    }
+    stats.elapsed = start.elapsed();
+    info!(
+        "Warmup done: {} commands in {:?}, {} errors",
+        stats.commands, stats.elapsed, stats.errors
+    );
+    backend.stats().set_warmup(stats.clone());
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmup() -> Result<()> {
+        let path = std::env::temp_dir().join("simple-redis-test-warmup.txt");
+        std::fs::write(
+            &path,
+            "# preload\nset greeting \"hello world\"\n\nsadd set a b\nset missing-value\nset \"unclosed\n",
+        )?;
+
+        let backend = Backend::new();
+        let stats = run(&backend, &path)?;
+        assert_eq!(stats.commands, 4);
+        assert_eq!(stats.errors, 2);
+        assert_eq!(backend.get("greeting").unwrap(), Some("hello world".into()));
+        assert_eq!(backend.stats().warmup(), Some(stats));
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}